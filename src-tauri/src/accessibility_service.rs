@@ -0,0 +1,203 @@
+// accessibility_service.rs - System accessibility preferences (high contrast,
+// reduced motion, text size), so the theme can follow them
+//
+// The preferences are read again whenever the window gains focus: they are
+// changed in the system settings, so the user has just come back from there.
+// `accessibility-preferences-changed` is emitted when they differ.
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Runtime};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessibilityPreferences {
+    pub high_contrast: bool,
+    pub reduced_motion: bool,
+    // Factor of the system text size, 1.0 when it isn't enlarged
+    pub text_scale: f64,
+}
+
+impl Default for AccessibilityPreferences {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            reduced_motion: false,
+            text_scale: 1.0,
+        }
+    }
+}
+
+// Last preferences read, to only report changes
+static LAST_PREFERENCES: Lazy<Mutex<Option<AccessibilityPreferences>>> =
+    Lazy::new(|| Mutex::new(None));
+
+#[cfg(target_os = "windows")]
+async fn read_preferences() -> AccessibilityPreferences {
+    use winreg::HKCU;
+
+    let mut preferences = AccessibilityPreferences::default();
+
+    // HCF_HIGHCONTRASTON is bit 0 of the flags, stored as a decimal string
+    if let Ok(flags) = HKCU
+        .open_subkey("Control Panel\\Accessibility\\HighContrast")
+        .and_then(|key| key.get_value::<String, _>("Flags"))
+    {
+        preferences.high_contrast = flags
+            .trim()
+            .parse::<u32>()
+            .is_ok_and(|flags| flags & 1 != 0);
+    }
+
+    // "Animate controls and elements" in the accessibility settings
+    if let Ok(animate) = HKCU
+        .open_subkey("Control Panel\\Desktop\\WindowMetrics")
+        .and_then(|key| key.get_value::<String, _>("MinAnimate"))
+    {
+        preferences.reduced_motion = animate.trim() == "0";
+    }
+
+    // "Text size", in percent
+    if let Ok(percent) = HKCU
+        .open_subkey("Software\\Microsoft\\Accessibility")
+        .and_then(|key| key.get_value::<u32, _>("TextScaleFactor"))
+    {
+        preferences.text_scale = f64::from(percent) / 100.0;
+    }
+
+    preferences
+}
+
+#[cfg(target_os = "macos")]
+async fn read_preferences() -> AccessibilityPreferences {
+    async fn read_flag(key: &str) -> bool {
+        tokio::process::Command::new("defaults")
+            .args(["read", "com.apple.universalaccess", key])
+            .output()
+            .await
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1")
+    }
+
+    // macOS has no system-wide text size that apps are expected to follow
+    AccessibilityPreferences {
+        high_contrast: read_flag("increaseContrast").await,
+        reduced_motion: read_flag("reduceMotion").await,
+        text_scale: 1.0,
+    }
+}
+
+// Through the settings portal, which also works in Flatpak. The
+// freedesktop contrast key is desktop-neutral; the others are GNOME's.
+#[cfg(target_os = "linux")]
+async fn read_preferences() -> AccessibilityPreferences {
+    use log::debug;
+    use zbus::zvariant::OwnedValue;
+    use zbus::{Connection, Proxy};
+
+    async fn read_one(portal: &Proxy<'_>, namespace: &str, key: &str) -> Option<OwnedValue> {
+        portal
+            .call::<_, _, OwnedValue>("ReadOne", &(namespace, key))
+            .await
+            .map_err(|e| debug!("Failed to read {} {}: {}", namespace, key, e))
+            .ok()
+    }
+
+    let mut preferences = AccessibilityPreferences::default();
+
+    let connection = match Connection::session().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            debug!("No D-Bus session for accessibility preferences: {}", e);
+            return preferences;
+        }
+    };
+    let portal = match Proxy::new(
+        &connection,
+        "org.freedesktop.portal.Desktop",
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Settings",
+    )
+    .await
+    {
+        Ok(portal) => portal,
+        Err(e) => {
+            debug!("Failed to connect to the settings portal: {}", e);
+            return preferences;
+        }
+    };
+
+    let contrast = read_one(&portal, "org.freedesktop.appearance", "contrast")
+        .await
+        .and_then(|value| u32::try_from(value).ok());
+    preferences.high_contrast = match contrast {
+        Some(contrast) => contrast == 1,
+        None => read_one(&portal, "org.gnome.desktop.a11y.interface", "high-contrast")
+            .await
+            .and_then(|value| bool::try_from(value).ok())
+            .unwrap_or(false),
+    };
+
+    preferences.reduced_motion =
+        read_one(&portal, "org.gnome.desktop.interface", "enable-animations")
+            .await
+            .and_then(|value| bool::try_from(value).ok())
+            .is_some_and(|enabled| !enabled);
+
+    if let Some(scale) = read_one(
+        &portal,
+        "org.gnome.desktop.interface",
+        "text-scaling-factor",
+    )
+    .await
+    .and_then(|value| f64::try_from(value).ok())
+    .filter(|scale| *scale > 0.0)
+    {
+        preferences.text_scale = scale;
+    }
+
+    preferences
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+async fn read_preferences() -> AccessibilityPreferences {
+    AccessibilityPreferences::default()
+}
+
+// Read the preferences again and tell the frontend if they changed
+pub fn refresh_preferences<R: Runtime>(app_handle: &AppHandle<R>) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let preferences = read_preferences().await;
+
+        let changed = match LAST_PREFERENCES.lock() {
+            Ok(mut last) => {
+                let changed = last.as_ref().is_some_and(|last| *last != preferences);
+                *last = Some(preferences.clone());
+                changed
+            }
+            Err(_) => false,
+        };
+
+        if changed {
+            info!(
+                "System accessibility preferences changed: {:?}",
+                preferences
+            );
+            if let Err(e) = app_handle.emit("accessibility-preferences-changed", &preferences) {
+                warn!("Failed to emit accessibility preferences: {}", e);
+            }
+        }
+    });
+}
+
+// The system's accessibility preferences
+#[tauri::command]
+pub async fn get_accessibility_preferences() -> AccessibilityPreferences {
+    let _timing = crate::metrics_service::time_async_command("get_accessibility_preferences");
+    let preferences = read_preferences().await;
+    if let Ok(mut last) = LAST_PREFERENCES.lock() {
+        *last = Some(preferences.clone());
+    }
+    preferences
+}
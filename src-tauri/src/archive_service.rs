@@ -0,0 +1,239 @@
+// archive_service.rs - Daily note: one tab that rolls over into archive/YYYY-MM-DD.md
+use chrono::{Local, NaiveDate};
+use log::{error, info};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::storage_service;
+
+pub const ARCHIVE_DIR_NAME: &str = "archive";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const ROLLOVER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    pub date: String,
+    pub size: u64,
+}
+
+fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("settings.json")
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+pub fn get_archive_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    storage_service::get_current_storage_dir(app_handle).join(ARCHIVE_DIR_NAME)
+}
+
+fn get_daily_note_tab<R: Runtime>(app_handle: &AppHandle<R>) -> Option<usize> {
+    read_settings(app_handle)["daily_note_tab"]
+        .as_u64()
+        .map(|tab| tab as usize)
+}
+
+fn today() -> String {
+    Local::now().format(DATE_FORMAT).to_string()
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .map_err(|_| format!("Invalid archive date: {}", date))
+}
+
+// Append content to the archive file of a day
+fn append_to_archive<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    date: &str,
+    content: &str,
+) -> Result<(), String> {
+    let archive_dir = get_archive_dir(app_handle);
+    fs::create_dir_all(&archive_dir)
+        .map_err(|e| format!("Failed to create archive directory: {}", e))?;
+
+    let path = archive_dir.join(format!("{}.md", date));
+    let combined = if path.exists() {
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read archive: {}", e))?;
+        let existing = storage_service::decode_note_bytes(app_handle, &bytes)?;
+        format!("{}\n\n{}", existing.trim_end(), content)
+    } else {
+        content.to_string()
+    };
+
+    let bytes = storage_service::encode_note_content(app_handle, &combined)?;
+    storage_service::write_atomic(&path, bytes)
+        .map_err(|e| format!("Failed to write archive: {}", e))
+}
+
+fn is_rollover_due<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
+    let settings = read_settings(app_handle);
+    settings["daily_note_tab"].is_u64()
+        && settings["daily_note_date"].as_str() != Some(today().as_str())
+}
+
+// Archive the daily note if the day has changed since it was last rolled
+// over. Returns the date that was archived, if any.
+pub fn roll_over_if_due<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Option<String>, String> {
+    let Some(tab_index) = get_daily_note_tab(app_handle) else {
+        return Ok(None);
+    };
+
+    let settings = read_settings(app_handle);
+    let today = today();
+    let last_date = settings["daily_note_date"].as_str().map(str::to_string);
+
+    let Some(last_date) = last_date.filter(|date| *date != today) else {
+        if settings["daily_note_date"].is_null() {
+            // First run in archive mode: today's content belongs to today
+            crate::data_dir::update_settings(app_handle, |settings| {
+                if settings["daily_note_date"].is_null() {
+                    settings["daily_note_date"] = serde_json::json!(today);
+                }
+                Ok(())
+            })?;
+        }
+        return Ok(None);
+    };
+
+    let content = storage_service::read_note(app_handle, tab_index)?.unwrap_or_default();
+    let archived = if content.trim().is_empty() {
+        None
+    } else {
+        append_to_archive(app_handle, &last_date, &content)?;
+        storage_service::write_note_for_operation(app_handle, tab_index, "", "Archive daily note")?;
+        info!("Archived daily note of {}", last_date);
+
+        if let Err(e) = crate::note_events::emit_note_updated(app_handle, tab_index, "") {
+            error!("{}", e);
+        }
+        if let Err(e) = app_handle.emit(
+            "daily-note-archived",
+            serde_json::json!({ "tabIndex": tab_index, "date": last_date }),
+        ) {
+            error!("Failed to emit daily-note-archived event: {}", e);
+        }
+        Some(last_date)
+    };
+
+    crate::data_dir::update_settings(app_handle, |settings| {
+        settings["daily_note_date"] = serde_json::json!(today);
+        Ok(())
+    })?;
+
+    Ok(archived)
+}
+
+// Called before a note is written. On the first save of a new day the
+// previous content is archived; if the editor still shows it, only what was
+// typed after it is kept in the fresh note.
+pub fn prepare_save<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: String,
+) -> Result<String, String> {
+    if get_daily_note_tab(app_handle) != Some(tab_index) {
+        return Ok(content);
+    }
+
+    let previous = storage_service::read_note(app_handle, tab_index)?.unwrap_or_default();
+    if roll_over_if_due(app_handle)?.is_none() {
+        return Ok(content);
+    }
+
+    Ok(match content.strip_prefix(previous.as_str()) {
+        Some(rest) if !previous.is_empty() => rest.trim_start().to_string(),
+        _ => content,
+    })
+}
+
+// Check for the day change in the background, so the note rolls over at
+// midnight even when nothing is saved
+pub fn start_rollover_scheduler<R: Runtime>(app_handle: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if is_rollover_due(&app_handle) {
+                // Buffered edits go through prepare_save, which may already
+                // do the rollover
+                let result = crate::autosave_service::flush_all(&app_handle)
+                    .and_then(|_| roll_over_if_due(&app_handle));
+                if let Err(e) = result {
+                    error!("Daily note rollover failed: {}", e);
+                }
+            }
+            tokio::time::sleep(ROLLOVER_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// Choose the tab that rolls over daily, or None to turn archive mode off
+#[tauri::command]
+pub fn set_daily_note_tab<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: Option<usize>,
+) -> Result<(), String> {
+    if tab_index.is_some_and(|tab| tab >= 7) {
+        return Err(format!("Invalid tab index: {:?}", tab_index));
+    }
+
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        settings["daily_note_tab"] = serde_json::json!(tab_index);
+        settings["daily_note_date"] = match tab_index {
+            Some(_) => serde_json::json!(today()),
+            None => serde_json::Value::Null,
+        };
+        Ok(())
+    })
+}
+
+// List archived days, newest first
+#[tauri::command]
+pub fn list_archive<R: Runtime>(app_handle: AppHandle<R>) -> Result<Vec<ArchiveEntry>, String> {
+    let archive_dir = get_archive_dir(&app_handle);
+    if !archive_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&archive_dir)
+        .map_err(|e| format!("Failed to read archive directory: {}", e))?;
+
+    let mut archive: Vec<ArchiveEntry> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            let date = path.file_name()?.to_str()?.strip_suffix(".md")?.to_string();
+            parse_date(&date).ok()?;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some(ArchiveEntry { date, size })
+        })
+        .collect();
+    archive.sort_by(|a, b| b.date.cmp(&a.date));
+
+    Ok(archive)
+}
+
+// Load the archived daily note of one day
+#[tauri::command]
+pub fn load_archive_entry<R: Runtime>(
+    app_handle: AppHandle<R>,
+    date: String,
+) -> Result<String, String> {
+    // Only well-formed dates, never paths
+    parse_date(&date)?;
+
+    let path = get_archive_dir(&app_handle).join(format!("{}.md", date));
+    if !path.exists() {
+        return Err(format!("No archive entry for {}", date));
+    }
+
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read archive: {}", e))?;
+    storage_service::decode_note_bytes(&app_handle, &bytes)
+}
@@ -0,0 +1,151 @@
+// attachments.rs - Images pasted into notes, stored next to the notes in assets/
+use log::info;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+
+pub const ASSETS_DIR_NAME: &str = "assets";
+
+// Refuse anything larger than this to keep notes, backups and sync lean
+const MAX_ATTACHMENT_SIZE: usize = 20 * 1024 * 1024;
+
+// Get the assets directory next to the notes
+pub fn get_assets_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::storage_service::get_current_storage_dir(app_handle).join(ASSETS_DIR_NAME)
+}
+
+// Detect the image type from its leading bytes
+fn detect_image_extension(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if data.starts_with(b"\xFF\xD8\xFF") {
+        Some("jpg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("webp")
+    } else if data.starts_with(b"BM") {
+        Some("bmp")
+    } else {
+        let head = String::from_utf8_lossy(&data[..data.len().min(512)]).to_lowercase();
+        if head.contains("<svg") {
+            Some("svg")
+        } else {
+            None
+        }
+    }
+}
+
+// Keep a readable, filesystem-safe stem from the original file name
+fn sanitize_stem(file_name: Option<&str>) -> String {
+    let stem = file_name
+        .and_then(|name| Path::new(name).file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let sanitized: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if sanitized.is_empty() {
+        "image".to_string()
+    } else {
+        sanitized.chars().take(48).collect()
+    }
+}
+
+// Save a pasted image into assets/ and return a markdown image link to it
+#[tauri::command]
+pub fn save_attachment<R: Runtime>(
+    app_handle: AppHandle<R>,
+    data: Vec<u8>,
+    file_name: Option<String>,
+) -> Result<String, String> {
+    if data.is_empty() {
+        return Err("Attachment is empty".to_string());
+    }
+    if data.len() > MAX_ATTACHMENT_SIZE {
+        return Err(format!(
+            "Attachment is too large ({} bytes, limit is {} bytes)",
+            data.len(),
+            MAX_ATTACHMENT_SIZE
+        ));
+    }
+
+    let extension = detect_image_extension(&data)
+        .ok_or_else(|| "Unsupported attachment: only images can be attached".to_string())?;
+
+    // Name by content hash so pasting the same image twice reuses one file
+    let digest = Sha256::digest(&data);
+    let short_hash: String = digest
+        .iter()
+        .take(6)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let stem = sanitize_stem(file_name.as_deref());
+    let asset_name = format!("{}-{}.{}", stem, short_hash, extension);
+
+    let assets_dir = get_assets_dir(&app_handle);
+    if !assets_dir.exists() {
+        fs::create_dir_all(&assets_dir)
+            .map_err(|e| format!("Failed to create assets directory: {}", e))?;
+    }
+
+    let asset_path = assets_dir.join(&asset_name);
+    if !asset_path.exists() {
+        crate::storage_service::write_atomic(&asset_path, &data)
+            .map_err(|e| format!("Failed to save attachment: {}", e))?;
+        info!("Saved attachment {}", asset_name);
+    }
+
+    Ok(format!("![{}]({}/{})", stem, ASSETS_DIR_NAME, asset_name))
+}
+
+// List the file names currently in assets/
+#[tauri::command]
+pub fn list_attachments<R: Runtime>(app_handle: AppHandle<R>) -> Result<Vec<String>, String> {
+    let assets_dir = get_assets_dir(&app_handle);
+    if !assets_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        fs::read_dir(&assets_dir).map_err(|e| format!("Failed to read assets directory: {}", e))?;
+
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+
+    Ok(names)
+}
+
+// Delete an attachment by file name
+#[tauri::command]
+pub fn delete_attachment<R: Runtime>(app_handle: AppHandle<R>, name: String) -> Result<(), String> {
+    // Only plain file names, never paths
+    if name.is_empty() || Path::new(&name).file_name() != Some(std::ffi::OsStr::new(&name)) {
+        return Err(format!("Invalid attachment name: {}", name));
+    }
+
+    let path = get_assets_dir(&app_handle).join(&name);
+    if !path.exists() {
+        return Err(format!("Attachment not found: {}", name));
+    }
+
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete attachment: {}", e))
+}
@@ -0,0 +1,289 @@
+// autosave_service.rs - Buffer note edits in memory and write them once typing pauses
+use log::{error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::storage_service;
+
+const DEFAULT_AUTOSAVE_DELAY_MS: u64 = 1000;
+// How often the interval policy is re-read while it's off
+const POLICY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// When buffered edits are written besides the autosave delay. Enforced here
+// rather than in the editor, so a frontend change can't drop them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SavePolicy {
+    // When the window loses focus
+    pub flush_on_blur: bool,
+    // Every N seconds; 0 turns it off
+    pub flush_interval_secs: u64,
+    // Before backups, restores and takeout exports/imports
+    pub flush_before_backup: bool,
+}
+
+struct PendingSave {
+    content: String,
+    generation: u64,
+}
+
+// Latest unsaved content per tab
+static PENDING_SAVES: Lazy<Mutex<HashMap<usize, PendingSave>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Bumped on every edit, so a timer only flushes if no newer edit arrived
+static SAVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Quiet period before a buffered edit is written, from settings.json
+fn autosave_delay<R: Runtime>(app_handle: &AppHandle<R>) -> Duration {
+    let delay_ms = read_settings(app_handle)["autosave_delay_ms"]
+        .as_u64()
+        .unwrap_or(DEFAULT_AUTOSAVE_DELAY_MS);
+
+    Duration::from_millis(delay_ms)
+}
+
+fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> std::path::PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("settings.json")
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+pub fn load_save_policy<R: Runtime>(app_handle: &AppHandle<R>) -> SavePolicy {
+    let settings = read_settings(app_handle);
+    SavePolicy {
+        flush_on_blur: settings["autosave_flush_on_blur"].as_bool().unwrap_or(true),
+        flush_interval_secs: settings["autosave_flush_interval_secs"]
+            .as_u64()
+            .unwrap_or(0),
+        flush_before_backup: settings["autosave_flush_before_backup"]
+            .as_bool()
+            .unwrap_or(true),
+    }
+}
+
+fn write_pending<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: String,
+) -> Result<(), String> {
+    let result = crate::archive_service::prepare_save(app_handle, tab_index, content.clone())
+        .and_then(|content| storage_service::write_note(app_handle, tab_index, &content));
+    match result {
+        Ok(()) => {
+            crate::publish_service::on_note_saved(app_handle, tab_index);
+            Ok(())
+        }
+        Err(e) => {
+            // Keep the content buffered so the next flush retries it, unless
+            // a newer edit has replaced it in the meantime
+            if let Ok(mut pending) = PENDING_SAVES.lock() {
+                pending.entry(tab_index).or_insert(PendingSave {
+                    content,
+                    generation: SAVE_GENERATION.load(Ordering::SeqCst),
+                });
+            }
+            if let Err(emit_error) = app_handle.emit(
+                "note-save-failed",
+                serde_json::json!({ "tabIndex": tab_index, "error": e }),
+            ) {
+                error!("Failed to emit note-save-failed event: {}", emit_error);
+            }
+            Err(e)
+        }
+    }
+}
+
+// Drop a tab's buffered content, e.g. when it's saved directly
+pub fn discard_pending(tab_index: usize) {
+    if let Ok(mut pending) = PENDING_SAVES.lock() {
+        pending.remove(&tab_index);
+    }
+}
+
+// Write all buffered content now; returns how many notes were written
+pub fn flush_all<R: Runtime>(app_handle: &AppHandle<R>) -> Result<usize, String> {
+    let pending: Vec<(usize, PendingSave)> = PENDING_SAVES
+        .lock()
+        .map(|mut pending| pending.drain().collect())
+        .unwrap_or_default();
+
+    let mut flushed = 0;
+    let mut last_error = None;
+    for (tab_index, save) in pending {
+        match write_pending(app_handle, tab_index, save.content) {
+            Ok(()) => flushed += 1,
+            Err(e) => {
+                error!("Failed to save note {}: {}", tab_index, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) => Err(e),
+        None => Ok(flushed),
+    }
+}
+
+// Called when the app exits, so no typing is lost
+pub fn flush_on_exit<R: Runtime>(app_handle: &AppHandle<R>) {
+    match flush_all(app_handle) {
+        Ok(0) => {}
+        Ok(count) => info!("Saved {} pending note(s) on exit", count),
+        Err(e) => error!("Failed to save pending notes on exit: {}", e),
+    }
+}
+
+fn flush_logged<R: Runtime>(app_handle: &AppHandle<R>, reason: &str) {
+    match flush_all(app_handle) {
+        Ok(0) => {}
+        Ok(count) => info!("Saved {} pending note(s) {}", count, reason),
+        Err(e) => error!("Failed to save pending notes {}: {}", reason, e),
+    }
+}
+
+// The main window lost focus
+pub fn on_window_blur<R: Runtime>(app_handle: &AppHandle<R>) {
+    if load_save_policy(app_handle).flush_on_blur {
+        flush_logged(app_handle, "on losing focus");
+    }
+}
+
+// Called before backups, restores and takeout, so they see the latest edits
+// and a late autosave can't overwrite what a restore put back
+pub fn flush_before_backup<R: Runtime>(app_handle: &AppHandle<R>) {
+    if load_save_policy(app_handle).flush_before_backup {
+        flush_logged(app_handle, "before backup");
+    }
+}
+
+// Write buffered edits every `flush_interval_secs`, when that's set
+pub fn start_flush_scheduler<R: Runtime>(app_handle: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match load_save_policy(&app_handle).flush_interval_secs {
+                0 => tokio::time::sleep(POLICY_POLL_INTERVAL).await,
+                secs => {
+                    tokio::time::sleep(Duration::from_secs(secs)).await;
+                    flush_logged(&app_handle, "on the flush interval");
+                }
+            }
+        }
+    });
+}
+
+// Buffer a note's content and write it after the autosave delay, unless
+// another edit to the same tab arrives first
+#[tauri::command]
+pub fn save_note_debounced<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    content: String,
+) -> Result<(), String> {
+    let throttle = crate::metrics_service::is_write_burst(tab_index);
+    buffer_save(&app_handle, tab_index, content, throttle)
+}
+
+// Buffer a note's content; when `throttle` is set, an autosave that's already
+// waiting isn't postponed
+pub fn buffer_save<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: String,
+    throttle: bool,
+) -> Result<(), String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    crate::note_events::remember_editor_content(tab_index, &content);
+
+    let generation = {
+        let mut pending = PENDING_SAVES
+            .lock()
+            .map_err(|e| format!("Failed to buffer note: {}", e))?;
+
+        // In a save loop, keep the timer that's already running instead of
+        // restarting it on every call, so the note is still written once per
+        // delay rather than postponed for as long as the loop lasts
+        if throttle {
+            if let Some(save) = pending.get_mut(&tab_index) {
+                save.content = content;
+                return Ok(());
+            }
+        }
+
+        let generation = SAVE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        pending.insert(
+            tab_index,
+            PendingSave {
+                content,
+                generation,
+            },
+        );
+        generation
+    };
+
+    // Unsaved edits: the file watcher must not clobber them
+    storage_service::mark_note_dirty(tab_index);
+
+    let delay = autosave_delay(app_handle);
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+
+        let due = PENDING_SAVES
+            .lock()
+            .ok()
+            .and_then(|mut pending| match pending.get(&tab_index) {
+                Some(save) if save.generation == generation => pending.remove(&tab_index),
+                _ => None,
+            });
+
+        if let Some(save) = due {
+            if let Err(e) = write_pending(&app_handle, tab_index, save.content) {
+                error!("Autosave of note {} failed: {}", tab_index, e);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// Write all buffered edits immediately
+#[tauri::command]
+pub fn flush_notes<R: Runtime>(app_handle: AppHandle<R>) -> Result<usize, String> {
+    flush_all(&app_handle)
+}
+
+// Get when buffered edits are written besides the autosave delay
+#[tauri::command]
+pub fn get_save_policy<R: Runtime>(app_handle: AppHandle<R>) -> SavePolicy {
+    load_save_policy(&app_handle)
+}
+
+// Change when buffered edits are written besides the autosave delay
+#[tauri::command]
+pub fn set_save_policy<R: Runtime>(
+    app_handle: AppHandle<R>,
+    policy: SavePolicy,
+) -> Result<(), String> {
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        settings["autosave_flush_on_blur"] = serde_json::json!(policy.flush_on_blur);
+        settings["autosave_flush_interval_secs"] = serde_json::json!(policy.flush_interval_secs);
+        settings["autosave_flush_before_backup"] = serde_json::json!(policy.flush_before_backup);
+        Ok(())
+    })
+}
@@ -1,24 +1,79 @@
 // backup_service.rs
+use log::info;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Manager, Runtime};
+use tauri::{AppHandle, Runtime};
 use zip::write::SimpleFileOptions;
 use zip::ZipWriter;
 
+use crate::tabs_service::TAB_COUNT;
+
+// Archive entry describing the note layout the backup was made with
+const LAYOUT_ENTRY: &str = "layout.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BackupLayout {
+    version: u32,
+    tab_count: usize,
+    // Tab indices in display order
+    order: Vec<usize>,
+}
+
+// Backups from before layout.json were all made with the fixed tabs
+impl Default for BackupLayout {
+    fn default() -> Self {
+        BackupLayout {
+            version: 1,
+            tab_count: 7,
+            order: (0..7).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreMapping {
+    pub backup_tab: usize,
+    // Note to restore it into; None skips it
+    pub target_tab: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreMappingRow {
+    pub backup_tab: usize,
+    pub backup_title: String,
+    pub target_tab: Option<usize>,
+    pub target_title: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RestorePlan {
+    // The backup's notes line up with the current ones and restore as they are
+    pub layout_matches: bool,
+    pub backup_tab_count: usize,
+    pub current_tab_count: usize,
+    // Proposed mapping, to be confirmed when the layout doesn't match
+    pub mapping: Vec<RestoreMappingRow>,
+}
+
 // Create a backup of all note files
 #[tauri::command]
 pub async fn create_backup<R: Runtime>(app_handle: AppHandle<R>) -> Result<String, String> {
+    let _timing = crate::metrics_service::time_async_command("create_backup");
+    crate::autosave_service::flush_before_backup(&app_handle);
+
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
         .as_secs();
 
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(&app_handle);
 
     // Create backups directory if it doesn't exist
     let backups_dir = app_dir.join("backups");
@@ -46,20 +101,64 @@ pub async fn create_backup<R: Runtime>(app_handle: AppHandle<R>) -> Result<Strin
     for i in 0..7 {
         let note_path = crate::storage_service::get_note_path(&app_handle, i);
         if note_path.exists() {
-            let note_content = fs::read_to_string(&note_path)
-                .map_err(|e| format!("Failed to read note {}: {}", i, e))?;
+            crate::storage_service::ensure_materialized(&note_path);
+            // Raw bytes: encrypted notes are backed up as ciphertext
+            let note_content =
+                fs::read(&note_path).map_err(|e| format!("Failed to read note {}: {}", i, e))?;
 
             // Add file to zip
             zip.start_file(format!("note_{}.md", i), options)
                 .map_err(|e| format!("Failed to add note {} to backup: {}", i, e))?;
 
-            zip.write_all(note_content.as_bytes())
+            zip.write_all(&note_content)
                 .map_err(|e| format!("Failed to write note {} content: {}", i, e))?;
 
             added_files += 1;
         }
     }
 
+    // Add attachments referenced by notes
+    let assets_dir = crate::attachments::get_assets_dir(&app_handle);
+    if assets_dir.is_dir() {
+        let entries = fs::read_dir(&assets_dir)
+            .map_err(|e| format!("Failed to read assets directory: {}", e))?;
+
+        for entry in entries.filter_map(Result::ok) {
+            let asset_path = entry.path();
+            if !asset_path.is_file() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            let data = fs::read(&asset_path)
+                .map_err(|e| format!("Failed to read attachment {}: {}", name, e))?;
+
+            zip.start_file(
+                format!("{}/{}", crate::attachments::ASSETS_DIR_NAME, name),
+                options,
+            )
+            .map_err(|e| format!("Failed to add attachment {} to backup: {}", name, e))?;
+
+            zip.write_all(&data)
+                .map_err(|e| format!("Failed to write attachment {} content: {}", name, e))?;
+
+            added_files += 1;
+        }
+    }
+
+    // Record the layout, so a restore into a different one can map the notes
+    let layout = BackupLayout {
+        version: 1,
+        tab_count: TAB_COUNT,
+        order: crate::tabs_service::load_layout(&app_handle).display_order(),
+    };
+    zip.start_file(LAYOUT_ENTRY, options)
+        .map_err(|e| format!("Failed to add layout to backup: {}", e))?;
+    let layout_json = serde_json::to_string_pretty(&layout)
+        .map_err(|e| format!("Failed to serialize backup layout: {}", e))?;
+    zip.write_all(layout_json.as_bytes())
+        .map_err(|e| format!("Failed to write backup layout: {}", e))?;
+
     // Add a metadata file with timestamp
     zip.start_file("backup_info.txt", options)
         .map_err(|e| format!("Failed to add metadata to backup: {}", e))?;
@@ -83,10 +182,7 @@ pub async fn create_backup<R: Runtime>(app_handle: AppHandle<R>) -> Result<Strin
 // Get a list of available backups
 #[tauri::command]
 pub fn list_backups(app_handle: AppHandle) -> Result<Vec<String>, String> {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(&app_handle);
 
     let backups_dir = app_dir.join("backups");
     if !backups_dir.exists() {
@@ -113,79 +209,308 @@ pub fn list_backups(app_handle: AppHandle) -> Result<Vec<String>, String> {
     Ok(backups)
 }
 
-// Restore from a backup file
-#[tauri::command]
-pub async fn restore_backup(app_handle: AppHandle, backup_path: String) -> Result<(), String> {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+// Whether any backup holds notes encrypted at rest, which can only be
+// restored with the encryption passphrase
+pub fn backups_contain_encrypted_notes<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
+    let backups_dir = crate::data_dir::get_app_data_dir(app_handle).join("backups");
+    let Ok(entries) = fs::read_dir(backups_dir) else {
+        return false;
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zip"))
+        .filter_map(|path| open_backup(&path).ok())
+        .any(|mut archive| {
+            (0..archive.len()).any(|i| {
+                let Ok(mut file) = archive.by_index(i) else {
+                    return false;
+                };
+                if file
+                    .enclosed_name()
+                    .and_then(|path| backup_note_index(&path))
+                    .is_none()
+                {
+                    return false;
+                }
+                let mut data = Vec::new();
+                io::copy(&mut file, &mut data).is_ok()
+                    && crate::storage_service::is_encrypted(&data)
+            })
+        })
+}
+
+// Tab of a note entry; backups keep their notes at the root of the archive,
+// so `note_N.md` anywhere else is not one of them
+fn backup_note_index(path: &Path) -> Option<usize> {
+    path.parent()
+        .is_none_or(|parent| parent.as_os_str().is_empty())
+        .then(|| crate::storage_service::note_index_from_path(path))
+        .flatten()
+}
 
-    let backup_path = Path::new(&backup_path);
+fn open_backup(backup_path: &Path) -> Result<zip::ZipArchive<fs::File>, String> {
     if !backup_path.exists() {
         return Err(format!("Backup file not found: {}", backup_path.display()));
     }
 
-    // Open the zip file
     let file =
         fs::File::open(backup_path).map_err(|e| format!("Failed to open backup file: {}", e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("Failed to read backup archive: {}", e))
+}
 
-    let mut archive =
-        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read backup archive: {}", e))?;
+fn read_layout(archive: &mut zip::ZipArchive<fs::File>) -> Result<BackupLayout, String> {
+    let Ok(mut entry) = archive.by_name(LAYOUT_ENTRY) else {
+        return Ok(BackupLayout::default());
+    };
 
-    // Extract each note file
+    let mut json_str = String::new();
+    entry
+        .read_to_string(&mut json_str)
+        .map_err(|e| format!("Failed to read backup layout: {}", e))?;
+    serde_json::from_str(&json_str).map_err(|e| format!("Failed to parse backup layout: {}", e))
+}
+
+// Title of a note stored in a backup; encrypted notes are decrypted with the
+// current passphrase
+fn backup_note_titles<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    archive: &mut zip::ZipArchive<fs::File>,
+) -> Result<Vec<(usize, String)>, String> {
+    let mut titles = Vec::new();
     for i in 0..archive.len() {
         let mut file = archive
             .by_index(i)
             .map_err(|e| format!("Failed to access backup file entry: {}", e))?;
+        let Some(tab_index) = file
+            .enclosed_name()
+            .and_then(|path| backup_note_index(&path))
+        else {
+            continue;
+        };
+
+        let mut data = Vec::new();
+        io::copy(&mut file, &mut data).map_err(|e| format!("Failed to copy file data: {}", e))?;
+        let title = crate::storage_service::decode_note_bytes(app_handle, &data)
+            .map(|content| crate::export_service::note_title(tab_index, &content))
+            .unwrap_or_else(|_| format!("Note {}", tab_index + 1));
+        titles.push((tab_index, title));
+    }
 
-        let outpath = match file.enclosed_name() {
-            Some(path) => {
-                if path.to_string_lossy().ends_with(".md") {
-                    app_dir.join(path)
-                } else {
-                    // Skip non-markdown files (like the metadata file)
-                    continue;
+    titles.sort_by_key(|(tab_index, _)| *tab_index);
+    Ok(titles)
+}
+
+fn build_restore_plan<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    archive: &mut zip::ZipArchive<fs::File>,
+) -> Result<RestorePlan, String> {
+    let layout = read_layout(archive)?;
+    let backup_titles = backup_note_titles(app_handle, archive)?;
+
+    let layout_matches = layout.tab_count == TAB_COUNT
+        && backup_titles
+            .iter()
+            .all(|(tab_index, _)| *tab_index < TAB_COUNT);
+
+    let current_titles: Vec<Option<String>> = (0..TAB_COUNT)
+        .map(|tab_index| {
+            crate::storage_service::read_note(app_handle, tab_index)
+                .ok()
+                .flatten()
+                .map(|content| crate::export_service::note_title(tab_index, &content))
+        })
+        .collect();
+
+    // Matching layouts restore note for note. Otherwise a note goes to the
+    // current note with the same title, or else to the one at its index.
+    let mut used = [false; TAB_COUNT];
+    let mut targets: Vec<Option<usize>> = vec![None; backup_titles.len()];
+    if layout_matches {
+        for (target, (tab_index, _)) in targets.iter_mut().zip(&backup_titles) {
+            *target = Some(*tab_index);
+        }
+    } else {
+        for (target, (_, title)) in targets.iter_mut().zip(&backup_titles) {
+            *target = (0..TAB_COUNT).find(|&tab_index| {
+                !used[tab_index]
+                    && current_titles[tab_index]
+                        .as_ref()
+                        .is_some_and(|current| current.eq_ignore_ascii_case(title))
+            });
+            if let Some(tab_index) = *target {
+                used[tab_index] = true;
+            }
+        }
+        for (target, (tab_index, _)) in targets.iter_mut().zip(&backup_titles) {
+            if target.is_none() && *tab_index < TAB_COUNT && !used[*tab_index] {
+                *target = Some(*tab_index);
+                used[*tab_index] = true;
+            }
+        }
+    }
+
+    let mapping = backup_titles
+        .into_iter()
+        .zip(targets)
+        .map(
+            |((backup_tab, backup_title), target_tab)| RestoreMappingRow {
+                backup_tab,
+                backup_title,
+                target_tab,
+                target_title: target_tab.and_then(|tab_index| current_titles[tab_index].clone()),
+            },
+        )
+        .collect();
+
+    Ok(RestorePlan {
+        layout_matches,
+        backup_tab_count: layout.tab_count,
+        current_tab_count: TAB_COUNT,
+        mapping,
+    })
+}
+
+// Compare a backup's notes with the current ones before restoring it; when the
+// layouts differ, the proposed mapping is shown for the user to confirm
+#[tauri::command]
+pub fn plan_backup_restore(
+    app_handle: AppHandle,
+    backup_path: String,
+) -> Result<RestorePlan, String> {
+    let mut archive = open_backup(Path::new(&backup_path))?;
+    build_restore_plan(&app_handle, &mut archive)
+}
+
+// Restore from a backup file. A backup made with a different note layout needs
+// a confirmed `mapping` from plan_backup_restore.
+#[tauri::command]
+pub async fn restore_backup(
+    app_handle: AppHandle,
+    backup_path: String,
+    mapping: Option<Vec<RestoreMapping>>,
+) -> Result<(), String> {
+    let _timing = crate::metrics_service::time_async_command("restore_backup");
+    crate::autosave_service::flush_before_backup(&app_handle);
+
+    let mut archive = open_backup(Path::new(&backup_path))?;
+
+    let targets: Vec<(usize, Option<usize>)> = match mapping {
+        Some(mapping) => {
+            let mut seen = Vec::new();
+            for entry in &mapping {
+                if let Some(target) = entry.target_tab {
+                    if target >= TAB_COUNT {
+                        return Err(format!("Invalid tab index: {}", target));
+                    }
+                    if seen.contains(&target) {
+                        return Err(format!(
+                            "More than one backup note restores into note {}",
+                            target + 1
+                        ));
+                    }
+                    seen.push(target);
                 }
             }
-            None => continue,
+            mapping
+                .into_iter()
+                .map(|entry| (entry.backup_tab, entry.target_tab))
+                .collect()
+        }
+        None => {
+            let plan = build_restore_plan(&app_handle, &mut archive)?;
+            if !plan.layout_matches {
+                return Err(format!(
+                    "The backup was made with {} notes and doesn't match the current {}; \
+                     confirm how its notes map to the current ones",
+                    plan.backup_tab_count, plan.current_tab_count
+                ));
+            }
+            plan.mapping
+                .into_iter()
+                .map(|row| (row.backup_tab, row.target_tab))
+                .collect()
+        }
+    };
+
+    // Read and check everything before touching the current notes, so a
+    // backup that can't be decrypted fails without replacing anything
+    let mut notes = Vec::new();
+    let mut attachments = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to access backup file entry: {}", e))?;
+        let Some(path) = file.enclosed_name() else {
+            continue;
         };
 
-        // Create parent directory if it doesn't exist
+        let mut data = Vec::new();
+        if path.starts_with(crate::attachments::ASSETS_DIR_NAME) {
+            io::copy(&mut file, &mut data)
+                .map_err(|e| format!("Failed to copy file data: {}", e))?;
+            // Attachments go back next to the notes
+            attachments.push((
+                crate::storage_service::get_current_storage_dir(&app_handle).join(path),
+                data,
+            ));
+        } else if let Some(tab_index) = backup_note_index(&path) {
+            // Each note goes back to its mapped tab
+            let Some((_, Some(target))) = targets
+                .iter()
+                .find(|(backup_tab, _)| *backup_tab == tab_index)
+            else {
+                continue;
+            };
+            io::copy(&mut file, &mut data)
+                .map_err(|e| format!("Failed to copy file data: {}", e))?;
+            let content =
+                crate::storage_service::decode_note_bytes(&app_handle, &data).map_err(|e| {
+                    format!(
+                        "Failed to read note {} from the backup: {}",
+                        tab_index + 1,
+                        e
+                    )
+                })?;
+            notes.push((*target, content));
+        }
+        // Anything else (like the metadata file) isn't restored
+    }
+    drop(archive);
+
+    // Keep what's being replaced
+    let safety_backup = create_backup(app_handle.clone()).await.map_err(|e| {
+        format!(
+            "Failed to back up the current notes before restoring: {}",
+            e
+        )
+    })?;
+    info!(
+        "Backed up the current notes to {} before restoring",
+        safety_backup
+    );
+
+    for (outpath, data) in attachments {
         if let Some(parent) = outpath.parent() {
             if !parent.exists() {
                 fs::create_dir_all(parent)
                     .map_err(|e| format!("Failed to create parent directory: {}", e))?;
             }
         }
-
-        // Extract the file
-        let mut outfile = fs::File::create(&outpath)
+        crate::storage_service::write_atomic(&outpath, &data)
             .map_err(|e| format!("Failed to create output file: {}", e))?;
+    }
 
-        io::copy(&mut file, &mut outfile)
-            .map_err(|e| format!("Failed to copy file data: {}", e))?;
-
-        // Emit an event to update the UI for this note
-        if let Some(filename) = outpath.file_name() {
-            if let Some(note_name) = filename.to_string_lossy().strip_prefix("note_") {
-                if let Some(index_str) = note_name.strip_suffix(".md") {
-                    if let Ok(index) = index_str.parse::<usize>() {
-                        let outpath = crate::storage_service::get_note_path(&app_handle, index);
-                        let content = fs::read_to_string(&outpath)
-                            .map_err(|e| format!("Failed to read restored note: {}", e))?;
-
-                        // Emit an event to update the UI
-                        tauri::Emitter::emit(
-                            &app_handle,
-                            &format!("note-updated-{}", index),
-                            content,
-                        )
-                        .map_err(|e| format!("Failed to emit update event: {}", e))?;
-                    }
-                }
-            }
-        }
+    // Written with the current encryption setting, and undoable
+    for (tab_index, content) in notes {
+        crate::storage_service::write_note_for_operation(
+            &app_handle,
+            tab_index,
+            &content,
+            "Restore backup",
+        )?;
+        crate::note_events::emit_note_updated(&app_handle, tab_index, &content)?;
     }
 
     // Return success
@@ -208,10 +533,7 @@ pub fn delete_backup(backup_path: String) -> Result<(), String> {
 // Count the number of backups
 #[tauri::command]
 pub fn count_backups(app_handle: AppHandle) -> Result<usize, String> {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(&app_handle);
 
     let backups_dir = app_dir.join("backups");
     if !backups_dir.exists() {
@@ -234,10 +556,7 @@ pub fn count_backups(app_handle: AppHandle) -> Result<usize, String> {
 // Prune old backups, keeping only the specified number of recent backups
 #[tauri::command]
 pub fn prune_backups(app_handle: AppHandle, keep_count: usize) -> Result<usize, String> {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(&app_handle);
 
     let backups_dir = app_dir.join("backups");
     if !backups_dir.exists() {
@@ -276,3 +595,17 @@ pub fn prune_backups(app_handle: AppHandle, keep_count: usize) -> Result<usize,
 
     Ok(deleted_count)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_only_read_from_the_archive_root() {
+        assert_eq!(backup_note_index(Path::new("note_0.md")), Some(0));
+        assert_eq!(backup_note_index(Path::new("note_6.md")), Some(6));
+        assert_eq!(backup_note_index(Path::new("assets/note_1.md")), None);
+        assert_eq!(backup_note_index(Path::new("old/nested/note_2.md")), None);
+        assert_eq!(backup_note_index(Path::new("layout.json")), None);
+    }
+}
@@ -65,7 +65,6 @@ pub fn get_credential(service: &str, username: &str) -> Result<String, String> {
 }
 
 // Delete a credential from the system keychain
-#[allow(dead_code)]
 pub fn delete_credential(service: &str, username: &str) -> Result<(), String> {
     debug!(
         "Deleting credential for service: {}, username: {}",
@@ -92,10 +91,7 @@ pub fn get_languagetool_credential(username: String) -> Result<String, String> {
 
 #[command]
 pub fn has_languagetool_credential(username: String) -> bool {
-    match get_credential(LANGUAGETOOL_SERVICE, &username) {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+    get_credential(LANGUAGETOOL_SERVICE, &username).is_ok()
 }
 
 #[command]
@@ -113,8 +109,5 @@ pub fn get_chatgpt_credential(app_handle: AppHandle) -> Result<String, String> {
 #[command]
 pub fn has_chatgpt_credential(app_handle: AppHandle) -> bool {
     let app_id = app_handle.config().identifier.clone();
-    match get_credential(CHATGPT_SERVICE, &app_id) {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+    get_credential(CHATGPT_SERVICE, &app_id).is_ok()
 }
@@ -0,0 +1,259 @@
+// data_dir.rs - Location of the app data directory and read-only detection
+//
+// The data directory can be moved to another folder (e.g. when the default one
+// is read-only on a managed machine). The new location is taken from the
+// JOT_DATA_DIR environment variable or from a small pointer file outside the
+// data directory.
+use log::{error, info, warn};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+
+const DATA_DIR_ENV: &str = "JOT_DATA_DIR";
+const POINTER_FILE_NAME: &str = "data_location";
+const WRITE_TEST_FILE: &str = ".jot_write_test";
+
+// Not copied when relocating; they're rebuilt as needed
+const SKIPPED_ON_RELOCATE: [&str; 1] = ["logs"];
+
+static RELOCATED_DIR: OnceCell<PathBuf> = OnceCell::new();
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+static READ_ONLY_REASON: Mutex<Option<String>> = Mutex::new(None);
+// Held while settings.json is read, changed and written back
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirStatus {
+    pub path: String,
+    pub relocated: bool,
+    pub read_only: bool,
+    pub reason: Option<String>,
+    // Notes live in the data directory, so they can't be edited
+    pub editing_disabled: bool,
+}
+
+fn default_app_data_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .expect("Failed to get app data directory")
+}
+
+// Where the pointer to a relocated data directory may be kept, in order
+fn pointer_dirs<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = [
+        app_handle.path().app_config_dir(),
+        app_handle.path().app_local_data_dir(),
+    ]
+    .into_iter()
+    .filter_map(Result::ok)
+    .collect();
+    dirs.dedup();
+    dirs
+}
+
+fn read_relocation<R: Runtime>(app_handle: &AppHandle<R>) -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    pointer_dirs(app_handle).into_iter().find_map(|dir| {
+        let location = fs::read_to_string(dir.join(POINTER_FILE_NAME)).ok()?;
+        let location = PathBuf::from(location.trim());
+        location.is_dir().then_some(location)
+    })
+}
+
+// Whether files can be created in a directory (creating it if needed)
+fn probe_writable(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let test_path = dir.join(WRITE_TEST_FILE);
+    fs::write(&test_path, "test").map_err(|e| format!("Directory is not writable: {}", e))?;
+    let _ = fs::remove_file(test_path);
+
+    Ok(())
+}
+
+// Resolve the data directory and check that it's writable; call before
+// anything else touches it
+pub fn init<R: Runtime>(app_handle: &AppHandle<R>) {
+    if let Some(dir) = read_relocation(app_handle) {
+        let _ = RELOCATED_DIR.set(dir);
+    }
+
+    let app_dir = get_app_data_dir(app_handle);
+    if let Err(e) = probe_writable(&app_dir) {
+        // The logger isn't up yet, and may never be
+        eprintln!("Data directory {} is read-only: {}", app_dir.display(), e);
+        READ_ONLY.store(true, Ordering::SeqCst);
+        if let Ok(mut reason) = READ_ONLY_REASON.lock() {
+            *reason = Some(e);
+        }
+    }
+}
+
+// The app data directory: settings, logs, backups and (by default) notes
+pub fn get_app_data_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    match RELOCATED_DIR.get() {
+        Some(dir) => dir.clone(),
+        None => default_app_data_dir(app_handle),
+    }
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::SeqCst)
+}
+
+// Fail early with one clear message instead of scattered write errors
+pub fn ensure_writable<R: Runtime>(app_handle: &AppHandle<R>, path: &Path) -> Result<(), String> {
+    if is_read_only() && path.starts_with(get_app_data_dir(app_handle)) {
+        return Err(
+            "Jot's data directory is read-only; move it to a writable folder to make changes"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+// Change settings.json under the settings lock, so a background task and a
+// save from the UI can't drop each other's keys. Nothing is written if
+// `update` fails. Every change to settings.json goes through here.
+pub fn update_settings<R: Runtime, T>(
+    app_handle: &AppHandle<R>,
+    update: impl FnOnce(&mut serde_json::Value) -> Result<T, String>,
+) -> Result<T, String> {
+    let path = get_app_data_dir(app_handle).join("settings.json");
+    ensure_writable(app_handle, &path)?;
+
+    let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut settings: serde_json::Value = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    let result = update(&mut settings)?;
+
+    let json_str = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    crate::storage_service::write_atomic(&path, json_str)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    Ok(result)
+}
+
+fn copy_dir(source: &Path, target: &Path) -> Result<usize, String> {
+    fs::create_dir_all(target)
+        .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+
+    let entries =
+        fs::read_dir(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+
+    let mut copied = 0;
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let destination = target.join(entry.file_name());
+        if path.is_dir() {
+            copied += copy_dir(&path, &destination)?;
+        } else {
+            fs::copy(&path, &destination)
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+            copied += 1;
+        }
+    }
+
+    Ok(copied)
+}
+
+// Get the data directory location and whether it's writable
+#[tauri::command]
+pub fn get_data_dir_status<R: Runtime>(app_handle: AppHandle<R>) -> DataDirStatus {
+    let app_dir = get_app_data_dir(&app_handle);
+    let read_only = is_read_only();
+    let editing_disabled = read_only
+        && (0..7).any(|tab| {
+            crate::storage_service::get_note_path(&app_handle, tab).starts_with(&app_dir)
+        });
+
+    DataDirStatus {
+        path: app_dir.to_string_lossy().to_string(),
+        relocated: RELOCATED_DIR.get().is_some(),
+        read_only,
+        reason: READ_ONLY_REASON
+            .lock()
+            .ok()
+            .and_then(|reason| reason.clone()),
+        editing_disabled,
+    }
+}
+
+// Copy the data directory to a writable folder, point Jot at it and restart
+#[tauri::command]
+pub fn relocate_data_dir<R: Runtime>(app_handle: AppHandle<R>, path: String) -> Result<(), String> {
+    let target = PathBuf::from(&path);
+    let current = get_app_data_dir(&app_handle);
+    if target == current {
+        return Err("Jot's data is already in this folder".to_string());
+    }
+    if target.starts_with(&current) {
+        return Err("The new folder must not be inside the current data directory".to_string());
+    }
+    probe_writable(&target)?;
+
+    // A folder that already holds Jot data is used as it is
+    if target.join("settings.json").exists() {
+        info!("Using existing data directory {}", target.display());
+    } else {
+        crate::autosave_service::flush_on_exit(&app_handle);
+
+        let entries =
+            fs::read_dir(&current).map_err(|e| format!("Failed to read data directory: {}", e))?;
+        let mut copied = 0;
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name();
+            if SKIPPED_ON_RELOCATE.contains(&name.to_string_lossy().as_ref()) {
+                continue;
+            }
+            let source = entry.path();
+            if source.is_dir() {
+                copied += copy_dir(&source, &target.join(&name))?;
+            } else {
+                fs::copy(&source, target.join(&name))
+                    .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+                copied += 1;
+            }
+        }
+        info!("Copied {} files to {}", copied, target.display());
+    }
+
+    // The pointer must live outside the (possibly read-only) data directory
+    let location = target.to_string_lossy().to_string();
+    let saved = pointer_dirs(&app_handle).into_iter().any(|dir| {
+        match fs::create_dir_all(&dir).and_then(|_| {
+            crate::storage_service::write_atomic(dir.join(POINTER_FILE_NAME), &location)
+        }) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to save data location in {}: {}", dir.display(), e);
+                false
+            }
+        }
+    });
+    if !saved {
+        error!("No writable place for the data location pointer");
+        return Err(format!(
+            "Your data was copied, but its new location can't be saved; set the {} \
+             environment variable to {} to use it",
+            DATA_DIR_ENV, location
+        ));
+    }
+
+    info!("Data directory moved to {}; restarting", location);
+    app_handle.restart()
+}
@@ -0,0 +1,471 @@
+// debug_console.rs - Developer gateway for invoking backend commands by name
+//
+// Only usable in debug builds. Credential getters are deliberately not exposed.
+use log::debug;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub required: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [ParamSpec],
+}
+
+const fn param(name: &'static str, kind: &'static str) -> ParamSpec {
+    ParamSpec {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, kind: &'static str) -> ParamSpec {
+    ParamSpec {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+// Expands the command table below into CATALOG and the dispatch used by
+// invoke_debug, so every listed command can be called and every callable
+// command is listed. Each entry names the command, how its result comes back
+// (`result` or `value`, optionally awaited), the function and whether it takes
+// the app handle, and its parameters in call order as the frontend would pass
+// them to invoke().
+macro_rules! debug_commands {
+    ($app:ident, $args:ident; $(
+        $name:ident => $kind:ident $func:path [$($with_app:ident)?] ($($param:expr),* $(,)?),
+            $description:literal;
+    )*) => {
+        const CATALOG: &[CommandSpec] = &[$(
+            CommandSpec {
+                name: stringify!($name),
+                description: $description,
+                params: &[$($param),*],
+            },
+        )*];
+
+        async fn dispatch(
+            $app: AppHandle,
+            command_name: &str,
+            $args: &Value,
+        ) -> Result<Value, String> {
+            match command_name {
+                $(stringify!($name) => debug_commands!(
+                    @call $kind $func, ($($with_app,)? $(arg($args, $param.name)?),*)
+                ),)*
+                _ => Err(format!("Unknown debug command: {}", command_name)),
+            }
+        }
+    };
+    (@call result $func:path, ($($arg:expr),* $(,)?)) => {
+        to_json($func($($arg),*)?)
+    };
+    (@call value $func:path, ($($arg:expr),* $(,)?)) => {
+        to_json($func($($arg),*))
+    };
+    (@call await_result $func:path, ($($arg:expr),* $(,)?)) => {
+        to_json($func($($arg),*).await?)
+    };
+    (@call await_value $func:path, ($($arg:expr),* $(,)?)) => {
+        to_json($func($($arg),*).await)
+    };
+}
+
+// Every registered command except the console itself and the credential
+// getters
+debug_commands! { app, args;
+    load_notes => result crate::load_notes [app] (), "Load all notes";
+    reload_note => result crate::reload_note [app] (param("tabIndex", "number")),
+        "Reload a note from disk";
+    save_note => result crate::save_note [app] (
+        param("tabIndex", "number"),
+        param("content", "string"),
+    ), "Save a note";
+    load_settings => result crate::load_settings [app] (), "Load settings.json";
+    save_settings => result crate::save_settings [app] (param("settings", "object")),
+        "Update settings.json";
+    save_active_tab => result crate::save_active_tab [app] (param("tabIndex", "number")),
+        "Persist the active tab";
+    get_window_mode => value crate::get_window_mode [] (), "How the window was opened";
+    close_window => value crate::close_window [app] (), "Close the main window";
+    quit_app => value crate::quit_app [app] (), "Quit the app";
+
+    create_backup => await_result crate::backup_service::create_backup [app] (),
+        "Create a backup archive";
+    list_backups => result crate::backup_service::list_backups [app] (),
+        "List backup archives";
+    plan_backup_restore => result crate::backup_service::plan_backup_restore [app] (
+        param("backupPath", "string"),
+    ), "Compare a backup's note layout with the current one";
+    restore_backup => await_result crate::backup_service::restore_backup [app] (
+        param("backupPath", "string"),
+        optional("mapping", "{backupTab, targetTab}[]"),
+    ), "Restore notes from a backup archive";
+    delete_backup => result crate::backup_service::delete_backup [] (
+        param("backupPath", "string"),
+    ), "Delete a backup archive";
+    count_backups => result crate::backup_service::count_backups [app] (),
+        "Count backup archives";
+    prune_backups => result crate::backup_service::prune_backups [app] (
+        param("keepCount", "number"),
+    ), "Delete all but the newest backups";
+
+    get_latest_logs => result crate::logging::get_latest_logs [app] (
+        optional("maxLines", "number"),
+    ), "Tail the latest log file";
+    list_log_files => result crate::logging::list_log_files [app] (), "List log files";
+    log_from_frontend => result crate::logging::log_from_frontend [app] (
+        param("logs", "string[]"),
+    ), "Write lines to the log";
+    calculate_log_size => result crate::logging::calculate_log_size [app] (),
+        "Total size of log files in bytes";
+    clear_logs => result crate::logging::clear_logs [app] (), "Delete old log files";
+    get_log_level => value crate::logging::get_log_level [] (), "Current log level";
+    set_log_level => value crate::logging::set_log_level [] (
+        param("level", "Trace|Debug|Info|Warn|Error|None"),
+    ), "Change the log level";
+
+    store_languagetool_credential => result
+        crate::credential_manager::store_languagetool_credential [] (
+            param("username", "string"),
+            param("apiKey", "string"),
+        ), "Store the LanguageTool API key";
+    has_languagetool_credential => value
+        crate::credential_manager::has_languagetool_credential [] (
+            param("username", "string"),
+        ), "Whether a LanguageTool API key is stored";
+    store_chatgpt_credential => result crate::credential_manager::store_chatgpt_credential [app] (
+        param("apiKey", "string"),
+    ), "Store the ChatGPT API key";
+    has_chatgpt_credential => value crate::credential_manager::has_chatgpt_credential [app] (),
+        "Whether a ChatGPT API key is stored";
+    get_language_services_config => result
+        crate::language_service::get_language_services_config [app] (),
+        "LanguageTool configuration (without secrets)";
+    save_language_tool_config => await_result
+        crate::language_service::save_language_tool_config [app] (
+            optional("username", "string"),
+            optional("apiKey", "string"),
+            optional("endpoint", "string"),
+        ), "Update the LanguageTool configuration";
+    check_grammar => await_result crate::language_service::check_grammar [app] (
+        param("text", "string"),
+        param("language", "string"),
+    ), "Run a LanguageTool grammar check";
+
+    get_storage_settings => result crate::storage_service::get_storage_settings [app] (),
+        "Current and default storage paths";
+    set_storage_path => await_result crate::storage_service::set_storage_path [app] (
+        optional("path", "string"),
+    ), "Move the notes to another folder";
+    get_tab_storage_paths => result crate::storage_service::get_tab_storage_paths [app] (),
+        "Per-tab storage paths";
+    set_tab_storage_path => await_result crate::storage_service::set_tab_storage_path [app] (
+        param("tabIndex", "number"),
+        optional("path", "string"),
+    ), "Store one tab in another folder";
+    get_storage_health => value crate::storage_monitor::get_storage_health [app] (),
+        "Reachability of the storage locations";
+    exclude_storage_from_indexing => result
+        crate::storage_monitor::exclude_storage_from_indexing [app] (),
+        "Keep the storage folder out of search indexing";
+    get_encryption_status => value crate::storage_service::get_encryption_status [app] (),
+        "Whether note encryption is enabled";
+    enable_note_encryption => result crate::storage_service::enable_note_encryption [app] (
+        param("passphrase", "string"),
+    ), "Encrypt the notes at rest";
+    disable_note_encryption => result crate::storage_service::disable_note_encryption [app] (),
+        "Decrypt the notes at rest";
+    list_markdown_files => result crate::storage_service::list_markdown_files [] (
+        param("path", "string"),
+    ), "List markdown files in a folder";
+    preview_markdown_import => result crate::storage_service::preview_markdown_import [app] (
+        param("path", "string"),
+        param("mapping", "{tab: file}"),
+    ), "Preview a markdown folder import";
+    import_markdown_folder => await_result crate::storage_service::import_markdown_folder [app] (
+        param("path", "string"),
+        param("mapping", "{tab: file}"),
+    ), "Import markdown files into tabs";
+    set_note_dirty => result crate::storage_service::set_note_dirty [app] (
+        param("tabIndex", "number"),
+        param("dirty", "boolean"),
+    ), "Mark a tab as having unsaved edits";
+    get_note_metadata => result crate::storage_service::get_note_metadata [app] (
+        param("tabIndex", "number"),
+    ), "Size and modification time of a note";
+    load_note => result crate::storage_service::load_note [app] (param("tabIndex", "number")),
+        "Load one note";
+    load_note_chunk => result crate::storage_service::load_note_chunk [app] (
+        param("tabIndex", "number"),
+        param("offset", "number"),
+        param("length", "number"),
+    ), "Load part of a large note";
+    duplicate_note => await_result crate::storage_service::duplicate_note [app] (
+        param("tab", "number"),
+        param("targetTab", "number"),
+    ), "Copy a note into another tab";
+    merge_notes => await_result crate::storage_service::merge_notes [app] (
+        param("source", "number"),
+        param("target", "number"),
+        optional("separator", "string"),
+    ), "Append a note to another";
+    resolve_links => result crate::links_service::resolve_links [app] (param("tab", "number")),
+        "Resolve the links of a note";
+
+    export_everything => await_result crate::takeout_service::export_everything [app] (
+        param("path", "string"),
+    ), "Write a takeout archive";
+    preview_import => result crate::takeout_service::preview_import [app] (
+        param("path", "string"),
+        param("mode", "merge|replace"),
+        optional("categories", "string[]"),
+    ), "Preview a takeout import";
+    import_everything => await_result crate::takeout_service::import_everything [app] (
+        param("path", "string"),
+        param("mode", "merge|replace"),
+        optional("categories", "string[]"),
+    ), "Import a takeout archive";
+    save_attachment => result crate::attachments::save_attachment [app] (
+        param("data", "number[]"),
+        optional("fileName", "string"),
+    ), "Save a file to assets/";
+    list_attachments => result crate::attachments::list_attachments [app] (),
+        "List files in assets/";
+    delete_attachment => result crate::attachments::delete_attachment [app] (
+        param("name", "string"),
+    ), "Delete a file from assets/";
+    export_note => result crate::export_service::export_note [app] (
+        param("tab", "number"),
+        param("format", "html|pdf"),
+        param("targetPath", "string"),
+    ), "Export a note as HTML or PDF";
+    export_all_notes => result crate::export_service::export_all_notes [app] (
+        param("targetPath", "string"),
+        param("format", "markdown|html|json"),
+    ), "Export all notes to a zip or folder";
+    get_publish_config => value crate::publish_service::get_publish_config [app] (),
+        "Static site settings";
+    publish_site => result crate::publish_service::publish_site [app] (
+        param("config", "object"),
+    ), "Publish tabs as a static site";
+    get_pandoc_status => await_value crate::pandoc_service::get_pandoc_status [app] (),
+        "Whether pandoc is installed";
+    export_with_pandoc => await_result crate::pandoc_service::export_with_pandoc [app] (
+        param("tabIndex", "number"),
+        param("format", "docx|odt|epub|latex"),
+        param("targetPath", "string"),
+        optional("options", "object"),
+    ), "Export a note with pandoc";
+
+    run_maintenance => await_result crate::maintenance_service::run_maintenance [app] (),
+        "Run the maintenance tasks now";
+    get_maintenance_report => value crate::maintenance_service::get_maintenance_report [app] (),
+        "Result of the last maintenance run";
+    get_metrics => result crate::metrics_service::get_metrics [] (), "Command timings";
+    get_write_metrics => result crate::metrics_service::get_write_metrics [] (),
+        "Note write counts";
+    get_active_shortcut => value crate::shortcut_service::get_active_shortcut [] (),
+        "The registered global shortcut";
+    get_shortcut_status => value crate::shortcut_service::get_shortcut_status [] (),
+        "Global shortcut registration status";
+    save_note_debounced => result crate::autosave_service::save_note_debounced [app] (
+        param("tabIndex", "number"),
+        param("content", "string"),
+    ), "Queue a note save";
+    flush_notes => result crate::autosave_service::flush_notes [app] (),
+        "Write all queued note saves";
+    get_save_policy => value crate::autosave_service::get_save_policy [app] (),
+        "Autosave policy";
+    set_save_policy => result crate::autosave_service::set_save_policy [app] (
+        param("policy", "object"),
+    ), "Change the autosave policy";
+
+    list_themes => value crate::theme_service::list_themes [app] (), "List themes";
+    get_theme => result crate::theme_service::get_theme [app] (param("id", "string")),
+        "Load a theme";
+    install_theme => result crate::theme_service::install_theme [app] (param("path", "string")),
+        "Install a theme file";
+    list_languages => value crate::i18n_service::list_languages [app] (),
+        "List available languages";
+    get_locale_strings => result crate::i18n_service::get_locale_strings [app] (
+        optional("lang", "string"),
+    ), "Translated strings of a language";
+    set_app_language => result crate::i18n_service::set_app_language [app] (
+        param("lang", "string"),
+    ), "Change the app language";
+
+    set_daily_note_tab => result crate::archive_service::set_daily_note_tab [app] (
+        optional("tabIndex", "number"),
+    ), "Choose the tab rolled over each day";
+    list_archive => result crate::archive_service::list_archive [app] (),
+        "List archived daily notes";
+    load_archive_entry => result crate::archive_service::load_archive_entry [app] (
+        param("date", "YYYY-MM-DD"),
+    ), "Load an archived daily note";
+    get_migration_status => value crate::migration_service::get_migration_status [app] (),
+        "Applied data migrations";
+    plan_migrations => value crate::migration_service::plan_migrations [app] (),
+        "Data migrations that would run";
+    get_data_dir_status => value crate::data_dir::get_data_dir_status [app] (),
+        "Data directory location and state";
+    relocate_data_dir => result crate::data_dir::relocate_data_dir [app] (
+        param("path", "string"),
+    ), "Move the data directory";
+
+    get_tab_layout => value crate::tabs_service::get_tab_layout [app] (),
+        "Tab order and pins";
+    set_tab_order => result crate::tabs_service::set_tab_order [app] (
+        param("order", "number[]"),
+    ), "Reorder the tabs";
+    pin_note => result crate::tabs_service::pin_note [app] (
+        param("tabIndex", "number"),
+        param("pinned", "boolean"),
+    ), "Pin or unpin a tab";
+    save_editor_state => result crate::tabs_service::save_editor_state [app] (
+        param("tabIndex", "number"),
+        param("state", "object"),
+    ), "Store a tab's cursor and scroll position";
+    verify_notes_integrity => value crate::integrity_service::verify_notes_integrity [app] (),
+        "Compare notes with their recorded checksums";
+    restore_note_from_backup => result crate::integrity_service::restore_note_from_backup [app] (
+        param("tabIndex", "number"),
+        optional("backupPath", "string"),
+    ), "Restore one note from a backup";
+    get_save_history => result crate::journal_service::get_save_history [app] (
+        param("tabIndex", "number"),
+    ), "Undo journal of a note";
+    undo_last_save => result crate::journal_service::undo_last_save [app] (
+        param("tabIndex", "number"),
+    ), "Revert a note's last save";
+    undo_last_operation => result crate::journal_service::undo_last_operation [app] (
+        param("tabIndex", "number"),
+    ), "Revert a note's last operation";
+    get_history_usage => value crate::journal_service::get_history_usage [app] (),
+        "Disk use of the undo journals";
+
+    get_tasks => result crate::tasks_service::get_tasks [app] (), "Tasks across all notes";
+    toggle_task => result crate::tasks_service::toggle_task [app] (
+        param("tab", "number"),
+        param("line", "number"),
+    ), "Check or uncheck a task";
+    get_structured_entries => result crate::structured_service::get_structured_entries [app] (
+        param("tabIndex", "number"),
+    ), "Entries of a structured tab";
+    upsert_entry => result crate::structured_service::upsert_entry [app] (
+        param("tabIndex", "number"),
+        param("values", "object"),
+    ), "Add or replace an entry of a structured tab";
+    get_smtp_settings => value crate::email_service::get_smtp_settings [app] (),
+        "SMTP settings (without the password)";
+    set_smtp_settings => result crate::email_service::set_smtp_settings [app] (
+        param("smtp", "object"),
+        optional("password", "string"),
+    ), "Change the SMTP settings";
+    get_email_digest => value crate::email_service::get_email_digest [app] (),
+        "Email digest settings";
+    set_email_digest => result crate::email_service::set_email_digest [app] (
+        param("digest", "object"),
+    ), "Change the email digest settings";
+    send_note_email => await_result crate::email_service::send_note_email [app] (
+        param("tabIndex", "number"),
+        param("to", "string"),
+    ), "Email a note";
+    set_code_execution_enabled => await_result
+        crate::runbook_service::set_code_execution_enabled [app] (
+            param("enabled", "boolean"),
+        ), "Allow running code blocks, after a native confirmation";
+    run_code_block => await_result crate::runbook_service::run_code_block [app] (
+        param("tabIndex", "number"),
+        param("blockIndex", "number"),
+    ), "Run a code block, after a native confirmation";
+    format_markdown_tables => result crate::tables_service::format_markdown_tables [app] (
+        param("tabIndex", "number"),
+    ), "Align the tables of a note";
+    csv_to_markdown_table => result crate::tables_service::csv_to_markdown_table [] (
+        param("text", "string"),
+        optional("delimiter", "string"),
+    ), "Convert CSV to a markdown table";
+    check_links => await_result crate::link_check_service::check_links [app] (
+        optional("tabIndex", "number"),
+    ), "Check the links of one or all notes";
+    find_duplicates => result crate::duplicates_service::find_duplicates [app] (
+        optional("minLength", "number"),
+    ), "Find repeated paragraphs across notes";
+
+    get_accessibility_preferences => await_value
+        crate::accessibility_service::get_accessibility_preferences [] (),
+        "System accessibility preferences";
+    get_proxy_settings => value crate::proxy_service::get_proxy_settings [app] (),
+        "Proxy settings (without the password)";
+    set_proxy_settings => result crate::proxy_service::set_proxy_settings [app] (
+        param("proxy", "object"),
+        optional("password", "string"),
+    ), "Change the proxy settings";
+    validate_user_files => value crate::user_files_service::validate_user_files [app] (),
+        "Problems in hand-edited settings and themes";
+    get_startup_behavior => value crate::startup_service::get_startup_behavior [app] (),
+        "What the app does on startup";
+    set_startup_behavior => result crate::startup_service::set_startup_behavior [app] (
+        param("behavior", "object"),
+    ), "Change what the app does on startup";
+    run_self_test => await_result crate::self_test_service::run_self_test [] (),
+        "Run the self test in a temporary profile";
+}
+
+// Missing arguments are read as null, which only optional parameters accept
+fn arg<T: DeserializeOwned>(args: &Value, name: &str) -> Result<T, String> {
+    let value = args.get(name).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| match args.get(name) {
+        None => format!("Missing argument: {}", name),
+        Some(_) => format!("Invalid argument {}: {}", name, e),
+    })
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to serialize result: {}", e))
+}
+
+fn ensure_debug_build() -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        Ok(())
+    } else {
+        Err("The debug console is only available in debug builds".to_string())
+    }
+}
+
+// List the commands invoke_debug can call, with their parameters
+#[tauri::command]
+pub fn list_debug_commands() -> Result<Vec<CommandSpec>, String> {
+    ensure_debug_build()?;
+    Ok(CATALOG.to_vec())
+}
+
+// Invoke a backend command by name with JSON arguments
+#[tauri::command]
+pub async fn invoke_debug(
+    app_handle: AppHandle,
+    command_name: String,
+    json_args: Option<Value>,
+) -> Result<Value, String> {
+    let _timing = crate::metrics_service::time_async_command("invoke_debug");
+    ensure_debug_build()?;
+
+    let args = json_args.unwrap_or_else(|| Value::Object(Default::default()));
+    debug!("Debug console invoking {}", command_name);
+
+    dispatch(app_handle, &command_name, &args).await
+}
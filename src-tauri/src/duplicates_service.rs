@@ -0,0 +1,244 @@
+// duplicates_service.rs - Find paragraphs repeated across tabs, identical or
+// nearly so, e.g. after pasting between weekday notes
+//
+// Paragraphs are compared as sets of word 3-grams ("shingles"). MinHash
+// signatures bucketed into bands find candidate pairs without comparing every
+// paragraph with every other; candidates are then confirmed with the exact
+// Jaccard similarity.
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use tauri::{AppHandle, Runtime};
+
+const SHINGLE_SIZE: usize = 3;
+const SIGNATURE_BANDS: usize = 16;
+const ROWS_PER_BAND: usize = 4;
+const SIGNATURE_SIZE: usize = SIGNATURE_BANDS * ROWS_PER_BAND;
+const SIMILARITY_THRESHOLD: f64 = 0.8;
+const DEFAULT_MIN_LENGTH: usize = 40;
+const PREVIEW_CHARS: usize = 80;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ParagraphLocation {
+    pub tab: usize,
+    // 1-based lines of the paragraph in the note
+    pub start_line: usize,
+    pub end_line: usize,
+    pub preview: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub identical: bool,
+    // Lowest similarity between two paragraphs of the group, 0..1
+    pub similarity: f64,
+    pub locations: Vec<ParagraphLocation>,
+}
+
+struct Paragraph {
+    location: ParagraphLocation,
+    normalized: String,
+    shingles: HashSet<u64>,
+}
+
+fn hash_of<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Lowercased words only, so formatting and punctuation don't matter
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shingles(normalized: &str) -> HashSet<u64> {
+    let words: Vec<&str> = normalized.split(' ').collect();
+    if words.len() < SHINGLE_SIZE {
+        return HashSet::from([hash_of(normalized)]);
+    }
+    words.windows(SHINGLE_SIZE).map(hash_of).collect()
+}
+
+// Paragraphs are separated by blank lines
+fn split_paragraphs(tab: usize, content: &str, min_length: usize) -> Vec<Paragraph> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut start_line = 0;
+
+    let lines: Vec<&str> = content.lines().chain([""]).collect();
+    for (index, line) in lines.iter().enumerate() {
+        if !line.trim().is_empty() {
+            if current.is_empty() {
+                start_line = index + 1;
+            }
+            current.push(line);
+            continue;
+        }
+        if current.is_empty() {
+            continue;
+        }
+
+        let text = current.join("\n");
+        current.clear();
+        let normalized = normalize(&text);
+        if normalized.chars().count() < min_length {
+            continue;
+        }
+
+        let mut preview: String = text.trim().chars().take(PREVIEW_CHARS).collect();
+        if text.trim().chars().count() > PREVIEW_CHARS {
+            preview.push('…');
+        }
+        paragraphs.push(Paragraph {
+            location: ParagraphLocation {
+                tab,
+                start_line,
+                end_line: index,
+                preview,
+            },
+            shingles: shingles(&normalized),
+            normalized,
+        });
+    }
+
+    paragraphs
+}
+
+// Minimum of each seeded hash over the shingles
+fn minhash_signature(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..SIGNATURE_SIZE as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|shingle| hash_of((seed, shingle)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+fn group_duplicates(paragraphs: &[Paragraph]) -> Vec<DuplicateGroup> {
+    // Paragraphs sharing a band of their signature are candidates
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for (index, paragraph) in paragraphs.iter().enumerate() {
+        let signature = minhash_signature(&paragraph.shingles);
+        for (band, rows) in signature.chunks(ROWS_PER_BAND).enumerate() {
+            buckets
+                .entry((band, hash_of(rows)))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut checked = HashSet::new();
+    let mut similarities: HashMap<(usize, usize), f64> = HashMap::new();
+    let mut parents: Vec<usize> = (0..paragraphs.len()).collect();
+    for members in buckets.values() {
+        for (position, &a) in members.iter().enumerate() {
+            for &b in &members[position + 1..] {
+                // Only repeats across tabs count
+                if paragraphs[a].location.tab == paragraphs[b].location.tab
+                    || !checked.insert((a, b))
+                {
+                    continue;
+                }
+                let similarity = jaccard(&paragraphs[a].shingles, &paragraphs[b].shingles);
+                if similarity >= SIMILARITY_THRESHOLD {
+                    similarities.insert((a, b), similarity);
+                    let (root_a, root_b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+                    parents[root_a] = root_b;
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for index in 0..paragraphs.len() {
+        let root = find_root(&mut parents, index);
+        groups.entry(root).or_default().push(index);
+    }
+
+    let mut result: Vec<DuplicateGroup> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let similarity = similarities
+                .iter()
+                .filter(|((a, _), _)| members.contains(a))
+                .map(|(_, similarity)| *similarity)
+                .fold(1.0, f64::min);
+            let first = &paragraphs[members[0]].normalized;
+            DuplicateGroup {
+                identical: members
+                    .iter()
+                    .all(|index| &paragraphs[*index].normalized == first),
+                similarity,
+                locations: members
+                    .iter()
+                    .map(|index| paragraphs[*index].location.clone())
+                    .collect(),
+            }
+        })
+        .collect();
+
+    // Most repeated first, then in note order
+    result.sort_by(|a, b| {
+        b.locations.len().cmp(&a.locations.len()).then_with(|| {
+            (a.locations[0].tab, a.locations[0].start_line)
+                .cmp(&(b.locations[0].tab, b.locations[0].start_line))
+        })
+    });
+    result
+}
+
+// Find paragraphs of at least `min_length` characters that appear, identical
+// or nearly so, in more than one tab
+#[tauri::command]
+pub fn find_duplicates<R: Runtime>(
+    app_handle: AppHandle<R>,
+    min_length: Option<usize>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let min_length = min_length.unwrap_or(DEFAULT_MIN_LENGTH).max(1);
+
+    let mut paragraphs = Vec::new();
+    for tab in 0..7 {
+        if let Some(content) = crate::storage_service::read_note(&app_handle, tab)? {
+            let body = crate::storage_service::split_frontmatter(&content).1;
+            // Keep line numbers relative to the whole file
+            let offset = content[..content.len() - body.len()].lines().count();
+            paragraphs.extend(split_paragraphs(tab, body, min_length).into_iter().map(
+                |mut paragraph| {
+                    paragraph.location.start_line += offset;
+                    paragraph.location.end_line += offset;
+                    paragraph
+                },
+            ));
+        }
+    }
+
+    Ok(group_duplicates(&paragraphs))
+}
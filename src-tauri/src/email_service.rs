@@ -0,0 +1,297 @@
+// email_service.rs - Send notes by email over SMTP, on demand or as a
+// scheduled daily digest of selected tabs
+//
+// The SMTP server and digest schedule live in settings.json; the SMTP
+// password is kept in the system keychain.
+use chrono::{Local, NaiveTime};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::credential_manager;
+use crate::export_service::{note_title, render_html};
+use crate::storage_service;
+
+const SMTP_SERVICE: &str = "jot.smtp";
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    // Implicit TLS, usually port 465
+    Tls,
+    // Upgrade with STARTTLS, usually port 587
+    Starttls,
+    // Unencrypted, e.g. a relay on localhost
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub from: String,
+    pub security: SmtpSecurity,
+    // Only reported to the frontend; the password is in the keychain
+    #[serde(default)]
+    pub has_password: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestSettings {
+    pub enabled: bool,
+    pub tabs: Vec<usize>,
+    pub to: String,
+    // Local time of day, "HH:MM"
+    pub time: String,
+}
+
+fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("settings.json")
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+// Set one settings key, under the shared settings lock
+fn write_setting<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    crate::data_dir::update_settings(app_handle, |settings| {
+        settings[key] = value;
+        Ok(())
+    })
+}
+
+fn load_smtp_settings<R: Runtime>(app_handle: &AppHandle<R>) -> Option<SmtpSettings> {
+    serde_json::from_value(read_settings(app_handle)["smtp"].clone()).ok()
+}
+
+fn load_digest_settings<R: Runtime>(app_handle: &AppHandle<R>) -> Option<DigestSettings> {
+    serde_json::from_value(read_settings(app_handle)["email_digest"].clone()).ok()
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, String> {
+    address
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid email address {}: {}", address, e))
+}
+
+fn parse_digest_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid digest time (expected HH:MM): {}", time))
+}
+
+async fn send_email<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    to: &str,
+    subject: &str,
+    title: &str,
+    markdown: &str,
+) -> Result<(), String> {
+    let smtp = load_smtp_settings(app_handle).ok_or("No SMTP server is configured")?;
+
+    let message = Message::builder()
+        .from(parse_mailbox(&smtp.from)?)
+        .to(parse_mailbox(to)?)
+        .subject(subject)
+        .multipart(MultiPart::alternative_plain_html(
+            markdown.to_string(),
+            render_html(title, markdown, None),
+        ))
+        .map_err(|e| format!("Failed to build email: {}", e))?;
+
+    let builder = match smtp.security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host),
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+            &smtp.host,
+        )),
+    }
+    .map_err(|e| format!("Failed to set up SMTP connection: {}", e))?
+    .port(smtp.port);
+
+    let transport = if smtp.username.is_empty() {
+        builder.build()
+    } else {
+        let password = credential_manager::get_credential(SMTP_SERVICE, &smtp.username)?;
+        builder
+            .credentials(Credentials::new(smtp.username.clone(), password))
+            .build()
+    };
+
+    transport
+        .send(message)
+        .await
+        .map_err(|e| format!("Failed to send email: {}", e))?;
+
+    info!("Sent \"{}\" by email", subject);
+    Ok(())
+}
+
+// The selected notes as one markdown document, frontmatter removed
+fn build_digest<R: Runtime>(app_handle: &AppHandle<R>, tabs: &[usize]) -> Result<String, String> {
+    let mut sections = Vec::new();
+    for &tab in tabs {
+        if let Some(content) = storage_service::read_note(app_handle, tab)? {
+            let body = storage_service::split_frontmatter(&content).1.trim();
+            if !body.is_empty() {
+                sections.push(body.to_string());
+            }
+        }
+    }
+    Ok(sections.join("\n\n---\n\n"))
+}
+
+async fn send_digest<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    digest: &DigestSettings,
+) -> Result<(), String> {
+    let markdown = build_digest(app_handle, &digest.tabs)?;
+    if markdown.is_empty() {
+        info!("Skipping the email digest: the selected notes are empty");
+        return Ok(());
+    }
+
+    let title = format!("Jot digest {}", Local::now().format(DATE_FORMAT));
+    send_email(app_handle, &digest.to, &title, &title, &markdown).await
+}
+
+fn is_digest_due<R: Runtime>(app_handle: &AppHandle<R>) -> Option<DigestSettings> {
+    let digest = load_digest_settings(app_handle).filter(|digest| digest.enabled)?;
+    let time = parse_digest_time(&digest.time).ok()?;
+
+    let now = Local::now();
+    let today = now.format(DATE_FORMAT).to_string();
+    let sent_today =
+        read_settings(app_handle)["email_digest_last_sent"].as_str() == Some(today.as_str());
+
+    (!sent_today && now.time() >= time).then_some(digest)
+}
+
+// Send the digest once a day, at the configured time or as soon as Jot runs
+// after it
+pub fn start_digest_scheduler<R: Runtime>(app_handle: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(digest) = is_digest_due(&app_handle) {
+                // Record the day first, so a failing server isn't retried
+                // every minute
+                let today = Local::now().format(DATE_FORMAT).to_string();
+                if let Err(e) = write_setting(
+                    &app_handle,
+                    "email_digest_last_sent",
+                    serde_json::json!(today),
+                ) {
+                    warn!("Failed to record the email digest date: {}", e);
+                }
+
+                if let Err(e) = crate::autosave_service::flush_all(&app_handle) {
+                    warn!("Failed to save pending notes before the digest: {}", e);
+                }
+                if let Err(e) = send_digest(&app_handle, &digest).await {
+                    error!("Failed to send the email digest: {}", e);
+                }
+            }
+            tokio::time::sleep(DIGEST_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// Get the SMTP server settings, if configured
+#[tauri::command]
+pub fn get_smtp_settings<R: Runtime>(app_handle: AppHandle<R>) -> Option<SmtpSettings> {
+    load_smtp_settings(&app_handle).map(|smtp| SmtpSettings {
+        has_password: credential_manager::get_credential(SMTP_SERVICE, &smtp.username).is_ok(),
+        ..smtp
+    })
+}
+
+// Save the SMTP server settings; the password (if given) goes to the keychain
+#[tauri::command]
+pub fn set_smtp_settings<R: Runtime>(
+    app_handle: AppHandle<R>,
+    smtp: SmtpSettings,
+    password: Option<String>,
+) -> Result<(), String> {
+    if smtp.host.trim().is_empty() {
+        return Err("SMTP host is required".to_string());
+    }
+    parse_mailbox(&smtp.from)?;
+
+    if let Some(password) = password.filter(|password| !password.is_empty()) {
+        credential_manager::store_credential(SMTP_SERVICE, &smtp.username, &password)?;
+    }
+
+    let smtp = serde_json::to_value(SmtpSettings {
+        has_password: false,
+        ..smtp
+    })
+    .map_err(|e| format!("Failed to serialize SMTP settings: {}", e))?;
+    write_setting(&app_handle, "smtp", smtp)
+}
+
+// Get the daily digest settings, if configured
+#[tauri::command]
+pub fn get_email_digest<R: Runtime>(app_handle: AppHandle<R>) -> Option<DigestSettings> {
+    load_digest_settings(&app_handle)
+}
+
+// Choose which tabs are emailed daily, to whom and when
+#[tauri::command]
+pub fn set_email_digest<R: Runtime>(
+    app_handle: AppHandle<R>,
+    digest: DigestSettings,
+) -> Result<(), String> {
+    if let Some(tab) = digest.tabs.iter().find(|tab| **tab >= 7) {
+        return Err(format!("Invalid tab index: {}", tab));
+    }
+    parse_digest_time(&digest.time)?;
+    if digest.enabled {
+        parse_mailbox(&digest.to)?;
+    }
+
+    let digest = serde_json::to_value(&digest)
+        .map_err(|e| format!("Failed to serialize digest settings: {}", e))?;
+    write_setting(&app_handle, "email_digest", digest)
+}
+
+// Email one note now
+#[tauri::command]
+pub async fn send_note_email<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    to: String,
+) -> Result<(), String> {
+    let _timing = crate::metrics_service::time_async_command("send_note_email");
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    if let Err(e) = crate::autosave_service::flush_all(&app_handle) {
+        warn!("Failed to save pending notes before sending: {}", e);
+    }
+
+    let content = storage_service::read_note(&app_handle, tab_index)?.unwrap_or_default();
+    let title = note_title(tab_index, &content);
+    let body = storage_service::split_frontmatter(&content).1;
+
+    send_email(&app_handle, &to, &title, &title, body).await
+}
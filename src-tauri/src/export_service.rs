@@ -0,0 +1,699 @@
+// export_service.rs - Export notes as standalone HTML or PDF documents
+use base64::Engine;
+use log::{info, warn};
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference};
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+// A4 portrait with uniform margins
+const PAGE_WIDTH_MM: f32 = 210.0;
+const PAGE_HEIGHT_MM: f32 = 297.0;
+const MARGIN_MM: f32 = 20.0;
+const PT_TO_MM: f32 = 0.3528;
+
+pub const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; line-height: 1.6;
+       max-width: 48rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+h1, h2, h3 { line-height: 1.25; }
+pre { background: #f6f8fa; padding: 0.75rem; border-radius: 6px; overflow-x: auto; }
+code { font-family: ui-monospace, Menlo, Consolas, monospace; font-size: 0.9em; }
+blockquote { margin: 0; padding-left: 1rem; border-left: 3px solid #d0d7de; color: #57606a; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d7de; padding: 0.3rem 0.6rem; }
+img { max-width: 100%; }
+"#;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Html => "html",
+            ExportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+// Formats for exporting all notes at once
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+#[derive(Serialize, Debug)]
+struct ExportedNote {
+    tab: usize,
+    title: String,
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    size: usize,
+}
+
+#[derive(Serialize, Debug)]
+struct ExportMetadata {
+    app_version: String,
+    exported_at: String,
+    format: String,
+    notes: Vec<ExportedNote>,
+    attachments: Vec<String>,
+}
+
+// Destination of a multi-file export: a zip archive or a plain folder
+enum ExportSink {
+    Zip(Box<ZipWriter<fs::File>>),
+    Folder(PathBuf),
+}
+
+impl ExportSink {
+    fn add(&mut self, relative_path: &str, bytes: &[u8]) -> Result<(), String> {
+        match self {
+            ExportSink::Zip(zip) => {
+                let options = SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .unix_permissions(0o644);
+                zip.start_file(relative_path, options)
+                    .map_err(|e| format!("Failed to add {} to export: {}", relative_path, e))?;
+                zip.write_all(bytes)
+                    .map_err(|e| format!("Failed to write {} to export: {}", relative_path, e))
+            }
+            ExportSink::Folder(root) => {
+                let path = root.join(relative_path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create export directory: {}", e))?;
+                }
+                crate::storage_service::write_atomic(&path, bytes)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        if let ExportSink::Zip(zip) = self {
+            zip.finish()
+                .map_err(|e| format!("Failed to finalize export: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn markdown_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Use the frontmatter title or the first heading as the document title,
+// falling back to the tab number
+pub fn note_title(tab_index: usize, markdown: &str) -> String {
+    if let Some(title) = crate::storage_service::parse_frontmatter(markdown)
+        .ok()
+        .and_then(|metadata| metadata.title)
+        .filter(|title| !title.trim().is_empty())
+    {
+        return title.trim().to_string();
+    }
+
+    crate::storage_service::split_frontmatter(markdown)
+        .1
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with('#'))
+        .map(|line| line.trim_start_matches('#').trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| format!("Note {}", tab_index + 1))
+}
+
+// Inline an assets/ image as a data URI so the exported file stands alone
+fn inline_asset(assets_base: &Path, dest_url: &str) -> Option<String> {
+    let relative = dest_url.strip_prefix("assets/")?;
+    if relative.contains("..") {
+        return None;
+    }
+
+    let bytes = fs::read(assets_base.join(relative)).ok()?;
+    let mime = match Path::new(relative).extension()?.to_str()? {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+// Render a note's markdown to HTML, without the surrounding document;
+// images under assets/ are inlined if `assets_dir` is given
+pub fn render_body(markdown: &str, assets_dir: Option<&Path>) -> String {
+    // Frontmatter is metadata, not content
+    let markdown = crate::storage_service::split_frontmatter(markdown).1;
+    let parser = Parser::new_ext(markdown, markdown_options()).map(|event| match event {
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => {
+            let dest_url = assets_dir
+                .and_then(|dir| inline_asset(dir, &dest_url))
+                .map(CowStr::from)
+                .unwrap_or(dest_url);
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            })
+        }
+        other => other,
+    });
+
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, parser);
+    body
+}
+
+// Render markdown to a complete, self-contained HTML document
+pub fn render_html(title: &str, markdown: &str, assets_dir: Option<&Path>) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        HTML_STYLE,
+        render_body(markdown, assets_dir)
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockStyle {
+    Heading(u8),
+    Paragraph,
+    ListItem(usize),
+    Quote,
+    Code,
+    Rule,
+}
+
+#[derive(Debug)]
+struct Block {
+    style: BlockStyle,
+    text: String,
+}
+
+// Flatten markdown into simple styled text blocks for the PDF layout
+fn markdown_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    let mut list_counters: Vec<Option<u64>> = Vec::new();
+    let mut quote_depth = 0;
+
+    let flush = |current: &mut Option<Block>, blocks: &mut Vec<Block>| {
+        if let Some(block) = current.take() {
+            if !block.text.trim().is_empty() {
+                blocks.push(block);
+            }
+        }
+    };
+
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                flush(&mut current, &mut blocks);
+                let level = match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    HeadingLevel::H3 => 3,
+                    _ => 4,
+                };
+                current = Some(Block {
+                    style: BlockStyle::Heading(level),
+                    text: String::new(),
+                });
+            }
+            // Paragraphs inside a list item keep writing into the item
+            Event::Start(Tag::Paragraph) if current.is_none() => {
+                current = Some(Block {
+                    style: if quote_depth > 0 {
+                        BlockStyle::Quote
+                    } else {
+                        BlockStyle::Paragraph
+                    },
+                    text: String::new(),
+                });
+            }
+            Event::Start(Tag::List(start)) => {
+                flush(&mut current, &mut blocks);
+                list_counters.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                flush(&mut current, &mut blocks);
+                list_counters.pop();
+            }
+            Event::Start(Tag::Item) => {
+                flush(&mut current, &mut blocks);
+                let marker = match list_counters.last_mut() {
+                    Some(Some(counter)) => {
+                        let marker = format!("{}. ", counter);
+                        *counter += 1;
+                        marker
+                    }
+                    _ => "\u{2022} ".to_string(),
+                };
+                current = Some(Block {
+                    style: BlockStyle::ListItem(list_counters.len().saturating_sub(1)),
+                    text: marker,
+                });
+            }
+            Event::Start(Tag::BlockQuote(_)) => {
+                flush(&mut current, &mut blocks);
+                quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                flush(&mut current, &mut blocks);
+                quote_depth -= 1;
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                flush(&mut current, &mut blocks);
+                current = Some(Block {
+                    style: BlockStyle::Code,
+                    text: String::new(),
+                });
+            }
+            Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::CodeBlock)
+            | Event::End(TagEnd::Item)
+            | Event::End(TagEnd::TableHead)
+            | Event::End(TagEnd::TableRow) => flush(&mut current, &mut blocks),
+            Event::End(TagEnd::Paragraph)
+                if !matches!(
+                    current.as_ref().map(|block| block.style),
+                    Some(BlockStyle::ListItem(_))
+                ) =>
+            {
+                flush(&mut current, &mut blocks);
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                flush(&mut current, &mut blocks);
+                current = Some(Block {
+                    style: BlockStyle::Paragraph,
+                    text: String::new(),
+                });
+            }
+            Event::End(TagEnd::TableCell) => {
+                if let Some(block) = current.as_mut() {
+                    block.text.push_str("  |  ");
+                }
+            }
+            Event::Rule => {
+                flush(&mut current, &mut blocks);
+                blocks.push(Block {
+                    style: BlockStyle::Rule,
+                    text: String::new(),
+                });
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(block) = current.as_mut() {
+                    block.text.push_str(if checked { "[x] " } else { "[ ] " });
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                let block = current.get_or_insert_with(|| Block {
+                    style: BlockStyle::Paragraph,
+                    text: String::new(),
+                });
+                block.text.push_str(&text);
+            }
+            Event::SoftBreak => {
+                if let Some(block) = current.as_mut() {
+                    block.text.push(' ');
+                }
+            }
+            Event::HardBreak => {
+                if let Some(block) = current.as_mut() {
+                    block.text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut current, &mut blocks);
+
+    // Strip the trailing separator left by table cells
+    for block in &mut blocks {
+        if let Some(stripped) = block.text.strip_suffix("  |  ") {
+            block.text = stripped.to_string();
+        }
+    }
+
+    blocks
+}
+
+// Greedy word wrap to a maximum number of characters per line
+fn wrap_text(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(10);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word.to_string();
+            // Hard-break words that can never fit on one line
+            while word.chars().count() > max_chars {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                let head: String = word.chars().take(max_chars).collect();
+                word = word.chars().skip(max_chars).collect();
+                lines.push(head);
+            }
+
+            let needed = if line.is_empty() {
+                word.chars().count()
+            } else {
+                line.chars().count() + 1 + word.chars().count()
+            };
+            if needed > max_chars && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Wrap preformatted text by characters, keeping indentation
+fn wrap_code(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(10);
+    text.trim_end_matches('\n')
+        .split('\n')
+        .flat_map(|line| {
+            let chars: Vec<char> = line.replace('\t', "    ").chars().collect();
+            if chars.is_empty() {
+                vec![String::new()]
+            } else {
+                chars
+                    .chunks(max_chars)
+                    .map(|chunk| chunk.iter().collect())
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+struct PdfCursor {
+    layer: PdfLayerReference,
+    y: f32,
+}
+
+// Render markdown to a paginated A4 PDF using the built-in PDF fonts
+pub fn render_pdf(title: &str, markdown: &str) -> Result<Vec<u8>, String> {
+    let markdown = crate::storage_service::split_frontmatter(markdown).1;
+    let (doc, page, layer) =
+        PdfDocument::new(title, Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Layer 1");
+
+    let add_font = |font: BuiltinFont| -> Result<IndirectFontRef, String> {
+        doc.add_builtin_font(font)
+            .map_err(|e| format!("Failed to load PDF font: {}", e))
+    };
+    let regular = add_font(BuiltinFont::Helvetica)?;
+    let bold = add_font(BuiltinFont::HelveticaBold)?;
+    let italic = add_font(BuiltinFont::HelveticaOblique)?;
+    let mono = add_font(BuiltinFont::Courier)?;
+
+    let mut cursor = PdfCursor {
+        layer: doc.get_page(page).get_layer(layer),
+        y: PAGE_HEIGHT_MM - MARGIN_MM,
+    };
+    let usable_width = PAGE_WIDTH_MM - 2.0 * MARGIN_MM;
+
+    for block in markdown_blocks(markdown) {
+        let (font, size, indent, spacing_before) = match block.style {
+            BlockStyle::Heading(1) => (&bold, 20.0, 0.0, 6.0),
+            BlockStyle::Heading(2) => (&bold, 16.0, 0.0, 5.0),
+            BlockStyle::Heading(3) => (&bold, 13.5, 0.0, 4.0),
+            BlockStyle::Heading(_) => (&bold, 12.0, 0.0, 3.0),
+            BlockStyle::Paragraph => (&regular, 11.0, 0.0, 2.5),
+            BlockStyle::ListItem(depth) => (&regular, 11.0, 5.0 + depth as f32 * 6.0, 1.0),
+            BlockStyle::Quote => (&italic, 11.0, 6.0, 2.5),
+            BlockStyle::Code => (&mono, 9.5, 4.0, 2.5),
+            BlockStyle::Rule => (&regular, 11.0, 0.0, 3.0),
+        };
+
+        let line_height = size * PT_TO_MM * 1.35;
+        // Average glyph width: ~0.5em for Helvetica, exactly 0.6em for Courier
+        let glyph_width = size
+            * PT_TO_MM
+            * if block.style == BlockStyle::Code {
+                0.6
+            } else {
+                0.5
+            };
+        let max_chars = ((usable_width - indent) / glyph_width) as usize;
+
+        let lines = match block.style {
+            BlockStyle::Code => wrap_code(&block.text, max_chars),
+            BlockStyle::Rule => vec!["\u{2014}".repeat(max_chars / 2)],
+            _ => wrap_text(block.text.trim(), max_chars),
+        };
+
+        cursor.y -= spacing_before;
+        for line in lines {
+            if cursor.y - line_height < MARGIN_MM {
+                let (new_page, new_layer) =
+                    doc.add_page(Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Layer 1");
+                cursor.layer = doc.get_page(new_page).get_layer(new_layer);
+                cursor.y = PAGE_HEIGHT_MM - MARGIN_MM;
+            }
+            cursor.y -= line_height;
+            cursor
+                .layer
+                .use_text(line, size, Mm(MARGIN_MM + indent), Mm(cursor.y), font);
+        }
+    }
+
+    doc.save_to_bytes()
+        .map_err(|e| format!("Failed to render PDF: {}", e))
+}
+
+// Resolve the export target: a directory gets a filename derived from the title
+pub fn resolve_target_path(target_path: &str, title: &str, extension: &str) -> PathBuf {
+    let target = PathBuf::from(target_path);
+    if !target.is_dir() {
+        return target;
+    }
+
+    let stem: String = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    target.join(format!("{}.{}", stem.trim_matches('_'), extension))
+}
+
+// Export a single note as HTML or PDF
+#[tauri::command]
+pub fn export_note<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab: usize,
+    format: ExportFormat,
+    target_path: String,
+) -> Result<String, String> {
+    let markdown = crate::storage_service::read_note(&app_handle, tab)?
+        .ok_or_else(|| format!("Note {} is empty", tab))?;
+
+    let title = note_title(tab, &markdown);
+    let path = resolve_target_path(&target_path, &title, format.extension());
+
+    let bytes = match format {
+        ExportFormat::Html => {
+            let assets_dir = crate::attachments::get_assets_dir(&app_handle);
+            render_html(&title, &markdown, Some(&assets_dir)).into_bytes()
+        }
+        ExportFormat::Pdf => {
+            if markdown.contains("](assets/") {
+                warn!("Images are not included in PDF exports");
+            }
+            render_pdf(&title, &markdown)?
+        }
+    };
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create export directory: {}", e))?;
+        }
+    }
+
+    crate::storage_service::write_atomic(&path, bytes)
+        .map_err(|e| format!("Failed to write export: {}", e))?;
+
+    info!(
+        "Exported note {} as {:?} to {}",
+        tab,
+        format,
+        path.display()
+    );
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+// Export every note, plus attachments and metadata, into a zip (if the target
+// ends in .zip) or a folder, as markdown, HTML or JSON
+#[tauri::command]
+pub fn export_all_notes<R: Runtime>(
+    app_handle: AppHandle<R>,
+    target_path: String,
+    format: ArchiveFormat,
+) -> Result<String, String> {
+    let target = PathBuf::from(&target_path);
+    let is_zip = matches!(target.extension(), Some(ext) if ext.eq_ignore_ascii_case("zip"));
+
+    let mut sink = if is_zip {
+        if let Some(parent) = target.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create export directory: {}", e))?;
+            }
+        }
+        let file = fs::File::create(&target)
+            .map_err(|e| format!("Failed to create export file: {}", e))?;
+        ExportSink::Zip(Box::new(ZipWriter::new(file)))
+    } else {
+        fs::create_dir_all(&target)
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+        ExportSink::Folder(target.clone())
+    };
+
+    let mut notes = Vec::new();
+    for tab in 0..7 {
+        let Some(content) = crate::storage_service::read_note(&app_handle, tab)? else {
+            continue;
+        };
+        let title = note_title(tab, &content);
+
+        let file = match format {
+            ArchiveFormat::Markdown => {
+                let file = format!("note_{}.md", tab);
+                sink.add(&file, content.as_bytes())?;
+                Some(file)
+            }
+            ArchiveFormat::Html => {
+                // Attachments are exported alongside, so relative links keep working
+                let file = format!("note_{}.html", tab);
+                sink.add(&file, render_html(&title, &content, None).as_bytes())?;
+                Some(file)
+            }
+            ArchiveFormat::Json => None,
+        };
+
+        notes.push(ExportedNote {
+            tab,
+            title,
+            file,
+            size: content.len(),
+            content: (format == ArchiveFormat::Json).then_some(content),
+        });
+    }
+
+    let mut attachments = Vec::new();
+    let assets_dir = crate::attachments::get_assets_dir(&app_handle);
+    if assets_dir.is_dir() {
+        let entries = fs::read_dir(&assets_dir)
+            .map_err(|e| format!("Failed to read assets directory: {}", e))?;
+        for entry in entries.filter_map(Result::ok) {
+            if !entry.path().is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let bytes = fs::read(entry.path())
+                .map_err(|e| format!("Failed to read attachment {}: {}", name, e))?;
+            sink.add(
+                &format!("{}/{}", crate::attachments::ASSETS_DIR_NAME, name),
+                &bytes,
+            )?;
+            attachments.push(name);
+        }
+    }
+
+    if format == ArchiveFormat::Html {
+        let links: String = notes
+            .iter()
+            .filter_map(|note| {
+                note.file.as_ref().map(|file| {
+                    format!(
+                        "<li><a href=\"{}\">{}</a></li>\n",
+                        file,
+                        escape_html(&note.title)
+                    )
+                })
+            })
+            .collect();
+        let index = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Jot notes</title>\n<style>{}</style>\n</head>\n<body>\n<h1>Jot notes</h1>\n<ul>\n{}</ul>\n</body>\n</html>\n",
+            HTML_STYLE, links
+        );
+        sink.add("index.html", index.as_bytes())?;
+    }
+
+    let metadata = ExportMetadata {
+        app_version: app_handle.package_info().version.to_string(),
+        exported_at: chrono::Local::now().to_rfc3339(),
+        format: format!("{:?}", format).to_lowercase(),
+        notes,
+        attachments,
+    };
+    let metadata_json = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize export metadata: {}", e))?;
+    let metadata_file = if format == ArchiveFormat::Json {
+        "notes.json"
+    } else {
+        "metadata.json"
+    };
+    sink.add(metadata_file, metadata_json.as_bytes())?;
+    sink.finish()?;
+
+    info!(
+        "Exported {} notes as {:?} to {}",
+        metadata.notes.len(),
+        format,
+        target.display()
+    );
+
+    Ok(target.to_string_lossy().to_string())
+}
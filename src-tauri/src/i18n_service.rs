@@ -0,0 +1,206 @@
+// i18n_service.rs - Translation catalogs for the UI, tray menu and notifications
+//
+// Catalogs ship with the app (locales/*.json, compiled in); users can override
+// or add languages with <lang>.json files in the app data locales/ directory.
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub const LOCALES_DIR_NAME: &str = "locales";
+const LANGUAGE_SETTING: &str = "app_language";
+const FALLBACK_LANGUAGE: &str = "en";
+
+const BUNDLED_CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.json")),
+    ("de", include_str!("../locales/de.json")),
+];
+
+fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("settings.json")
+}
+
+fn get_user_locales_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join(LOCALES_DIR_NAME)
+}
+
+// Normalize `de_AT.UTF-8` / `DE-at` to `de-AT`
+fn normalize_language(lang: &str) -> Option<String> {
+    let lang = lang.split('.').next().unwrap_or_default().replace('_', "-");
+    let mut parts = lang.split('-').filter(|part| !part.is_empty());
+
+    let language = parts.next()?.to_lowercase();
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    match parts.next() {
+        Some(region) if region.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            Some(format!("{}-{}", language, region.to_uppercase()))
+        }
+        Some(_) => None,
+        None => Some(language),
+    }
+}
+
+// Most specific first: de-AT -> de -> en
+fn fallback_chain(lang: &str) -> Vec<String> {
+    let mut chain = vec![lang.to_string()];
+    if let Some((base, _)) = lang.split_once('-') {
+        chain.push(base.to_string());
+    }
+    if !chain.iter().any(|l| l == FALLBACK_LANGUAGE) {
+        chain.push(FALLBACK_LANGUAGE.to_string());
+    }
+    chain
+}
+
+fn parse_catalog(source: &str, json: &str) -> HashMap<String, String> {
+    serde_json::from_str(json).unwrap_or_else(|e| {
+        warn!("Ignoring invalid translation catalog {}: {}", source, e);
+        HashMap::new()
+    })
+}
+
+fn bundled_catalog(lang: &str) -> Option<HashMap<String, String>> {
+    BUNDLED_CATALOGS
+        .iter()
+        .find(|(code, _)| *code == lang)
+        .map(|(code, json)| parse_catalog(code, json))
+}
+
+fn user_catalog<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    lang: &str,
+) -> Option<HashMap<String, String>> {
+    let path = get_user_locales_dir(app_handle).join(format!("{}.json", lang));
+    let json = fs::read_to_string(&path).ok()?;
+    Some(parse_catalog(&path.to_string_lossy(), &json))
+}
+
+fn has_catalog<R: Runtime>(app_handle: &AppHandle<R>, lang: &str) -> bool {
+    bundled_catalog(lang).is_some()
+        || get_user_locales_dir(app_handle)
+            .join(format!("{}.json", lang))
+            .exists()
+}
+
+// The language from settings, else the system locale, else English
+pub fn get_app_language<R: Runtime>(app_handle: &AppHandle<R>) -> String {
+    let configured = fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|settings| settings[LANGUAGE_SETTING].as_str().map(str::to_string));
+
+    configured
+        .or_else(sys_locale::get_locale)
+        .and_then(|lang| normalize_language(&lang))
+        .unwrap_or_else(|| FALLBACK_LANGUAGE.to_string())
+}
+
+// All strings for a language, with missing keys filled in along the fallback chain
+pub fn load_strings<R: Runtime>(app_handle: &AppHandle<R>, lang: &str) -> HashMap<String, String> {
+    let mut strings = HashMap::new();
+
+    // Least specific first, so more specific catalogs win
+    for code in fallback_chain(lang).iter().rev() {
+        for catalog in [bundled_catalog(code), user_catalog(app_handle, code)]
+            .into_iter()
+            .flatten()
+        {
+            strings.extend(catalog);
+        }
+    }
+
+    strings
+}
+
+// Translate a key in the app language, substituting `{name}` placeholders
+pub fn translate<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    key: &str,
+    args: &[(&str, &str)],
+) -> String {
+    let lang = get_app_language(app_handle);
+    let mut text = load_strings(app_handle, &lang)
+        .remove(key)
+        .unwrap_or_else(|| key.to_string());
+
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+
+    text
+}
+
+// Languages with a bundled or user-provided catalog
+#[tauri::command]
+pub fn list_languages<R: Runtime>(app_handle: AppHandle<R>) -> Vec<String> {
+    let mut languages: Vec<String> = BUNDLED_CATALOGS
+        .iter()
+        .map(|(code, _)| code.to_string())
+        .collect();
+
+    if let Ok(entries) = fs::read_dir(get_user_locales_dir(&app_handle)) {
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(code) = path
+                    .file_stem()
+                    .and_then(|stem| normalize_language(&stem.to_string_lossy()))
+                {
+                    languages.push(code);
+                }
+            }
+        }
+    }
+
+    languages.sort();
+    languages.dedup();
+    languages
+}
+
+// Get the strings for a language (default: the app language)
+#[tauri::command]
+pub fn get_locale_strings<R: Runtime>(
+    app_handle: AppHandle<R>,
+    lang: Option<String>,
+) -> Result<HashMap<String, String>, String> {
+    let lang = match lang {
+        Some(lang) => {
+            normalize_language(&lang).ok_or_else(|| format!("Invalid language code: {}", lang))?
+        }
+        None => get_app_language(&app_handle),
+    };
+
+    Ok(load_strings(&app_handle, &lang))
+}
+
+// Change the app language and tell the UI (and tray) to re-translate
+#[tauri::command]
+pub fn set_app_language<R: Runtime>(app_handle: AppHandle<R>, lang: String) -> Result<(), String> {
+    let lang =
+        normalize_language(&lang).ok_or_else(|| format!("Invalid language code: {}", lang))?;
+
+    // Needs its own catalog or one for its base language (de for de-AT)
+    let base = lang.split('-').next().unwrap_or(&lang);
+    if !has_catalog(&app_handle, &lang) && !has_catalog(&app_handle, base) {
+        return Err(format!("No translations available for {}", lang));
+    }
+
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        settings[LANGUAGE_SETTING] = serde_json::json!(lang);
+        Ok(())
+    })?;
+
+    info!("App language set to {}", lang);
+
+    if let Err(e) = crate::refresh_tray_menu(&app_handle) {
+        warn!("Failed to translate the tray menu: {}", e);
+    }
+
+    app_handle
+        .emit("language-changed", &lang)
+        .map_err(|e| format!("Failed to emit language-changed event: {}", e))
+}
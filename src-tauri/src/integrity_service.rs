@@ -0,0 +1,258 @@
+// integrity_service.rs - SHA-256 checksums of the note files, to catch
+// truncated or corrupted notes (e.g. after a crash or a failing disk)
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Runtime};
+
+use crate::storage_service;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteChecksum {
+    pub sha256: String,
+    pub size: u64,
+    pub recorded_at: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityStatus {
+    Ok,
+    // No checksum recorded yet
+    Unverified,
+    Missing,
+    Mismatch,
+    Unreadable,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteIntegrity {
+    pub tab_index: usize,
+    pub status: IntegrityStatus,
+    pub expected_size: Option<u64>,
+    pub actual_size: Option<u64>,
+    pub error: Option<String>,
+    // Latest backup that has this note, to restore from
+    pub backup: Option<String>,
+}
+
+// Serializes read-modify-write of checksums.json
+static CHECKSUMS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn get_checksums_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("checksums.json")
+}
+
+fn load_checksums<R: Runtime>(app_handle: &AppHandle<R>) -> HashMap<usize, NoteChecksum> {
+    fs::read_to_string(get_checksums_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn update_checksums<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    update: impl FnOnce(&mut HashMap<usize, NoteChecksum>),
+) {
+    let _guard = CHECKSUMS_LOCK.lock();
+
+    let mut checksums = load_checksums(app_handle);
+    update(&mut checksums);
+
+    let result = serde_json::to_string_pretty(&checksums)
+        .map_err(|e| e.to_string())
+        .and_then(|json_str| {
+            storage_service::write_atomic(get_checksums_path(app_handle), json_str)
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        // Not worth failing a save over; the next save records it again
+        error!("Failed to save note checksums: {}", e);
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Remember the checksum of the bytes just written for a note
+pub fn record_checksum<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize, bytes: &[u8]) {
+    let checksum = NoteChecksum {
+        sha256: sha256_hex(bytes),
+        size: bytes.len() as u64,
+        recorded_at: chrono::Local::now().to_rfc3339(),
+    };
+    update_checksums(app_handle, |checksums| {
+        checksums.insert(tab_index, checksum);
+    });
+}
+
+// Record whatever is on disk now, e.g. after an edit outside of Jot
+pub fn record_note_file<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize) {
+    match fs::read(storage_service::get_note_path(app_handle, tab_index)) {
+        Ok(bytes) => record_checksum(app_handle, tab_index, &bytes),
+        Err(_) => forget_checksum(app_handle, tab_index),
+    }
+}
+
+// The note was deleted on purpose
+pub fn forget_checksum<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize) {
+    update_checksums(app_handle, |checksums| {
+        checksums.remove(&tab_index);
+    });
+}
+
+fn backup_has_note(backup_path: &str, entry_name: &str) -> bool {
+    fs::File::open(backup_path)
+        .ok()
+        .and_then(|file| zip::ZipArchive::new(file).ok())
+        .is_some_and(|mut archive| archive.by_name(entry_name).is_ok())
+}
+
+fn latest_backup_with_note(app_handle: &AppHandle, tab_index: usize) -> Option<String> {
+    let entry_name = format!("note_{}.md", tab_index);
+    crate::backup_service::list_backups(app_handle.clone())
+        .ok()?
+        .into_iter()
+        .find(|backup| backup_has_note(backup, &entry_name))
+}
+
+fn check_note(
+    app_handle: &AppHandle,
+    tab_index: usize,
+    expected: Option<&NoteChecksum>,
+) -> NoteIntegrity {
+    let mut integrity = NoteIntegrity {
+        tab_index,
+        status: IntegrityStatus::Ok,
+        expected_size: expected.map(|checksum| checksum.size),
+        actual_size: None,
+        error: None,
+        backup: None,
+    };
+
+    let path = storage_service::get_note_path(app_handle, tab_index);
+    storage_service::ensure_materialized(&path);
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if expected.is_none() {
+                // No note and none expected: nothing to check
+                integrity.status = IntegrityStatus::Unverified;
+                return integrity;
+            }
+            integrity.status = IntegrityStatus::Missing;
+            integrity.backup = latest_backup_with_note(app_handle, tab_index);
+            return integrity;
+        }
+        Err(e) => {
+            integrity.status = IntegrityStatus::Unreadable;
+            integrity.error = Some(format!("Failed to read note: {}", e));
+            integrity.backup = latest_backup_with_note(app_handle, tab_index);
+            return integrity;
+        }
+    };
+    integrity.actual_size = Some(bytes.len() as u64);
+
+    if let Err(e) = storage_service::decode_note_bytes(app_handle, &bytes) {
+        integrity.status = IntegrityStatus::Unreadable;
+        integrity.error = Some(e);
+    } else {
+        integrity.status = match expected {
+            None => IntegrityStatus::Unverified,
+            Some(checksum) if checksum.sha256 == sha256_hex(&bytes) => IntegrityStatus::Ok,
+            Some(_) => IntegrityStatus::Mismatch,
+        };
+    }
+
+    if !matches!(
+        integrity.status,
+        IntegrityStatus::Ok | IntegrityStatus::Unverified
+    ) {
+        integrity.backup = latest_backup_with_note(app_handle, tab_index);
+    }
+
+    integrity
+}
+
+// Compare every note with the checksum recorded when it was last saved
+#[tauri::command]
+pub fn verify_notes_integrity(app_handle: AppHandle) -> Vec<NoteIntegrity> {
+    let checksums = load_checksums(&app_handle);
+
+    let results: Vec<NoteIntegrity> = (0..7)
+        .map(|tab_index| check_note(&app_handle, tab_index, checksums.get(&tab_index)))
+        .collect();
+
+    for result in &results {
+        if !matches!(
+            result.status,
+            IntegrityStatus::Ok | IntegrityStatus::Unverified
+        ) {
+            warn!(
+                "Note {} failed the integrity check: {:?}",
+                result.tab_index, result.status
+            );
+        }
+    }
+
+    results
+}
+
+// Restore one note from a backup (default: the latest one that has it)
+#[tauri::command]
+pub fn restore_note_from_backup(
+    app_handle: AppHandle,
+    tab_index: usize,
+    backup_path: Option<String>,
+) -> Result<String, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    let backup_path = match backup_path {
+        Some(path) => path,
+        None => latest_backup_with_note(&app_handle, tab_index)
+            .ok_or_else(|| format!("No backup contains note {}", tab_index + 1))?,
+    };
+
+    let file =
+        fs::File::open(&backup_path).map_err(|e| format!("Failed to open backup file: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read backup archive: {}", e))?;
+    let mut entry = archive
+        .by_name(&format!("note_{}.md", tab_index))
+        .map_err(|e| format!("Backup has no note {}: {}", tab_index + 1, e))?;
+
+    // The backup holds what was on disk, encrypted or not
+    let mut bytes = Vec::new();
+    entry
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read note from backup: {}", e))?;
+    let content = storage_service::decode_note_bytes(&app_handle, &bytes)?;
+
+    crate::autosave_service::discard_pending(tab_index);
+    storage_service::write_note_for_operation(
+        &app_handle,
+        tab_index,
+        &content,
+        "Restore from backup",
+    )?;
+
+    info!("Restored note {} from {}", tab_index, backup_path);
+
+    crate::note_events::emit_note_updated(&app_handle, tab_index, &content)?;
+
+    Ok(backup_path)
+}
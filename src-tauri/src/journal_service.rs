@@ -0,0 +1,452 @@
+// journal_service.rs - Undo journal of note saves, kept on disk so it
+// survives restarts (the editor's own undo stack is lost when the webview reloads)
+//
+// Each save records a reverse diff: the region of the saved content that
+// changed and the text it replaced. Undoing applies the latest one. The
+// note itself is the full snapshot the chain of diffs starts from.
+//
+// Journals are zstd-compressed unless `compress_history` is turned off, and
+// maintenance drops entries older than `history_retention_days`.
+use log::{error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Runtime};
+
+use crate::storage_service;
+
+const MAX_JOURNAL_ENTRIES: usize = 100;
+// Replaced text kept per note; the oldest entries go first
+const MAX_JOURNAL_BYTES: usize = 4 * 1024 * 1024;
+const DEFAULT_RETENTION_DAYS: i64 = 90;
+
+// Compressed journal layout: MAGIC | zstd frame of the JSON
+const COMPRESSED_MAGIC: &[u8] = b"JOTZSTD1\n";
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct JournalEntry {
+    saved_at: String,
+    // Byte range of the saved content that the save changed
+    start: usize,
+    replaced_len: usize,
+    // The text that was there before
+    previous: String,
+    // Hash of the saved content, so an undo only applies on top of it
+    content_sha256: String,
+    // Name of the operation that rewrote the note; None for the user's edits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    operation: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveHistoryEntry {
+    pub saved_at: String,
+    pub added_bytes: usize,
+    pub removed_bytes: usize,
+    pub operation: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryUsage {
+    pub tab: usize,
+    pub entries: usize,
+    // Size of the journal file on disk
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoneOperation {
+    pub operation: String,
+    pub content: String,
+}
+
+pub const JOURNAL_DIR_NAME: &str = "journal";
+
+// Serializes read-modify-write of the journal files
+static JOURNAL_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+pub fn get_journal_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join(JOURNAL_DIR_NAME)
+}
+
+fn get_journal_path<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize) -> PathBuf {
+    get_journal_dir(app_handle).join(format!("note_{}.json", tab_index))
+}
+
+// Hold off journal writes while the journal files are rewritten elsewhere
+pub fn lock_journals() -> std::sync::MutexGuard<'static, ()> {
+    JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    fs::read_to_string(crate::data_dir::get_app_data_dir(app_handle).join("settings.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Stored like the notes themselves, so it's encrypted when they are
+fn load_journal<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+) -> Result<Vec<JournalEntry>, String> {
+    let path = get_journal_path(app_handle, tab_index);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read undo journal: {}", e))?;
+    let mut json = storage_service::decode_bytes(app_handle, &bytes)?;
+    if let Some(compressed) = json.strip_prefix(COMPRESSED_MAGIC) {
+        json = zstd::decode_all(compressed)
+            .map_err(|e| format!("Failed to decompress undo journal: {}", e))?;
+    }
+    serde_json::from_slice(&json).map_err(|e| format!("Failed to parse undo journal: {}", e))
+}
+
+fn save_journal<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    journal: &[JournalEntry],
+) -> Result<(), String> {
+    let path = get_journal_path(app_handle, tab_index);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create journal directory: {}", e))?;
+    }
+
+    let mut json = serde_json::to_vec(journal)
+        .map_err(|e| format!("Failed to serialize undo journal: {}", e))?;
+    if read_settings(app_handle)["compress_history"]
+        .as_bool()
+        .unwrap_or(true)
+    {
+        let compressed = zstd::encode_all(json.as_slice(), COMPRESSION_LEVEL)
+            .map_err(|e| format!("Failed to compress undo journal: {}", e))?;
+        json = [COMPRESSED_MAGIC, &compressed].concat();
+    }
+    let bytes = storage_service::encode_bytes(app_handle, &json)?;
+    storage_service::write_atomic(path, bytes)
+        .map_err(|e| format!("Failed to save undo journal: {}", e))
+}
+
+// The changed region as (start, end in old, end in new), on char boundaries
+fn changed_region(old: &str, new: &str) -> (usize, usize, usize) {
+    let mut start = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    let max_suffix = old.len().min(new.len()) - start;
+    let mut suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) {
+        suffix -= 1;
+    }
+
+    (start, old.len() - suffix, new.len() - suffix)
+}
+
+// Record a save of `content` over `previous`, made by `operation` if it wasn't
+// the user's edit; called by storage_service
+pub fn record_save<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    previous: &str,
+    content: &str,
+    operation: Option<&str>,
+) {
+    if previous == content {
+        return;
+    }
+
+    let (start, old_end, new_end) = changed_region(previous, content);
+    let entry = JournalEntry {
+        saved_at: chrono::Local::now().to_rfc3339(),
+        start,
+        replaced_len: new_end - start,
+        previous: previous[start..old_end].to_string(),
+        content_sha256: crate::integrity_service::sha256_hex(content.as_bytes()),
+        operation: operation.map(str::to_string),
+    };
+
+    let _guard = JOURNAL_LOCK.lock();
+    let result = load_journal(app_handle, tab_index)
+        .or_else(|e| {
+            // A journal written with another passphrase can't be extended
+            error!("Starting a new undo journal for note {}: {}", tab_index, e);
+            Ok::<_, String>(Vec::new())
+        })
+        .and_then(|mut journal| {
+            journal.push(entry);
+
+            let mut total: usize = journal.iter().map(|entry| entry.previous.len()).sum();
+            while journal.len() > MAX_JOURNAL_ENTRIES
+                || (journal.len() > 1 && total > MAX_JOURNAL_BYTES)
+            {
+                total -= journal.remove(0).previous.len();
+            }
+
+            save_journal(app_handle, tab_index, &journal)
+        });
+    if let Err(e) = result {
+        // Not worth failing a save over
+        error!("Failed to record note {} in undo journal: {}", tab_index, e);
+    }
+}
+
+// Apply the latest entry of a note's journal, which has been popped off
+// `journal`; returns the restored content
+fn revert_entry<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    entry: &JournalEntry,
+    journal: &[JournalEntry],
+) -> Result<String, String> {
+    let current = storage_service::read_note(app_handle, tab_index)?.unwrap_or_default();
+    if crate::integrity_service::sha256_hex(current.as_bytes()) != entry.content_sha256 {
+        return Err(format!(
+            "Note {} was changed outside of Jot since its last save and can't be undone",
+            tab_index + 1
+        ));
+    }
+
+    let end = entry.start + entry.replaced_len;
+    let (Some(before), Some(after)) = (current.get(..entry.start), current.get(end..)) else {
+        return Err("Undo journal doesn't match the note".to_string());
+    };
+    let restored = format!("{}{}{}", before, entry.previous, after);
+
+    storage_service::write_note_unjournaled(app_handle, tab_index, &restored)?;
+    save_journal(app_handle, tab_index, journal)?;
+
+    info!(
+        "Undid the save of note {} from {}",
+        tab_index, entry.saved_at
+    );
+
+    crate::note_events::emit_note_updated(app_handle, tab_index, &restored)?;
+
+    Ok(restored)
+}
+
+// Drop journal entries older than the retention period and rewrite the
+// journals in the current format; returns (entries dropped, bytes saved).
+// Run by the maintenance job.
+pub fn compact_journals<R: Runtime>(app_handle: &AppHandle<R>) -> (usize, u64) {
+    let retention_days = read_settings(app_handle)["history_retention_days"]
+        .as_i64()
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    let cutoff = chrono::Local::now() - chrono::Duration::days(retention_days.max(1));
+
+    let mut dropped = 0;
+    let mut saved: u64 = 0;
+    let _guard = JOURNAL_LOCK.lock();
+    for tab_index in 0..7 {
+        let path = get_journal_path(app_handle, tab_index);
+        let Ok(size_before) = fs::metadata(&path).map(|metadata| metadata.len()) else {
+            continue;
+        };
+
+        let result = load_journal(app_handle, tab_index).and_then(|mut journal| {
+            let count = journal.len();
+            journal.retain(|entry| {
+                chrono::DateTime::parse_from_rfc3339(&entry.saved_at)
+                    .map_or(true, |saved_at| saved_at >= cutoff)
+            });
+            dropped += count - journal.len();
+
+            if journal.is_empty() {
+                fs::remove_file(&path).map_err(|e| format!("Failed to remove undo journal: {}", e))
+            } else {
+                save_journal(app_handle, tab_index, &journal)
+            }
+        });
+        match result {
+            Ok(()) => {
+                let size_after = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+                saved += size_before.saturating_sub(size_after);
+            }
+            Err(e) => error!(
+                "Failed to compact undo journal of note {}: {}",
+                tab_index, e
+            ),
+        }
+    }
+
+    info!(
+        "Compacted undo journals: {} entries dropped, {} bytes saved",
+        dropped, saved
+    );
+    (dropped, saved)
+}
+
+// Disk space used by each note's undo journal
+#[tauri::command]
+pub fn get_history_usage<R: Runtime>(app_handle: AppHandle<R>) -> Vec<HistoryUsage> {
+    (0..7)
+        .filter_map(|tab_index| {
+            let bytes = fs::metadata(get_journal_path(&app_handle, tab_index))
+                .ok()?
+                .len();
+            Some(HistoryUsage {
+                tab: tab_index,
+                entries: load_journal(&app_handle, tab_index).map_or(0, |journal| journal.len()),
+                bytes,
+            })
+        })
+        .collect()
+}
+
+// List the saves that can be undone for a note, newest first
+#[tauri::command]
+pub fn get_save_history<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+) -> Result<Vec<SaveHistoryEntry>, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    let journal = load_journal(&app_handle, tab_index)?;
+    Ok(journal
+        .iter()
+        .rev()
+        .map(|entry| SaveHistoryEntry {
+            saved_at: entry.saved_at.clone(),
+            added_bytes: entry.replaced_len,
+            removed_bytes: entry.previous.len(),
+            operation: entry.operation.clone(),
+        })
+        .collect())
+}
+
+// Revert a note's last save; returns the restored content
+#[tauri::command]
+pub fn undo_last_save<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+) -> Result<String, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    // Buffered edits are the latest save to undo
+    crate::autosave_service::flush_all(&app_handle)?;
+
+    let _guard = JOURNAL_LOCK.lock();
+    let mut journal = load_journal(&app_handle, tab_index)?;
+    let entry = journal
+        .pop()
+        .ok_or_else(|| format!("Note {} has no saves to undo", tab_index + 1))?;
+
+    revert_entry(&app_handle, tab_index, &entry, &journal)
+}
+
+// Revert the last operation that rewrote a note (formatting tables, toggling
+// a task, an import, ...), provided the note wasn't edited since
+#[tauri::command]
+pub fn undo_last_operation<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+) -> Result<UndoneOperation, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    crate::autosave_service::flush_all(&app_handle)?;
+
+    let _guard = JOURNAL_LOCK.lock();
+    let mut journal = load_journal(&app_handle, tab_index)?;
+    let Some(position) = journal.iter().rposition(|entry| entry.operation.is_some()) else {
+        return Err(format!("Note {} has no operations to undo", tab_index + 1));
+    };
+    let entry = journal.remove(position);
+    let operation = entry.operation.clone().unwrap_or_default();
+    if position != journal.len() {
+        // Undoing it would also throw away the edits made since
+        return Err(format!(
+            "Note {} was edited after \"{}\"; undo those edits first",
+            tab_index + 1,
+            operation
+        ));
+    }
+
+    let content = revert_entry(&app_handle, tab_index, &entry, &journal)?;
+    Ok(UndoneOperation { operation, content })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The region, after checking that it turns `old` into `new`
+    fn region(old: &str, new: &str) -> (usize, usize, usize) {
+        let (start, old_end, new_end) = changed_region(old, new);
+        let rebuilt = format!(
+            "{}{}{}",
+            &old[..start],
+            &new[start..new_end],
+            &old[old_end..]
+        );
+        assert_eq!(rebuilt, new);
+        (start, old_end, new_end)
+    }
+
+    #[test]
+    fn unchanged_text_has_an_empty_region() {
+        assert_eq!(region("abc", "abc"), (3, 3, 3));
+    }
+
+    #[test]
+    fn insert_and_delete_at_the_start() {
+        assert_eq!(region("bc", "abc"), (0, 0, 1));
+        assert_eq!(region("abc", "bc"), (0, 1, 0));
+    }
+
+    #[test]
+    fn insert_and_delete_at_the_end() {
+        assert_eq!(region("ab", "abc"), (2, 2, 3));
+        assert_eq!(region("abc", "ab"), (2, 3, 2));
+        assert_eq!(region("aaa", "aaaa"), (3, 3, 4));
+    }
+
+    #[test]
+    fn multibyte_insert_and_delete_at_the_ends() {
+        assert_eq!(region("ab", "abü"), (2, 2, 4));
+        assert_eq!(region("ü", "abü"), (0, 0, 2));
+        assert_eq!(region("😀x", "x"), (0, 4, 0));
+        assert_eq!(region("x😀", "x"), (1, 5, 1));
+    }
+
+    #[test]
+    fn shared_leading_bytes_of_a_char_stay_in_the_region() {
+        // é and ä both start with 0xC3
+        assert_eq!(region("héllo", "hällo"), (1, 3, 3));
+        // 本 and 木 share their first two bytes
+        assert_eq!(region("日本", "日木"), (3, 6, 6));
+    }
+
+    #[test]
+    fn shared_trailing_bytes_of_a_char_stay_in_the_region() {
+        // é and © both end with 0xA9
+        assert_eq!(region("é", "©"), (0, 2, 2));
+        assert_eq!(region("aé!", "a©!"), (1, 3, 3));
+    }
+}
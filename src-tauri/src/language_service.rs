@@ -1,13 +1,15 @@
 use crate::credential_manager;
-use log::{debug, error};
-use reqwest::Client;
+use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
-use tauri::{command, AppHandle, Manager};
+use std::time::Duration;
+use tauri::{command, AppHandle};
+use url::Url;
 
 const LT_DEFAULT_ENDPOINT: &str = "https://api.languagetoolplus.com/v2/check";
 const LANGUAGETOOL_SERVICE: &str = "jot.languagetool";
+const LT_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LtReplacement {
@@ -44,10 +46,7 @@ pub struct LanguageServicesConfig {
 }
 
 fn get_settings_path(app_handle: &AppHandle) -> PathBuf {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
     if !app_dir.exists() {
         fs::create_dir_all(&app_dir).expect("Failed to create app data directory");
     }
@@ -66,13 +65,6 @@ fn read_settings(app_handle: &AppHandle) -> serde_json::Value {
     serde_json::json!({})
 }
 
-fn write_settings(app_handle: &AppHandle, settings: &serde_json::Value) -> Result<(), String> {
-    let path = get_settings_path(app_handle);
-    let json_str = serde_json::to_string_pretty(settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-    fs::write(path, json_str).map_err(|e| format!("Failed to write settings: {}", e))
-}
-
 #[command]
 pub fn get_language_services_config(
     app_handle: AppHandle,
@@ -85,7 +77,7 @@ pub fn get_language_services_config(
     let username = settings["lt_username"].as_str().map(|s| s.to_string());
 
     // Indicate if API key is stored (without exposing the key itself)
-    let has_key = username.as_deref().map_or(false, |uname| {
+    let has_key = username.as_deref().is_some_and(|uname| {
         credential_manager::get_credential(LANGUAGETOOL_SERVICE, uname).is_ok()
     });
 
@@ -100,29 +92,148 @@ pub fn get_language_services_config(
     })
 }
 
+// Turn whatever the user pasted (`lt.example.com`, `https://lt.example.com/v2/`,
+// ...) into the server's base URL, without the /v2/check API path
+fn normalize_lt_base(input: &str) -> Result<Url, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Server URL is empty".to_string());
+    }
+
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+
+    let mut url =
+        Url::parse(&with_scheme).map_err(|e| format!("Invalid server URL {}: {}", input, e))?;
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!(
+            "Unsupported URL scheme {}: use http or https",
+            url.scheme()
+        ));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(format!("Server URL {} has no host name", input));
+    }
+
+    // Drop the API path if it was pasted along with the server
+    let mut path = url.path().trim_end_matches('/').to_string();
+    for suffix in ["/check", "/languages", "/v2"] {
+        if let Some(stripped) = path.strip_suffix(suffix) {
+            path = stripped.to_string();
+        }
+    }
+    url.set_path(&path);
+    url.set_query(None);
+    url.set_fragment(None);
+
+    Ok(url)
+}
+
+fn api_url(base: &Url, api_path: &str) -> Result<Url, String> {
+    let joined = format!("{}/v2/{}", base.as_str().trim_end_matches('/'), api_path);
+    Url::parse(&joined).map_err(|e| format!("Invalid server URL {}: {}", joined, e))
+}
+
+// The check endpoint for whatever the user entered, without contacting the server
+pub fn normalize_lt_endpoint(input: &str) -> Result<String, String> {
+    Ok(api_url(&normalize_lt_base(input)?, "check")?.to_string())
+}
+
+// Check that a LanguageTool server answers at the base URL, following
+// redirects to its canonical address
+async fn probe_lt_server(app_handle: &AppHandle, base: Url) -> Result<Url, String> {
+    let host = base.host_str().unwrap_or_default().to_string();
+    let probe_url = api_url(&base, "languages")?;
+
+    let client = crate::proxy_service::client_builder(app_handle)?
+        .timeout(LT_PROBE_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client.get(probe_url.clone()).send().await.map_err(|e| {
+        if e.is_timeout() {
+            format!(
+                "{} did not respond within {} seconds",
+                host,
+                LT_PROBE_TIMEOUT.as_secs()
+            )
+        } else if e.is_connect() {
+            format!("Could not connect to {}: {}", host, e)
+        } else {
+            format!("Request to {} failed: {}", host, e)
+        }
+    })?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(format!(
+            "{} doesn't look like a LanguageTool server (no /v2/languages)",
+            base
+        ));
+    }
+    if !status.is_success() {
+        return Err(format!("{} answered with {}", probe_url, status));
+    }
+
+    // Redirected (e.g. http -> https): derive the base from where we ended up
+    if response.url() != &probe_url {
+        debug!("LanguageTool server redirected to {}", response.url());
+        return normalize_lt_base(response.url().as_str());
+    }
+
+    Ok(base)
+}
+
+// Save the LanguageTool settings. A changed server is checked first; if it
+// can't be reached the settings are saved anyway and the returned warning
+// says why, so saving works offline.
 #[command]
-pub fn save_language_tool_config(
+pub async fn save_language_tool_config(
     app_handle: AppHandle,
     username: Option<String>,
     api_key: Option<String>,
     endpoint: Option<String>,
-) -> Result<(), String> {
-    let mut settings = read_settings(&app_handle);
+) -> Result<Option<String>, String> {
+    let _timing = crate::metrics_service::time_async_command("save_language_tool_config");
+    let current_endpoint = read_settings(&app_handle)["lt_endpoint"].clone();
+    let mut warning = None;
+    let mut new_endpoint = None;
 
     if let Some(ref ep) = endpoint {
-        settings["lt_endpoint"] = serde_json::json!(ep);
-    }
-    if let Some(ref uname) = username {
-        settings["lt_username"] = serde_json::json!(uname);
+        let base = normalize_lt_base(ep)?;
+        let check_url = api_url(&base, "check")?;
+        if current_endpoint.as_str() != Some(check_url.as_str()) {
+            let check_url = match probe_lt_server(&app_handle, base).await {
+                Ok(base) => api_url(&base, "check")?,
+                Err(e) => {
+                    warn!("Saving LanguageTool server without checking it: {}", e);
+                    warning = Some(e);
+                    check_url
+                }
+            };
+            new_endpoint = Some(check_url.to_string());
+        }
     }
 
-    write_settings(&app_handle, &settings)?;
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        if let Some(check_url) = new_endpoint {
+            settings["lt_endpoint"] = serde_json::json!(check_url);
+        }
+        if let Some(ref uname) = username {
+            settings["lt_username"] = serde_json::json!(uname);
+        }
+        Ok(())
+    })?;
 
     if let (Some(ref uname), Some(ref key)) = (&username, &api_key) {
         credential_manager::store_credential(LANGUAGETOOL_SERVICE, uname, key)?;
     }
 
-    Ok(())
+    Ok(warning)
 }
 
 #[command]
@@ -131,6 +242,7 @@ pub async fn check_grammar(
     text: String,
     language: String,
 ) -> Result<GrammarCheckResult, String> {
+    let _timing = crate::metrics_service::time_async_command("check_grammar");
     let settings = read_settings(&app_handle);
     let endpoint = settings["lt_endpoint"]
         .as_str()
@@ -138,7 +250,9 @@ pub async fn check_grammar(
         .to_string();
     let username = settings["lt_username"].as_str().map(|s| s.to_string());
 
-    let client = Client::new();
+    let client = crate::proxy_service::client_builder(&app_handle)?
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
     let mut params: Vec<(String, String)> = vec![
         ("text".to_string(), text),
         ("language".to_string(), language),
@@ -176,4 +290,3 @@ pub async fn check_grammar(
 
     Ok(result)
 }
-
@@ -1,20 +1,55 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #![allow(deprecated)]
 
-use log::info;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use tauri::menu::{MenuBuilder, MenuItemBuilder};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::menu::{Menu, MenuBuilder, MenuItemBuilder};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconEvent};
-use tauri::{App, AppHandle, Manager};
+use tauri::{App, AppHandle, Manager, Runtime};
 
+mod accessibility_service;
+mod archive_service;
+mod attachments;
+mod autosave_service;
 mod backup_service;
 mod credential_manager;
+mod data_dir;
+mod debug_console;
+mod duplicates_service;
+mod email_service;
+mod export_service;
+mod i18n_service;
+mod integrity_service;
+mod journal_service;
 mod language_service;
+mod link_check_service;
+mod links_service;
 mod logging;
+mod maintenance_service;
+mod metrics_service;
+mod migration_service;
+mod note_events;
+mod pandoc_service;
+mod proxy_service;
+mod publish_service;
+mod runbook_service;
+mod self_test_service;
+#[cfg(target_os = "linux")]
+mod shortcut_portal;
+mod shortcut_service;
+mod startup_service;
+mod storage_monitor;
 mod storage_service;
+mod structured_service;
+mod tables_service;
+mod tabs_service;
+mod takeout_service;
+mod tasks_service;
+mod theme_service;
+mod user_files_service;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
@@ -26,17 +61,10 @@ struct AppSettings {
     using_custom_storage: bool,
 }
 
-fn get_note_path(app_handle: &AppHandle, tab_index: usize) -> PathBuf {
-    storage_service::get_note_path(app_handle, tab_index)
-}
-
 // Get the path to the notes directory
 fn get_notes_dir(app_handle: &AppHandle) -> PathBuf {
     // In newer Tauri versions, we use app_handle.path() instead of path_resolver
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
 
     // Create the directory if it doesn't exist
     if !app_dir.exists() {
@@ -55,13 +83,29 @@ fn get_settings_path(app_handle: &AppHandle) -> PathBuf {
 #[tauri::command]
 fn save_settings(app_handle: AppHandle, settings: serde_json::Value) -> Result<(), String> {
     let path = get_settings_path(&app_handle);
+    data_dir::ensure_writable(&app_handle, &path)?;
+
+    theme_service::validate_appearance(&app_handle, &settings)?;
+    // Only a native confirmation may turn on running code blocks
+    if settings.get("code_execution_enabled").is_some() {
+        return Err(
+            "code_execution_enabled can only be changed in the code block settings".to_string(),
+        );
+    }
 
-    // Convert to pretty JSON string
-    let json_str = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-
-    // Write to file
-    fs::write(path, json_str).map_err(|e| format!("Failed to save settings: {}", e))
+    // Merge into the existing file, so keys managed by the backend (storage
+    // paths, encryption, shortcut, ...) survive a save from the UI
+    let updates = settings
+        .as_object()
+        .ok_or("Settings must be a JSON object")?;
+    data_dir::update_settings(&app_handle, |existing| {
+        if let Some(existing) = existing.as_object_mut() {
+            for (key, value) in updates {
+                existing.insert(key.clone(), value.clone());
+            }
+        }
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -74,9 +118,14 @@ fn load_settings(app_handle: AppHandle) -> Result<serde_json::Value, String> {
             .map_err(|e| format!("Failed to read settings file: {}", e))?;
 
         // Parse JSON
-        let settings: serde_json::Value = serde_json::from_str(&content)
+        let mut settings: serde_json::Value = serde_json::from_str(&content)
             .map_err(|e| format!("Failed to parse settings JSON: {}", e))?;
 
+        // Open the configured tab instead of the last active one
+        if let Some(tab) = startup_service::launch_tab(&settings) {
+            settings["activeTab"] = serde_json::json!(tab);
+        }
+
         Ok(settings)
     } else {
         // Return default settings if file doesn't exist
@@ -92,54 +141,64 @@ fn load_settings(app_handle: AppHandle) -> Result<serde_json::Value, String> {
 
 #[tauri::command]
 fn save_active_tab(app_handle: AppHandle, tab_index: usize) -> Result<(), String> {
-    // Load current settings
-    let settings_path = get_settings_path(&app_handle);
-    let mut settings: serde_json::Value = if settings_path.exists() {
-        let content = fs::read_to_string(&settings_path)
-            .map_err(|e| format!("Failed to read settings file: {}", e))?;
-
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse settings JSON: {}", e))?
-    } else {
-        serde_json::json!({
-            "theme": "light",
-            "fontSize": "medium"
-        })
-    };
-
-    // Update activeTab
-    settings["activeTab"] = serde_json::json!(tab_index);
-
-    // Save back to file
-    let json_str = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-
-    fs::write(settings_path, json_str).map_err(|e| format!("Failed to save settings: {}", e))
+    data_dir::update_settings(&app_handle, |settings| {
+        settings["activeTab"] = serde_json::json!(tab_index);
+        Ok(())
+    })
 }
 
 #[tauri::command]
 fn save_note(app_handle: AppHandle, tab_index: usize, content: String) -> Result<(), String> {
-    let path = get_note_path(&app_handle, tab_index);
+    // A runaway save loop in the editor gets buffered like autosave
+    if metrics_service::record_save_request(tab_index) {
+        return autosave_service::buffer_save(&app_handle, tab_index, content, true);
+    }
+
+    // A direct save supersedes anything still waiting for autosave
+    autosave_service::discard_pending(tab_index);
+    note_events::remember_editor_content(tab_index, &content);
+    let content = archive_service::prepare_save(&app_handle, tab_index, content)?;
+    storage_service::write_note(&app_handle, tab_index, &content)?;
+    publish_service::on_note_saved(&app_handle, tab_index);
+    Ok(())
+}
 
-    fs::write(path, content).map_err(|e| format!("Failed to save note: {}", e))
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LoadedNote {
+    tab_index: usize,
+    content: String,
+    pinned: bool,
+    // Where the editor was when the tab was last used
+    editor_state: Option<tabs_service::EditorState>,
 }
 
 #[tauri::command]
-fn load_notes(app_handle: AppHandle) -> Result<HashMap<usize, String>, String> {
-    let mut notes = HashMap::new();
+fn load_notes(app_handle: AppHandle) -> Result<Vec<LoadedNote>, String> {
+    let mut notes = Vec::new();
 
-    // Try to load notes for all 7 tabs
-    for tab_index in 0..7 {
-        let path = get_note_path(&app_handle, tab_index);
+    // Make sure buffered edits are on disk before reading
+    if let Err(e) = autosave_service::flush_all(&app_handle) {
+        warn!("Failed to save pending notes before loading: {}", e);
+    }
 
-        if path.exists() {
-            match fs::read_to_string(&path) {
-                Ok(content) => {
-                    notes.insert(tab_index, content);
-                }
-                Err(e) => {
-                    return Err(format!("Failed to load note {}: {}", tab_index, e));
-                }
+    // Load the notes of all 7 tabs, pinned ones first, in the saved tab order
+    let layout = tabs_service::load_layout(&app_handle);
+    let mut editor_states = tabs_service::load_editor_states(&app_handle);
+    for tab_index in layout.display_order() {
+        match storage_service::read_note(&app_handle, tab_index) {
+            Ok(Some(content)) => {
+                note_events::remember_editor_content(tab_index, &content);
+                notes.push(LoadedNote {
+                    tab_index,
+                    content,
+                    pinned: layout.pinned.contains(&tab_index),
+                    editor_state: editor_states.remove(&tab_index),
+                });
+            }
+            Ok(None) => {}
+            Err(e) => {
+                return Err(format!("Failed to load note {}: {}", tab_index, e));
             }
         }
     }
@@ -147,28 +206,161 @@ fn load_notes(app_handle: AppHandle) -> Result<HashMap<usize, String>, String> {
     Ok(notes)
 }
 
+// Re-read one note after it changed on disk (`note-updated-{i}`,
+// `storage-changed`), without reloading every tab. Buffered edits aren't
+// flushed: the file on disk is what changed.
+#[tauri::command]
+fn reload_note(app_handle: AppHandle, tab_index: usize) -> Result<Option<LoadedNote>, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    let layout = tabs_service::load_layout(&app_handle);
+    let content = storage_service::read_note(&app_handle, tab_index)
+        .map_err(|e| format!("Failed to reload note {}: {}", tab_index, e))?;
+
+    if let Some(content) = &content {
+        note_events::remember_editor_content(tab_index, content);
+    }
+    Ok(content.map(|content| LoadedNote {
+        tab_index,
+        content,
+        pinned: layout.pinned.contains(&tab_index),
+        editor_state: None,
+    }))
+}
+
+// Without a tray icon a hidden window could only come back through the
+// global shortcut, so it's minimized instead; closing it quits as usual
+static TRAY_AVAILABLE: AtomicBool = AtomicBool::new(true);
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WindowMode {
+    tray_available: bool,
+}
+
 #[tauri::command]
 fn close_window(app_handle: AppHandle) {
     info!("Closing the main window");
     if let Some(main_window) = app_handle.get_webview_window("main") {
-        main_window
-            .hide()
-            .unwrap_or_else(|e| info!("Failed to hide window: {}", e));
+        let result = if TRAY_AVAILABLE.load(Ordering::SeqCst) {
+            main_window.hide()
+        } else {
+            main_window.minimize()
+        };
+        result.unwrap_or_else(|e| info!("Failed to hide window: {}", e));
     }
 }
 
-fn configure_tray_menu(app: &App) -> Result<(), tauri::Error> {
-    let quit = MenuItemBuilder::new("Quit").id("quit").build(app)?;
-    let toggle = MenuItemBuilder::new("Toggle").id("toggle").build(app)?;
+// Quit through the event loop so pending autosaves are flushed
+#[tauri::command]
+fn quit_app(app_handle: AppHandle) {
+    info!("Quit requested from the UI");
+    app_handle.exit(0);
+}
 
-    let tray_menu = MenuBuilder::new(app).items(&[&toggle, &quit]).build()?;
+// Whether there's a tray icon to bring the window back
+#[tauri::command]
+fn get_window_mode() -> WindowMode {
+    WindowMode {
+        tray_available: TRAY_AVAILABLE.load(Ordering::SeqCst),
+    }
+}
+
+// GNOME only shows tray icons with the AppIndicator extension installed
+fn desktop_shows_tray() -> bool {
+    if !cfg!(target_os = "linux") {
+        return true;
+    }
+
+    let is_gnome = std::env::var("XDG_CURRENT_DESKTOP")
+        .is_ok_and(|desktop| desktop.to_uppercase().contains("GNOME"));
+    if !is_gnome {
+        return true;
+    }
+
+    let mut extension_dirs = vec![PathBuf::from("/usr/share/gnome-shell/extensions")];
+    if let Some(home) = std::env::var_os("HOME") {
+        extension_dirs.push(PathBuf::from(home).join(".local/share/gnome-shell/extensions"));
+    }
+
+    extension_dirs.iter().any(|dir| {
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries.filter_map(Result::ok).any(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .to_lowercase()
+                        .contains("appindicator")
+                })
+            })
+            .unwrap_or(false)
+    })
+}
+
+// "tray_mode" in settings.json: "auto" (default), "always" or "never"
+fn tray_wanted(app_handle: &AppHandle) -> bool {
+    let settings: serde_json::Value = fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    match settings["tray_mode"].as_str() {
+        Some("always") => true,
+        Some("never") => false,
+        _ => desktop_shows_tray(),
+    }
+}
+
+fn enable_no_tray_mode(app: &App) {
+    TRAY_AVAILABLE.store(false, Ordering::SeqCst);
+
+    if let Some(tray_icon) = app.tray_by_id("main") {
+        let _ = tray_icon.set_visible(false);
+    }
+
+    // The window starts hidden, and nothing else would show it
+    if let Some(window) = app.get_webview_window("main") {
+        window
+            .show()
+            .unwrap_or_else(|e| warn!("Failed to show window: {}", e));
+    }
+}
+
+// Build the tray menu in the current app language
+fn build_tray_menu<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Menu<R>, tauri::Error> {
+    let quit = MenuItemBuilder::new(i18n_service::translate(app_handle, "tray.quit", &[]))
+        .id("quit")
+        .build(app_handle)?;
+    let toggle = MenuItemBuilder::new(i18n_service::translate(app_handle, "tray.toggle", &[]))
+        .id("toggle")
+        .build(app_handle)?;
+
+    MenuBuilder::new(app_handle)
+        .items(&[&toggle, &quit])
+        .build()
+}
+
+// Rebuild the tray menu, e.g. after the app language changed
+fn refresh_tray_menu<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), tauri::Error> {
+    if let Some(tray_icon) = app_handle.tray_by_id("main") {
+        tray_icon.set_menu(Some(build_tray_menu(app_handle)?))?;
+    }
+    Ok(())
+}
+
+fn configure_tray_menu(app: &App) -> Result<(), tauri::Error> {
+    let tray_menu = build_tray_menu(app.handle())?;
 
     let tray_icon = app.tray_by_id("main").unwrap();
 
     tray_icon.set_menu(Some(tray_menu))?;
 
     tray_icon.on_menu_event(|app, event| match event.id.as_ref() {
-        "quit" => std::process::exit(0),
+        // Exit through the event loop so pending autosaves are flushed
+        "quit" => app.exit(0),
         "toggle" => toggle_window(app),
         _ => {}
     });
@@ -181,7 +373,7 @@ fn configure_tray_menu(app: &App) -> Result<(), tauri::Error> {
         } = event
         {
             let app = tray.app_handle();
-            toggle_window(&app.app_handle());
+            toggle_window(app.app_handle());
         }
     });
 
@@ -190,20 +382,33 @@ fn configure_tray_menu(app: &App) -> Result<(), tauri::Error> {
 
 fn toggle_window(app: &AppHandle) {
     let window = app.get_webview_window("main").unwrap();
-    if window.is_visible().unwrap() {
-        window.hide().unwrap();
+    let minimized = window.is_minimized().unwrap_or(false);
+    if window.is_visible().unwrap() && !minimized {
+        if TRAY_AVAILABLE.load(Ordering::SeqCst) {
+            window.hide().unwrap();
+        } else {
+            window.minimize().unwrap();
+        }
     } else {
+        if minimized {
+            window.unminimize().unwrap();
+        }
         window.show().unwrap();
         window.set_focus().unwrap();
+        startup_service::on_window_shown(app);
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let context = tauri::generate_context!();
-    let _tauri_app = tauri::Builder::default()
+    if std::env::args().any(|arg| arg == self_test_service::SELF_TEST_FLAG) {
+        self_test_service::run_from_command_line(context);
+    }
+    tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
             // Windows: DWM folgt System-Theme nicht automatisch → explizit setzen
             #[cfg(target_os = "windows")]
@@ -229,42 +434,68 @@ pub fn run() {
             }
             #[cfg(desktop)]
             {
-                use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+                use tauri_plugin_global_shortcut::ShortcutState;
 
-                let ctrl_j_shortcut = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyJ);
                 let app_handle = app.handle();
                 app_handle.plugin(
                     tauri_plugin_global_shortcut::Builder::new()
-                    .with_handler({
-                        let app_handle = app_handle.clone();
-                        move |_app, shortcut, event| {
-                            if shortcut == &ctrl_j_shortcut {
-                                if event.state() == ShortcutState::Released {
-                                   toggle_window(&app_handle);
-                                }
-                            }
+                    .with_handler(|app, _shortcut, event| {
+                        // The show/hide shortcut is the only one registered
+                        if event.state() == ShortcutState::Released {
+                            toggle_window(app);
                         }
                     })
                     .build(),
                 )?;
-
-                app_handle.global_shortcut().register(ctrl_j_shortcut)?;
             }
-            logging::init_logger(app.app_handle())?;
+            data_dir::init(app.handle());
+            if let Err(e) = logging::init_logger(app.app_handle()) {
+                // Keep running without a log file, e.g. on a read-only data directory
+                eprintln!("Failed to initialize logging: {}", e);
+            }
             info!("Jot application starting up");
-            configure_tray_menu(app).unwrap();
+            if data_dir::is_read_only() {
+                warn!("Data directory is read-only; changes are disabled");
+            } else {
+                migration_service::run_startup_migrations(app.handle());
+            }
+            if let Err(e) = storage_service::start_note_watcher(app.handle()) {
+                warn!("External note edits won't be picked up: {}", e);
+            }
+            #[cfg(desktop)]
+            shortcut_service::register_toggle_shortcut(app.handle());
+            if !tray_wanted(app.handle()) {
+                info!("No tray on this desktop; the window is minimized instead of hidden");
+                enable_no_tray_mode(app);
+            } else if app.tray_by_id("main").is_none() {
+                warn!("Tray icon could not be created; the window is minimized instead of hidden");
+                enable_no_tray_mode(app);
+            } else if let Err(e) = configure_tray_menu(app) {
+                warn!("Failed to set up the tray icon: {}", e);
+                enable_no_tray_mode(app);
+            }
+            maintenance_service::start_maintenance_scheduler(app.handle().clone());
+            user_files_service::report_user_files(app.handle());
+            storage_monitor::start_storage_monitor(app.handle().clone());
+            archive_service::start_rollover_scheduler(app.handle().clone());
+            email_service::start_digest_scheduler(app.handle().clone());
+            autosave_service::start_flush_scheduler(app.handle().clone());
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(metrics_service::with_timing(tauri::generate_handler![
             save_note,
             save_settings,
             load_settings,
             save_active_tab,
             load_notes,
+            reload_note,
             close_window,
+            quit_app,
+            get_window_mode,
             // Backup service commands
             backup_service::create_backup,
             backup_service::list_backups,
+            backup_service::plan_backup_restore,
             backup_service::restore_backup,
             backup_service::delete_backup,
             backup_service::count_backups,
@@ -287,8 +518,111 @@ pub fn run() {
             language_service::save_language_tool_config,
             language_service::check_grammar,
             storage_service::get_storage_settings,
-            storage_service::set_storage_path
-        ])
-        .run(context)
-        .expect("error while running tauri application");
+            storage_service::set_storage_path,
+            storage_service::get_tab_storage_paths,
+            storage_service::set_tab_storage_path,
+            storage_monitor::get_storage_health,
+            storage_monitor::exclude_storage_from_indexing,
+            storage_service::get_encryption_status,
+            storage_service::enable_note_encryption,
+            storage_service::disable_note_encryption,
+            storage_service::list_markdown_files,
+            storage_service::preview_markdown_import,
+            storage_service::import_markdown_folder,
+            storage_service::set_note_dirty,
+            storage_service::get_note_metadata,
+            storage_service::load_note,
+            storage_service::load_note_chunk,
+            storage_service::duplicate_note,
+            storage_service::merge_notes,
+            links_service::resolve_links,
+            takeout_service::export_everything,
+            takeout_service::preview_import,
+            takeout_service::import_everything,
+            attachments::save_attachment,
+            attachments::list_attachments,
+            attachments::delete_attachment,
+            export_service::export_note,
+            export_service::export_all_notes,
+            publish_service::get_publish_config,
+            publish_service::publish_site,
+            pandoc_service::get_pandoc_status,
+            pandoc_service::export_with_pandoc,
+            debug_console::list_debug_commands,
+            debug_console::invoke_debug,
+            maintenance_service::run_maintenance,
+            maintenance_service::get_maintenance_report,
+            metrics_service::get_metrics,
+            metrics_service::get_write_metrics,
+            shortcut_service::get_active_shortcut,
+            shortcut_service::get_shortcut_status,
+            autosave_service::save_note_debounced,
+            autosave_service::flush_notes,
+            theme_service::list_themes,
+            theme_service::get_theme,
+            theme_service::install_theme,
+            i18n_service::list_languages,
+            i18n_service::get_locale_strings,
+            i18n_service::set_app_language,
+            archive_service::set_daily_note_tab,
+            archive_service::list_archive,
+            archive_service::load_archive_entry,
+            migration_service::get_migration_status,
+            migration_service::plan_migrations,
+            data_dir::get_data_dir_status,
+            data_dir::relocate_data_dir,
+            tabs_service::get_tab_layout,
+            tabs_service::set_tab_order,
+            tabs_service::pin_note,
+            tabs_service::save_editor_state,
+            integrity_service::verify_notes_integrity,
+            integrity_service::restore_note_from_backup,
+            journal_service::get_save_history,
+            journal_service::undo_last_save,
+            journal_service::undo_last_operation,
+            journal_service::get_history_usage,
+            tasks_service::get_tasks,
+            tasks_service::toggle_task,
+            structured_service::get_structured_entries,
+            structured_service::upsert_entry,
+            email_service::get_smtp_settings,
+            email_service::set_smtp_settings,
+            email_service::get_email_digest,
+            email_service::set_email_digest,
+            email_service::send_note_email,
+            runbook_service::set_code_execution_enabled,
+            runbook_service::run_code_block,
+            tables_service::format_markdown_tables,
+            tables_service::csv_to_markdown_table,
+            link_check_service::check_links,
+            duplicates_service::find_duplicates,
+            autosave_service::get_save_policy,
+            autosave_service::set_save_policy,
+            accessibility_service::get_accessibility_preferences,
+            proxy_service::get_proxy_settings,
+            proxy_service::set_proxy_settings,
+            user_files_service::validate_user_files,
+            startup_service::get_startup_behavior,
+            startup_service::set_startup_behavior,
+            self_test_service::run_self_test
+        ]))
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            match event {
+                tauri::RunEvent::Exit => {
+                    autosave_service::flush_on_exit(app_handle);
+                    tabs_service::flush_editor_state(app_handle);
+                }
+                tauri::RunEvent::WindowEvent {
+                    event: tauri::WindowEvent::Focused(false),
+                    ..
+                } => autosave_service::on_window_blur(app_handle),
+                tauri::RunEvent::WindowEvent {
+                    event: tauri::WindowEvent::Focused(true),
+                    ..
+                } => accessibility_service::refresh_preferences(app_handle),
+                _ => {}
+            }
+        });
 }
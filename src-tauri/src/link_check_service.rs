@@ -0,0 +1,310 @@
+// link_check_service.rs - Find dead and permanently redirected web links in notes
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use url::Url;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_CONCURRENT_CHECKS: usize = 8;
+const MAX_REDIRECTS: usize = 5;
+// Results are reused for a while, so checking again doesn't hammer the same hosts
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkStatus {
+    Ok,
+    // Moved permanently; `suggestion` has the new address
+    Redirected,
+    // Gone (404/410) or the host doesn't exist
+    Dead,
+    // Server errors, timeouts and other answers worth a second look
+    Failing,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkCheck {
+    pub status: LinkStatus,
+    pub http_status: Option<u16>,
+    pub suggestion: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkProblem {
+    pub tab: usize,
+    pub line: usize,
+    pub url: String,
+    #[serde(flatten)]
+    pub check: LinkCheck,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkReport {
+    pub checked: usize,
+    pub problems: Vec<LinkProblem>,
+}
+
+static LINK_CACHE: Lazy<Mutex<HashMap<String, (Instant, LinkCheck)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// The end of a URL found in text: stop at whitespace and markdown/HTML
+// delimiters, keep balanced parentheses (e.g. Wikipedia links) and drop
+// trailing punctuation
+fn url_end(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut end = text.len();
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            ')' | '<' | '>' | '"' | '\'' | '`' | ']' => {
+                end = index;
+                break;
+            }
+            c if c.is_whitespace() => {
+                end = index;
+                break;
+            }
+            _ => {}
+        }
+    }
+    text[..end]
+        .trim_end_matches(['.', ',', ';', ':', '!', '?', '*', '_'])
+        .len()
+}
+
+// Web links in a note as (line, url), skipping fenced code blocks
+fn extract_urls(content: &str) -> Vec<(usize, String)> {
+    let mut urls = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = ["https://", "http://"]
+            .iter()
+            .filter_map(|scheme| rest.find(scheme))
+            .min()
+        {
+            let candidate = &rest[start..];
+            let end = url_end(candidate);
+            if Url::parse(&candidate[..end]).is_ok_and(|url| url.host_str().is_some()) {
+                urls.push((line_index + 1, candidate[..end].to_string()));
+            }
+            rest = &candidate[end.max(1)..];
+        }
+    }
+
+    urls
+}
+
+fn check_result(status: LinkStatus, http_status: Option<StatusCode>) -> LinkCheck {
+    LinkCheck {
+        status,
+        http_status: http_status.map(|status| status.as_u16()),
+        suggestion: None,
+        error: None,
+    }
+}
+
+// HEAD first; some servers only answer GET properly
+async fn request(client: &Client, url: &Url) -> Result<reqwest::Response, reqwest::Error> {
+    let response = client.head(url.clone()).send().await?;
+    if matches!(
+        response.status(),
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::FORBIDDEN | StatusCode::NOT_IMPLEMENTED
+    ) {
+        return client.get(url.clone()).send().await;
+    }
+    Ok(response)
+}
+
+// Follow redirects by hand, so permanent moves can be told from temporary
+// ones (login pages, load balancers) that shouldn't be "fixed"
+async fn check_url(client: &Client, original: &str) -> LinkCheck {
+    let Ok(mut url) = Url::parse(original) else {
+        return LinkCheck {
+            error: Some("Invalid URL".to_string()),
+            ..check_result(LinkStatus::Dead, None)
+        };
+    };
+    let mut permanent = true;
+    let mut redirected = false;
+
+    for _ in 0..=MAX_REDIRECTS {
+        let response = match request(client, &url).await {
+            Ok(response) => response,
+            Err(e) => {
+                // A host that doesn't resolve or refuses connections is dead;
+                // a slow one may just be having a bad day
+                let status = if e.is_connect() {
+                    LinkStatus::Dead
+                } else {
+                    LinkStatus::Failing
+                };
+                return LinkCheck {
+                    error: Some(e.to_string()),
+                    ..check_result(status, None)
+                };
+            }
+        };
+
+        let status = response.status();
+        if status.is_redirection() {
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|location| url.join(location).ok());
+            let Some(next) = location else {
+                return check_result(LinkStatus::Failing, Some(status));
+            };
+            permanent &= matches!(
+                status,
+                StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+            );
+            redirected = true;
+            url = next;
+            continue;
+        }
+
+        return match status {
+            status if status.is_success() && redirected && permanent => LinkCheck {
+                suggestion: Some(url.to_string()),
+                ..check_result(LinkStatus::Redirected, Some(status))
+            },
+            status if status.is_success() => check_result(LinkStatus::Ok, Some(status)),
+            StatusCode::NOT_FOUND | StatusCode::GONE => {
+                check_result(LinkStatus::Dead, Some(status))
+            }
+            // Rate limits and bot walls say nothing about the link itself
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                check_result(LinkStatus::Ok, Some(status))
+            }
+            status => check_result(LinkStatus::Failing, Some(status)),
+        };
+    }
+
+    LinkCheck {
+        error: Some(format!("More than {} redirects", MAX_REDIRECTS)),
+        ..check_result(LinkStatus::Failing, None)
+    }
+}
+
+fn cached_check(url: &str) -> Option<LinkCheck> {
+    let cache = LINK_CACHE.lock().ok()?;
+    cache
+        .get(url)
+        .filter(|(checked_at, _)| checked_at.elapsed() < CACHE_TTL)
+        .map(|(_, check)| check.clone())
+}
+
+// Check the web links of one note, or of all notes; returns the broken and
+// moved ones with suggested replacements
+#[tauri::command]
+pub async fn check_links<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: Option<usize>,
+) -> Result<LinkReport, String> {
+    let _timing = crate::metrics_service::time_async_command("check_links");
+    let tabs: Vec<usize> = match tab_index {
+        Some(tab) if tab >= 7 => return Err(format!("Invalid tab index: {}", tab)),
+        Some(tab) => vec![tab],
+        None => (0..7).collect(),
+    };
+
+    let mut links = Vec::new();
+    for tab in tabs {
+        if let Some(content) = crate::storage_service::read_note(&app_handle, tab)? {
+            links.extend(
+                extract_urls(&content)
+                    .into_iter()
+                    .map(|(line, url)| (tab, line, url)),
+            );
+        }
+    }
+
+    let client = crate::proxy_service::client_builder(&app_handle)?
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .user_agent(format!("Jot/{} link checker", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    // Each distinct URL is checked once, a few at a time
+    let mut results: HashMap<String, LinkCheck> = HashMap::new();
+    let mut pending = JoinSet::new();
+    let limit = Arc::new(Semaphore::new(MAX_CONCURRENT_CHECKS));
+    for (_, _, url) in &links {
+        if results.contains_key(url) {
+            continue;
+        }
+        if let Some(check) = cached_check(url) {
+            results.insert(url.clone(), check);
+            continue;
+        }
+        // Placeholder, so duplicates aren't queued twice
+        results.insert(url.clone(), check_result(LinkStatus::Ok, None));
+
+        let client = client.clone();
+        let limit = limit.clone();
+        let url = url.clone();
+        pending.spawn(async move {
+            let _permit = limit.acquire_owned().await;
+            let check = check_url(&client, &url).await;
+            (url, check)
+        });
+    }
+
+    while let Some(joined) = pending.join_next().await {
+        let (url, check) = joined.map_err(|e| format!("Link check failed: {}", e))?;
+        debug!("Checked {}: {:?}", url, check.status);
+        if let Ok(mut cache) = LINK_CACHE.lock() {
+            cache.insert(url.clone(), (Instant::now(), check.clone()));
+        }
+        results.insert(url, check);
+    }
+
+    let problems: Vec<LinkProblem> = links
+        .into_iter()
+        .filter_map(|(tab, line, url)| {
+            let check = results.get(&url)?.clone();
+            (check.status != LinkStatus::Ok).then_some(LinkProblem {
+                tab,
+                line,
+                url,
+                check,
+            })
+        })
+        .collect();
+
+    info!(
+        "Checked {} links, {} need attention",
+        results.len(),
+        problems.len()
+    );
+
+    Ok(LinkReport {
+        checked: results.len(),
+        problems,
+    })
+}
@@ -0,0 +1,176 @@
+// links_service.rs - [[Wiki-links]] between tabs and backlinks
+use serde::Serialize;
+use tauri::{AppHandle, Runtime};
+
+use crate::export_service::note_title;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WikiLink {
+    pub target: String,
+    pub alias: Option<String>,
+    pub heading: Option<String>,
+    pub line: usize,
+    pub tab: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Backlink {
+    pub tab: usize,
+    pub title: String,
+    pub lines: Vec<usize>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkResolution {
+    pub title: String,
+    pub links: Vec<WikiLink>,
+    pub backlinks: Vec<Backlink>,
+}
+
+// Find `[[Target]]`, `[[Target|alias]]` and `[[Target#Heading]]` links,
+// skipping fenced code blocks
+fn parse_wiki_links(content: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_index, line) in crate::storage_service::split_frontmatter(content)
+        .1
+        .lines()
+        .enumerate()
+    {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            let inner = &after[..end];
+            rest = &after[end + 2..];
+
+            let (target, alias) = match inner.split_once('|') {
+                Some((target, alias)) => (target, Some(alias.trim().to_string())),
+                None => (inner, None),
+            };
+            let (target, heading) = match target.split_once('#') {
+                Some((target, heading)) => (target, Some(heading.trim().to_string())),
+                None => (target, None),
+            };
+
+            let target = target.trim();
+            if target.is_empty() {
+                continue;
+            }
+
+            links.push(WikiLink {
+                target: target.to_string(),
+                alias,
+                heading,
+                line: line_index + 1,
+                tab: None,
+            });
+        }
+    }
+
+    links
+}
+
+// Names each tab can be linked by: its title (frontmatter title or first
+// heading), plus "Note N" and "Tab N"
+struct TitleIndex {
+    titles: Vec<(usize, String)>,
+}
+
+impl TitleIndex {
+    fn resolve(&self, target: &str) -> Option<usize> {
+        let target = target.trim().to_lowercase();
+
+        self.titles
+            .iter()
+            .find(|(_, title)| title.to_lowercase() == target)
+            .map(|(tab, _)| *tab)
+            .or_else(|| {
+                (0..7).find(|tab| {
+                    target == format!("note {}", tab + 1) || target == format!("tab {}", tab + 1)
+                })
+            })
+    }
+
+    fn title(&self, tab: usize) -> String {
+        self.titles
+            .iter()
+            .find(|(index, _)| *index == tab)
+            .map(|(_, title)| title.clone())
+            .unwrap_or_else(|| format!("Note {}", tab + 1))
+    }
+}
+
+// Resolve the wiki-links in a note to tabs, and list the notes linking to it
+#[tauri::command]
+pub fn resolve_links<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab: usize,
+) -> Result<LinkResolution, String> {
+    let mut notes = Vec::new();
+    for index in 0..7 {
+        if let Some(content) = crate::storage_service::read_note(&app_handle, index)? {
+            notes.push((index, content));
+        }
+    }
+
+    let index = TitleIndex {
+        titles: notes
+            .iter()
+            .map(|(tab, content)| (*tab, note_title(*tab, content)))
+            .collect(),
+    };
+
+    let links = notes
+        .iter()
+        .find(|(index, _)| *index == tab)
+        .map(|(_, content)| {
+            parse_wiki_links(content)
+                .into_iter()
+                .map(|link| WikiLink {
+                    tab: index.resolve(&link.target),
+                    ..link
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let backlinks = notes
+        .iter()
+        .filter(|(source, _)| *source != tab)
+        .filter_map(|(source, content)| {
+            let lines: Vec<usize> = parse_wiki_links(content)
+                .into_iter()
+                .filter(|link| index.resolve(&link.target) == Some(tab))
+                .map(|link| link.line)
+                .collect();
+
+            (!lines.is_empty()).then(|| Backlink {
+                tab: *source,
+                title: index.title(*source),
+                lines,
+            })
+        })
+        .collect();
+
+    Ok(LinkResolution {
+        title: index.title(tab),
+        links,
+        backlinks,
+    })
+}
@@ -8,7 +8,7 @@ use std::path::PathBuf;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Manager};
+use tauri::AppHandle;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub enum LogLevel {
@@ -130,10 +130,7 @@ pub static LOGGER: once_cell::sync::OnceCell<Arc<FileLogger>> = once_cell::sync:
 
 // Initialize the logger
 pub fn init_logger(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
 
     let log_dir = app_dir.join("logs");
     create_dir_all(&log_dir)?;
@@ -163,10 +160,7 @@ pub fn init_logger(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Err
 
 // Helper function to get the log directory
 pub fn get_log_dir(app_handle: &AppHandle) -> PathBuf {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
     app_dir.join("logs")
 }
 
@@ -248,10 +242,7 @@ pub fn list_log_files(app_handle: AppHandle) -> Result<Vec<String>, String> {
 
 #[tauri::command]
 pub fn log_from_frontend(app_handle: AppHandle, logs: Vec<String>) -> Result<(), String> {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(&app_handle);
 
     let log_dir = app_dir.join("logs");
     let frontend_log_path = log_dir.join("frontend.log");
@@ -0,0 +1,250 @@
+// maintenance_service.rs - Periodic integrity checks and housekeeping
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+// Run at most once a week; check whether it's due every hour
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Don't compete with startup work
+const STARTUP_DELAY: Duration = Duration::from_secs(5 * 60);
+
+const DEFAULT_BACKUP_KEEP_COUNT: usize = 10;
+const DEFAULT_LOG_RETENTION_DAYS: u64 = 30;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MaintenanceReport {
+    pub started_at: String,
+    pub duration_ms: u128,
+    pub notes_checked: usize,
+    pub note_errors: Vec<String>,
+    pub latest_backup: Option<String>,
+    pub backup_ok: Option<bool>,
+    pub backup_error: Option<String>,
+    pub backups_pruned: usize,
+    pub logs_pruned: usize,
+    #[serde(default)]
+    pub history_entries_pruned: usize,
+    #[serde(default)]
+    pub history_bytes_saved: u64,
+}
+
+impl MaintenanceReport {
+    pub fn is_healthy(&self) -> bool {
+        self.note_errors.is_empty() && self.backup_ok != Some(false)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct MaintenanceState {
+    last_run: Option<u64>,
+    last_report: Option<MaintenanceReport>,
+}
+
+fn get_state_path(app_handle: &AppHandle) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("maintenance.json")
+}
+
+fn load_state(app_handle: &AppHandle) -> MaintenanceState {
+    fs::read_to_string(get_state_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(app_handle: &AppHandle, state: &MaintenanceState) -> Result<(), String> {
+    let json_str = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize maintenance state: {}", e))?;
+    crate::storage_service::write_atomic(get_state_path(app_handle), json_str)
+        .map_err(|e| format!("Failed to save maintenance state: {}", e))
+}
+
+// Retention policy from settings.json, with defaults
+fn load_policy(app_handle: &AppHandle) -> (usize, u64) {
+    let settings_path = crate::data_dir::get_app_data_dir(app_handle).join("settings.json");
+    let settings: serde_json::Value = fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let keep_count = settings["maintenance_backup_keep_count"]
+        .as_u64()
+        .map(|count| count as usize)
+        .unwrap_or(DEFAULT_BACKUP_KEEP_COUNT);
+    let retention_days = settings["maintenance_log_retention_days"]
+        .as_u64()
+        .unwrap_or(DEFAULT_LOG_RETENTION_DAYS);
+
+    (keep_count, retention_days)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Read every entry of a backup archive; the zip reader verifies CRCs as it goes
+fn verify_backup_archive(path: &str) -> Result<(), String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read backup archive: {}", e))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to access backup entry {}: {}", i, e))?;
+        let name = entry.name().to_string();
+        std::io::copy(&mut entry, &mut std::io::sink())
+            .map_err(|e| format!("Backup entry {} is corrupted: {}", name, e))?;
+    }
+
+    Ok(())
+}
+
+// Delete log files older than the retention period
+fn prune_logs(app_handle: &AppHandle, retention_days: u64) -> usize {
+    let cutoff = SystemTime::now() - Duration::from_secs(retention_days * 24 * 60 * 60);
+
+    crate::logging::get_log_files(app_handle)
+        .into_iter()
+        .filter(|path| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified < cutoff)
+        })
+        .filter(|path| fs::remove_file(path).is_ok())
+        .count()
+}
+
+fn check_notes(app_handle: &AppHandle, report: &mut MaintenanceReport) {
+    for tab_index in 0..7 {
+        let path = crate::storage_service::get_note_path(app_handle, tab_index);
+        if !path.exists() {
+            continue;
+        }
+
+        report.notes_checked += 1;
+        let result = fs::File::open(&path)
+            .and_then(|mut file| {
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes).map(|_| bytes)
+            })
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| crate::storage_service::decode_note_bytes(app_handle, &bytes));
+
+        if let Err(e) = result {
+            report
+                .note_errors
+                .push(format!("Note {}: {}", tab_index, e));
+        }
+    }
+}
+
+// Run all maintenance tasks and persist the report
+pub fn run_maintenance_tasks(app_handle: &AppHandle) -> MaintenanceReport {
+    let started = std::time::Instant::now();
+    let mut report = MaintenanceReport {
+        started_at: chrono::Local::now().to_rfc3339(),
+        ..Default::default()
+    };
+    let (keep_count, retention_days) = load_policy(app_handle);
+
+    check_notes(app_handle, &mut report);
+
+    match crate::backup_service::list_backups(app_handle.clone()) {
+        Ok(backups) => {
+            if let Some(latest) = backups.first() {
+                report.latest_backup = Some(latest.clone());
+                match verify_backup_archive(latest) {
+                    Ok(()) => report.backup_ok = Some(true),
+                    Err(e) => {
+                        report.backup_ok = Some(false);
+                        report.backup_error = Some(e);
+                    }
+                }
+            }
+        }
+        Err(e) => warn!("Maintenance could not list backups: {}", e),
+    }
+
+    // Never prune away the only good copy
+    if report.backup_ok != Some(false) {
+        match crate::backup_service::prune_backups(app_handle.clone(), keep_count) {
+            Ok(pruned) => report.backups_pruned = pruned,
+            Err(e) => warn!("Maintenance could not prune backups: {}", e),
+        }
+    }
+
+    report.logs_pruned = prune_logs(app_handle, retention_days);
+    (report.history_entries_pruned, report.history_bytes_saved) =
+        crate::journal_service::compact_journals(app_handle);
+    report.duration_ms = started.elapsed().as_millis();
+
+    let state = MaintenanceState {
+        last_run: Some(unix_now()),
+        last_report: Some(report.clone()),
+    };
+    if let Err(e) = save_state(app_handle, &state) {
+        error!("{}", e);
+    }
+
+    if report.is_healthy() {
+        info!("Maintenance completed: {:?}", report);
+    } else {
+        warn!("Maintenance found problems: {:?}", report);
+    }
+
+    if let Err(e) = app_handle.emit("maintenance-completed", &report) {
+        error!("Failed to emit maintenance-completed event: {}", e);
+    }
+
+    report
+}
+
+fn is_due(app_handle: &AppHandle) -> bool {
+    match load_state(app_handle).last_run {
+        Some(last_run) => unix_now().saturating_sub(last_run) >= MAINTENANCE_INTERVAL.as_secs(),
+        None => true,
+    }
+}
+
+// Start the background job that runs maintenance once a week
+pub fn start_maintenance_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        loop {
+            if is_due(&app_handle) {
+                let handle = app_handle.clone();
+                if let Err(e) =
+                    tokio::task::spawn_blocking(move || run_maintenance_tasks(&handle)).await
+                {
+                    error!("Maintenance task failed: {}", e);
+                }
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// Run maintenance immediately
+#[tauri::command]
+pub async fn run_maintenance(app_handle: AppHandle) -> Result<MaintenanceReport, String> {
+    let _timing = crate::metrics_service::time_async_command("run_maintenance");
+    tokio::task::spawn_blocking(move || run_maintenance_tasks(&app_handle))
+        .await
+        .map_err(|e| format!("Maintenance task failed: {}", e))
+}
+
+// Get the report from the last maintenance run, if any
+#[tauri::command]
+pub fn get_maintenance_report(app_handle: AppHandle) -> Option<MaintenanceReport> {
+    load_state(&app_handle).last_report
+}
@@ -0,0 +1,421 @@
+// metrics_service.rs - Per-command execution timing and slow-command logging,
+// and per-note write counters with burst-save protection
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::Runtime;
+
+// Commands slower than this get logged
+const SLOW_COMMAND_THRESHOLD: Duration = Duration::from_millis(100);
+
+// More direct saves or disk writes of one note than this per minute means a
+// save loop; further direct saves go through the debounced pipeline, and
+// debounced ones stop restarting the autosave delay
+const BURST_SAVE_LIMIT: usize = 60;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// Async commands return to the invoke handler as soon as their future is
+// spawned, so they time themselves with time_async_command instead. A test
+// checks this against the async commands registered in lib.rs.
+const ASYNC_COMMANDS: [&str; 21] = [
+    "create_backup",
+    "restore_backup",
+    "save_language_tool_config",
+    "check_grammar",
+    "set_storage_path",
+    "set_tab_storage_path",
+    "import_markdown_folder",
+    "duplicate_note",
+    "merge_notes",
+    "export_everything",
+    "import_everything",
+    "get_pandoc_status",
+    "export_with_pandoc",
+    "invoke_debug",
+    "run_maintenance",
+    "send_note_email",
+    "set_code_execution_enabled",
+    "run_code_block",
+    "check_links",
+    "get_accessibility_preferences",
+    "run_self_test",
+];
+
+#[derive(Default)]
+struct CommandStats {
+    count: u64,
+    total: Duration,
+    max: Duration,
+    slow_count: u64,
+}
+
+static COMMAND_STATS: Lazy<Mutex<HashMap<String, CommandStats>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Default)]
+struct NoteWriteStats {
+    writes: u64,
+    bytes_written: u64,
+    throttled_saves: u64,
+    // Within the last RATE_WINDOW; saves are direct (undebounced) ones
+    recent_writes: VecDeque<Instant>,
+    recent_saves: VecDeque<Instant>,
+    throttling: bool,
+}
+
+static NOTE_WRITE_STATS: Lazy<Mutex<HashMap<usize, NoteWriteStats>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandMetrics {
+    pub command: String,
+    pub count: u64,
+    pub total_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub slow_count: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteWriteMetrics {
+    pub tab: usize,
+    pub writes: u64,
+    pub bytes_written: u64,
+    pub writes_last_minute: usize,
+    pub saves_last_minute: usize,
+    pub throttled_saves: u64,
+}
+
+// Describe a JSON value by type (and length), never by content
+fn value_shape(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(_) => "boolean".to_string(),
+        Value::Number(_) => "number".to_string(),
+        Value::String(s) => format!("string[{}]", s.len()),
+        Value::Array(items) => format!("array[{}]", items.len()),
+        Value::Object(map) => {
+            let fields: Vec<String> = map
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key, value_shape(value)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+}
+
+fn payload_shape(payload: &InvokeBody) -> String {
+    match payload {
+        InvokeBody::Json(value) => value_shape(value),
+        InvokeBody::Raw(bytes) => format!("raw[{}]", bytes.len()),
+    }
+}
+
+fn record(command: &str, elapsed: Duration) -> bool {
+    let slow = elapsed >= SLOW_COMMAND_THRESHOLD;
+    if let Ok(mut stats) = COMMAND_STATS.lock() {
+        let entry = stats.entry(command.to_string()).or_default();
+        entry.count += 1;
+        entry.total += elapsed;
+        entry.max = entry.max.max(elapsed);
+        if slow {
+            entry.slow_count += 1;
+        }
+    }
+    slow
+}
+
+// Wrap the generated invoke handler to time every synchronous command. They
+// run inline and block the UI thread, so this is their full duration.
+pub fn with_timing<R, F>(handler: F) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static
+where
+    R: Runtime,
+    F: Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+{
+    move |invoke| {
+        let command = invoke.message.command().to_string();
+        if ASYNC_COMMANDS.contains(&command.as_str()) {
+            return handler(invoke);
+        }
+        let shape = payload_shape(invoke.message.payload());
+
+        let started = Instant::now();
+        let handled = handler(invoke);
+        let elapsed = started.elapsed();
+
+        if record(&command, elapsed) {
+            warn!(
+                "Slow command {} took {} ms (args: {})",
+                command,
+                elapsed.as_millis(),
+                shape
+            );
+        }
+
+        handled
+    }
+}
+
+// Times an async command until its future completes or is dropped
+pub struct CommandTimer {
+    command: &'static str,
+    started: Instant,
+}
+
+impl Drop for CommandTimer {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        if record(self.command, elapsed) {
+            warn!(
+                "Slow command {} took {} ms",
+                self.command,
+                elapsed.as_millis()
+            );
+        }
+    }
+}
+
+// Start timing an async command; bind the guard at the top of its body. Calls
+// from other backend code are counted too.
+pub fn time_async_command(command: &'static str) -> CommandTimer {
+    debug_assert!(
+        ASYNC_COMMANDS.contains(&command),
+        "{} is missing from ASYNC_COMMANDS",
+        command
+    );
+    CommandTimer {
+        command,
+        started: Instant::now(),
+    }
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// Aggregated timings per command, slowest total first
+#[tauri::command]
+pub fn get_metrics() -> Result<Vec<CommandMetrics>, String> {
+    let stats = COMMAND_STATS
+        .lock()
+        .map_err(|e| format!("Failed to read metrics: {}", e))?;
+
+    let mut metrics: Vec<CommandMetrics> = stats
+        .iter()
+        .map(|(command, s)| CommandMetrics {
+            command: command.clone(),
+            count: s.count,
+            total_ms: as_ms(s.total),
+            avg_ms: as_ms(s.total) / s.count.max(1) as f64,
+            max_ms: as_ms(s.max),
+            slow_count: s.slow_count,
+        })
+        .collect();
+    metrics.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+    Ok(metrics)
+}
+
+fn prune_window(times: &mut VecDeque<Instant>, now: Instant) {
+    while times
+        .front()
+        .is_some_and(|time| now.duration_since(*time) > RATE_WINDOW)
+    {
+        times.pop_front();
+    }
+}
+
+// Count a note file written to disk
+pub fn record_note_write(tab_index: usize, bytes: usize) {
+    let now = Instant::now();
+    if let Ok(mut stats) = NOTE_WRITE_STATS.lock() {
+        let entry = stats.entry(tab_index).or_default();
+        entry.writes += 1;
+        entry.bytes_written += bytes as u64;
+        prune_window(&mut entry.recent_writes, now);
+        entry.recent_writes.push_back(now);
+    }
+}
+
+// Count a throttled save, warning once per burst rather than on every save
+fn update_throttling(
+    entry: &mut NoteWriteStats,
+    tab_index: usize,
+    rate: usize,
+    what: &str,
+) -> bool {
+    let throttle = rate > BURST_SAVE_LIMIT;
+    if throttle {
+        entry.throttled_saves += 1;
+        if !entry.throttling {
+            warn!(
+                "Note {} is being {} {} times a minute; debouncing its saves",
+                tab_index, what, rate
+            );
+        }
+    }
+    entry.throttling = throttle;
+
+    throttle
+}
+
+// Count a direct save request for a note; true if it's being saved so often
+// that this save should be debounced instead of written right away
+pub fn record_save_request(tab_index: usize) -> bool {
+    let now = Instant::now();
+    let Ok(mut stats) = NOTE_WRITE_STATS.lock() else {
+        return false;
+    };
+    let entry = stats.entry(tab_index).or_default();
+    prune_window(&mut entry.recent_saves, now);
+    entry.recent_saves.push_back(now);
+
+    let rate = entry.recent_saves.len();
+    update_throttling(entry, tab_index, rate, "saved")
+}
+
+// Whether a debounced save should stop restarting the autosave delay. The
+// editor sends one on every change, so this goes by disk writes, not calls:
+// a burst of typing is a single write.
+pub fn is_write_burst(tab_index: usize) -> bool {
+    let now = Instant::now();
+    let Ok(mut stats) = NOTE_WRITE_STATS.lock() else {
+        return false;
+    };
+    let entry = stats.entry(tab_index).or_default();
+    prune_window(&mut entry.recent_writes, now);
+
+    let rate = entry.recent_writes.len();
+    update_throttling(entry, tab_index, rate, "written")
+}
+
+// Disk writes and save requests per note
+#[tauri::command]
+pub fn get_write_metrics() -> Result<Vec<NoteWriteMetrics>, String> {
+    let now = Instant::now();
+    let mut stats = NOTE_WRITE_STATS
+        .lock()
+        .map_err(|e| format!("Failed to read metrics: {}", e))?;
+
+    let mut metrics: Vec<NoteWriteMetrics> = stats
+        .iter_mut()
+        .map(|(tab, s)| {
+            prune_window(&mut s.recent_writes, now);
+            prune_window(&mut s.recent_saves, now);
+            NoteWriteMetrics {
+                tab: *tab,
+                writes: s.writes,
+                bytes_written: s.bytes_written,
+                writes_last_minute: s.recent_writes.len(),
+                saves_last_minute: s.recent_saves.len(),
+                throttled_saves: s.throttled_saves,
+            }
+        })
+        .collect();
+    metrics.sort_by_key(|metric| metric.tab);
+
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    // The `async fn` commands in lib.rs's generate_handler! list, found by
+    // reading the sources
+    fn registered_async_commands() -> Vec<String> {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let lib = std::fs::read_to_string(src.join("lib.rs")).unwrap();
+        let handler = lib
+            .split("generate_handler![")
+            .nth(1)
+            .and_then(|rest| rest.split(']').next())
+            .expect("lib.rs registers commands with generate_handler!");
+
+        handler
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or_default())
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|command| !command.is_empty())
+            .filter(|command| {
+                let (file, name) = match command.rsplit_once("::") {
+                    Some((module, name)) => (format!("{}.rs", module), name),
+                    None => ("lib.rs".to_string(), *command),
+                };
+                let source = std::fs::read_to_string(src.join(&file))
+                    .unwrap_or_else(|e| panic!("Failed to read {}: {}", file, e));
+                let definition = format!("async fn {}", name);
+                source.match_indices(&definition).any(|(start, _)| {
+                    matches!(
+                        source[start + definition.len()..].chars().next(),
+                        Some('(' | '<')
+                    )
+                })
+            })
+            .map(|command| command.rsplit("::").next().unwrap_or(command).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn async_commands_match_the_registered_ones() {
+        let mut registered = registered_async_commands();
+        registered.sort();
+        let mut listed: Vec<String> = ASYNC_COMMANDS.iter().map(|c| c.to_string()).collect();
+        listed.sort();
+        assert_eq!(registered, listed);
+    }
+
+    fn throttled_saves(tab_index: usize) -> u64 {
+        NOTE_WRITE_STATS.lock().unwrap()[&tab_index].throttled_saves
+    }
+
+    // Each test uses its own tab, as the counters are shared
+
+    #[test]
+    fn debounced_burst_with_one_write_is_not_throttled() {
+        for _ in 0..BURST_SAVE_LIMIT * 3 {
+            assert!(!is_write_burst(1));
+        }
+        record_note_write(1, 100);
+        assert!(!is_write_burst(1));
+        assert_eq!(throttled_saves(1), 0);
+    }
+
+    #[test]
+    fn direct_save_loop_is_throttled() {
+        for _ in 0..BURST_SAVE_LIMIT {
+            assert!(!record_save_request(2));
+        }
+        assert!(record_save_request(2));
+        assert!(record_save_request(2));
+        assert_eq!(throttled_saves(2), 2);
+    }
+
+    #[test]
+    fn write_loop_throttles_debounced_saves() {
+        for _ in 0..BURST_SAVE_LIMIT {
+            record_note_write(3, 100);
+        }
+        assert!(!is_write_burst(3));
+        record_note_write(3, 100);
+        assert!(is_write_burst(3));
+        assert_eq!(throttled_saves(3), 1);
+    }
+
+    #[test]
+    fn debounced_saves_do_not_count_as_direct_saves() {
+        for _ in 0..BURST_SAVE_LIMIT * 3 {
+            is_write_burst(4);
+        }
+        assert!(!record_save_request(4));
+    }
+}
@@ -0,0 +1,385 @@
+// migration_service.rs - Versioned upgrades of the data directory, run at startup
+//
+// Each migration step has a version; the versions applied so far are recorded
+// in migrations.json. Steps don't touch the disk themselves: they change the
+// settings in memory and write files through the context, which backs up every
+// file before its first write, so a failed run is rolled back completely and a
+// dry run only reports what would change.
+use chrono::Local;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+const MIGRATIONS_DIR_NAME: &str = "migrations";
+
+struct MigrationStep {
+    version: u32,
+    description: &'static str,
+    run: fn(&mut MigrationContext) -> Result<(), String>,
+}
+
+// Ordered by version; never renumber or remove a released step
+const MIGRATIONS: &[MigrationStep] = &[
+    MigrationStep {
+        version: 1,
+        description: "Store the LanguageTool endpoint as a full check URL",
+        run: normalize_languagetool_endpoint,
+    },
+    MigrationStep {
+        version: 2,
+        description: "Turn off custom storage that has no directory set",
+        run: drop_empty_custom_storage,
+    },
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StepReport {
+    pub version: u32,
+    pub description: String,
+    pub changes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub started_at: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<StepReport>,
+    pub backup_path: Option<String>,
+    pub error: Option<String>,
+    pub rolled_back: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub description: String,
+    pub applied_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct MigrationState {
+    applied: Vec<AppliedMigration>,
+    last_report: Option<MigrationReport>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    pub current_version: u32,
+    pub latest_version: u32,
+    pub applied: Vec<AppliedMigration>,
+    pub last_report: Option<MigrationReport>,
+}
+
+// A file written during a run, with the copy to restore on rollback
+// (None if the file didn't exist before)
+struct TouchedFile {
+    path: PathBuf,
+    backup: Option<PathBuf>,
+}
+
+struct MigrationContext {
+    app_dir: PathBuf,
+    // Settings as the steps see them; written once all steps succeeded
+    settings: serde_json::Value,
+    dry_run: bool,
+    changes: Vec<String>,
+    backup_dir: PathBuf,
+    touched: Vec<TouchedFile>,
+}
+
+impl MigrationContext {
+    fn record(&mut self, change: impl Into<String>) {
+        self.changes.push(change.into());
+    }
+
+    // Write a file, backing up its previous content first
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        if !self.touched.iter().any(|touched| touched.path == path) {
+            let backup = if path.exists() {
+                fs::create_dir_all(&self.backup_dir)
+                    .map_err(|e| format!("Failed to create migration backup: {}", e))?;
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let backup = self
+                    .backup_dir
+                    .join(format!("{}-{}", self.touched.len(), name));
+                fs::copy(path, &backup)
+                    .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+                Some(backup)
+            } else {
+                None
+            };
+            self.touched.push(TouchedFile {
+                path: path.to_path_buf(),
+                backup,
+            });
+        }
+
+        crate::storage_service::write_atomic(path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn rollback(&self) -> Result<(), String> {
+        let mut last_error = None;
+        for touched in self.touched.iter().rev() {
+            let result = match &touched.backup {
+                Some(backup) => fs::copy(backup, &touched.path).map(|_| ()),
+                None => fs::remove_file(&touched.path),
+            };
+            if let Err(e) = result {
+                error!("Failed to roll back {}: {}", touched.path.display(), e);
+                last_error = Some(format!(
+                    "Failed to roll back {}: {}",
+                    touched.path.display(),
+                    e
+                ));
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+// Endpoints saved by older versions may be a bare host or base URL
+fn normalize_languagetool_endpoint(ctx: &mut MigrationContext) -> Result<(), String> {
+    let Some(endpoint) = ctx.settings["lt_endpoint"].as_str().map(str::to_string) else {
+        return Ok(());
+    };
+
+    match crate::language_service::normalize_lt_endpoint(&endpoint) {
+        Ok(normalized) if normalized != endpoint => {
+            ctx.settings["lt_endpoint"] = serde_json::json!(normalized);
+            ctx.record(format!(
+                "LanguageTool endpoint {} -> {}",
+                endpoint, normalized
+            ));
+        }
+        Ok(_) => {}
+        // Leave it for the user to fix in the settings
+        Err(e) => warn!("Keeping unrecognized LanguageTool endpoint: {}", e),
+    }
+
+    Ok(())
+}
+
+// "using_custom_storage" without a path made the storage settings show a
+// custom location that was never used
+fn drop_empty_custom_storage(ctx: &mut MigrationContext) -> Result<(), String> {
+    let enabled = ctx.settings["using_custom_storage"]
+        .as_bool()
+        .unwrap_or(false);
+    let path_empty = ctx.settings["custom_storage_path"]
+        .as_str()
+        .is_none_or(str::is_empty);
+
+    if enabled && path_empty {
+        ctx.settings["using_custom_storage"] = serde_json::json!(false);
+        ctx.record("Custom storage turned off: no directory was set");
+    }
+
+    Ok(())
+}
+
+fn get_app_dir(app_handle: &AppHandle) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle)
+}
+
+fn get_state_path(app_dir: &Path) -> PathBuf {
+    app_dir.join("migrations.json")
+}
+
+fn load_state(app_dir: &Path) -> MigrationState {
+    fs::read_to_string(get_state_path(app_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(app_dir: &Path, state: &MigrationState) -> Result<(), String> {
+    let json_str = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize migration state: {}", e))?;
+    crate::storage_service::write_atomic(get_state_path(app_dir), json_str)
+        .map_err(|e| format!("Failed to save migration state: {}", e))
+}
+
+fn current_version(state: &MigrationState) -> u32 {
+    state
+        .applied
+        .iter()
+        .map(|applied| applied.version)
+        .max()
+        .unwrap_or(0)
+}
+
+fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|step| step.version).unwrap_or(0)
+}
+
+fn run_pending(
+    ctx: &mut MigrationContext,
+    pending: &[&MigrationStep],
+    steps: &mut Vec<StepReport>,
+) -> Result<(), String> {
+    let settings_before = ctx.settings.clone();
+
+    for step in pending {
+        ctx.changes.clear();
+        (step.run)(ctx).map_err(|e| format!("Migration {} failed: {}", step.version, e))?;
+        steps.push(StepReport {
+            version: step.version,
+            description: step.description.to_string(),
+            changes: std::mem::take(&mut ctx.changes),
+        });
+    }
+
+    if ctx.settings != settings_before {
+        let json_str = serde_json::to_string_pretty(&ctx.settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        let settings_path = ctx.app_dir.join("settings.json");
+        ctx.write_file(&settings_path, json_str.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+// Apply (or with dry_run, just report) all migrations newer than the data
+// directory's version
+pub fn run_migrations(app_handle: &AppHandle, dry_run: bool) -> MigrationReport {
+    let app_dir = get_app_dir(app_handle);
+    let mut state = load_state(&app_dir);
+    let from_version = current_version(&state);
+    let started_at = Local::now();
+
+    let pending: Vec<&MigrationStep> = MIGRATIONS
+        .iter()
+        .filter(|step| step.version > from_version)
+        .collect();
+
+    let settings = fs::read_to_string(app_dir.join("settings.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    let mut ctx = MigrationContext {
+        backup_dir: app_dir
+            .join(MIGRATIONS_DIR_NAME)
+            .join(format!("backup_{}", started_at.format("%Y-%m-%d_%H-%M-%S"))),
+        app_dir: app_dir.clone(),
+        settings,
+        dry_run,
+        changes: Vec::new(),
+        touched: Vec::new(),
+    };
+
+    let mut report = MigrationReport {
+        dry_run,
+        started_at: started_at.to_rfc3339(),
+        from_version,
+        to_version: from_version,
+        steps: Vec::new(),
+        backup_path: None,
+        error: None,
+        rolled_back: false,
+    };
+
+    if pending.is_empty() {
+        return report;
+    }
+
+    let mut result = run_pending(&mut ctx, &pending, &mut report.steps);
+
+    if result.is_ok() && !dry_run {
+        let applied_at = Local::now().to_rfc3339();
+        state
+            .applied
+            .extend(pending.iter().map(|step| AppliedMigration {
+                version: step.version,
+                description: step.description.to_string(),
+                applied_at: applied_at.clone(),
+            }));
+        result = save_state(&app_dir, &state);
+    }
+
+    match result {
+        Ok(()) => {
+            report.to_version = latest_version();
+        }
+        Err(e) => {
+            error!("{}", e);
+            report.error = Some(e);
+            if !dry_run {
+                report.rolled_back = ctx.rollback().is_ok();
+                // Reload, so the failed steps aren't recorded as applied
+                state = load_state(&app_dir);
+            }
+        }
+    }
+
+    if ctx.backup_dir.exists() {
+        report.backup_path = Some(ctx.backup_dir.to_string_lossy().to_string());
+    }
+
+    if !dry_run {
+        state.last_report = Some(report.clone());
+        if let Err(e) = save_state(&app_dir, &state) {
+            error!("{}", e);
+        }
+    }
+
+    report
+}
+
+// Bring the data directory up to date before anything else reads it
+pub fn run_startup_migrations(app_handle: &AppHandle) {
+    let report = run_migrations(app_handle, false);
+
+    match &report.error {
+        None if report.steps.is_empty() => {}
+        None => info!(
+            "Migrated data directory from version {} to {}",
+            report.from_version, report.to_version
+        ),
+        Some(e) if report.rolled_back => {
+            warn!("Data directory migration rolled back: {}", e)
+        }
+        Some(e) => error!(
+            "Data directory migration failed and could not be fully rolled back \
+             (backup: {:?}): {}",
+            report.backup_path, e
+        ),
+    }
+}
+
+// Version of the data directory and the last migration run
+#[tauri::command]
+pub fn get_migration_status(app_handle: AppHandle) -> MigrationStatus {
+    let state = load_state(&get_app_dir(&app_handle));
+
+    MigrationStatus {
+        current_version: current_version(&state),
+        latest_version: latest_version(),
+        applied: state.applied,
+        last_report: state.last_report,
+    }
+}
+
+// Report what the pending migrations would change, without changing anything
+#[tauri::command]
+pub fn plan_migrations(app_handle: AppHandle) -> MigrationReport {
+    run_migrations(&app_handle, true)
+}
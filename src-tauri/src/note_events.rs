@@ -0,0 +1,176 @@
+// note_events.rs - Tell the editor a note changed outside of it
+//
+// `note-updated-{i}` carries the whole note. Alongside it, `note-diff-{i}`
+// carries the edits from the content the editor last had, so it can apply
+// them in place instead of resetting scroll and cursor. Offsets are in UTF-16
+// code units, like JavaScript strings, and refer to the old content (the
+// shape of CodeMirror's change specs). The editor should only apply the diff
+// if its content matches `baseSha256`, and otherwise use the full note.
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use similar::{DiffTag, TextDiff};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+// Very different versions of a big note aren't worth a perfect diff
+const DIFF_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TextChange {
+    pub from: usize,
+    pub to: usize,
+    pub insert: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteDiff {
+    pub base_sha256: String,
+    pub changes: Vec<TextChange>,
+}
+
+// What the editor has for each tab, as far as the backend knows: the content
+// it last saved, loaded or was sent
+static EDITOR_CONTENT: Lazy<Mutex<HashMap<usize, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn remember_editor_content(tab_index: usize, content: &str) {
+    if let Ok(mut known) = EDITOR_CONTENT.lock() {
+        known.insert(tab_index, content.to_string());
+    }
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+// Line-based edits turning `old` into `new`
+pub fn diff_changes(old: &str, new: &str) -> Vec<TextChange> {
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(old, new);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+
+    // UTF-16 offset where each old line starts, plus the end
+    let mut line_starts = Vec::with_capacity(old_lines.len() + 1);
+    let mut offset = 0;
+    for line in old_lines {
+        line_starts.push(offset);
+        offset += utf16_len(line);
+    }
+    line_starts.push(offset);
+
+    diff.ops()
+        .iter()
+        .map(|op| op.as_tag_tuple())
+        .filter(|(tag, _, _)| *tag != DiffTag::Equal)
+        .map(|(_, old_range, new_range)| TextChange {
+            from: line_starts[old_range.start],
+            to: line_starts[old_range.end],
+            insert: new_lines[new_range].concat(),
+        })
+        .collect()
+}
+
+// Announce a note's new content, with a diff when the editor's content is known
+pub fn emit_note_updated<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: &str,
+) -> Result<(), String> {
+    let previous = EDITOR_CONTENT
+        .lock()
+        .ok()
+        .and_then(|mut known| known.insert(tab_index, content.to_string()));
+
+    if let Some(previous) = previous.filter(|previous| previous != content) {
+        let diff = NoteDiff {
+            base_sha256: crate::integrity_service::sha256_hex(previous.as_bytes()),
+            changes: diff_changes(&previous, content),
+        };
+        if let Err(e) = app_handle.emit(&format!("note-diff-{}", tab_index), diff) {
+            warn!("Failed to emit diff event: {}", e);
+        }
+    }
+
+    app_handle
+        .emit(&format!("note-updated-{}", tab_index), content)
+        .map_err(|e| format!("Failed to emit update event: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(from: usize, to: usize, insert: &str) -> TextChange {
+        TextChange {
+            from,
+            to,
+            insert: insert.to_string(),
+        }
+    }
+
+    // Apply the changes the way the editor does, in UTF-16 code units
+    fn apply(old: &str, changes: &[TextChange]) -> String {
+        let old: Vec<u16> = old.encode_utf16().collect();
+        let mut result = Vec::new();
+        let mut position = 0;
+        for change in changes {
+            result.extend_from_slice(&old[position..change.from]);
+            result.extend(change.insert.encode_utf16());
+            position = change.to;
+        }
+        result.extend_from_slice(&old[position..]);
+        String::from_utf16(&result).unwrap()
+    }
+
+    #[test]
+    fn unchanged_content_has_no_changes() {
+        assert!(diff_changes("a\nb\n", "a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn inserted_line_is_an_empty_range() {
+        let changes = diff_changes("a\nc\n", "a\nb\nc\n");
+        assert_eq!(changes, vec![change(2, 2, "b\n")]);
+    }
+
+    #[test]
+    fn deleted_line_inserts_nothing() {
+        let changes = diff_changes("a\nb\nc\n", "a\nc\n");
+        assert_eq!(changes, vec![change(2, 4, "")]);
+    }
+
+    #[test]
+    fn replaced_line_covers_the_old_line() {
+        let changes = diff_changes("a\nb\nc\n", "a\nB\nc\n");
+        assert_eq!(changes, vec![change(2, 4, "B\n")]);
+    }
+
+    #[test]
+    fn offsets_count_utf16_code_units() {
+        // The emoji is two UTF-16 code units, "é" one
+        let changes = diff_changes("😀\né\nx\n", "😀\né\ny\n");
+        assert_eq!(changes, vec![change(5, 7, "y\n")]);
+    }
+
+    #[test]
+    fn multibyte_insert_and_delete() {
+        let changes = diff_changes("ünï\n😀\ncödé\n", "😀\ncödé\n✓ done\n");
+        assert_eq!(changes, vec![change(0, 4, ""), change(12, 12, "✓ done\n")]);
+    }
+
+    #[test]
+    fn changes_refer_to_the_old_content() {
+        let old = "# Title\n\n- [ ] one\n- [ ] two 😀\n- [ ] three\n";
+        let new = "# Title 😀\n\n- [x] one\n- [ ] two 😀\n- [ ] four\nend";
+        let changes = diff_changes(old, new);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(apply(old, &changes), new);
+    }
+}
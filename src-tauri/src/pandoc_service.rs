@@ -0,0 +1,242 @@
+// pandoc_service.rs - Extra export formats (docx, odt, epub, LaTeX) through
+// Pandoc, when it's installed
+//
+// Pandoc is run directly, never through a shell: the note goes in on stdin
+// and only arguments built here are passed, so note content or file names
+// can't inject options.
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::export_service::{note_title, resolve_target_path};
+
+const DEFAULT_PANDOC: &str = "pandoc";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PandocFormat {
+    Docx,
+    Odt,
+    Epub,
+    Latex,
+}
+
+impl PandocFormat {
+    const ALL: [PandocFormat; 4] = [
+        PandocFormat::Docx,
+        PandocFormat::Odt,
+        PandocFormat::Epub,
+        PandocFormat::Latex,
+    ];
+
+    fn writer(&self) -> &'static str {
+        match self {
+            PandocFormat::Docx => "docx",
+            PandocFormat::Odt => "odt",
+            PandocFormat::Epub => "epub3",
+            PandocFormat::Latex => "latex",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            PandocFormat::Docx => "docx",
+            PandocFormat::Odt => "odt",
+            PandocFormat::Epub => "epub",
+            PandocFormat::Latex => "tex",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PandocOptions {
+    pub table_of_contents: bool,
+    pub number_sections: bool,
+    // Style template for docx/odt
+    pub reference_doc: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PandocStatus {
+    pub available: bool,
+    pub path: String,
+    pub version: Option<String>,
+    pub formats: Vec<PandocFormat>,
+    pub error: Option<String>,
+}
+
+// `pandoc_path` in settings.json, for installs outside of PATH
+fn pandoc_path<R: Runtime>(app_handle: &AppHandle<R>) -> String {
+    let settings_path = crate::data_dir::get_app_data_dir(app_handle).join("settings.json");
+
+    fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|settings| settings["pandoc_path"].as_str().map(str::to_string))
+        .filter(|path| !path.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PANDOC.to_string())
+}
+
+// Run `pandoc --version`; returns the first line, e.g. "pandoc 3.1.3"
+async fn probe_pandoc(path: &str) -> Result<String, String> {
+    let output = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        Command::new(path)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| "Pandoc did not respond".to_string())?
+    .map_err(|e| format!("Pandoc not found: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Pandoc failed to start: {}", output.status));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string())
+}
+
+// Whether Pandoc is installed, and which extra formats it enables
+#[tauri::command]
+pub async fn get_pandoc_status<R: Runtime>(app_handle: AppHandle<R>) -> PandocStatus {
+    let _timing = crate::metrics_service::time_async_command("get_pandoc_status");
+    let path = pandoc_path(&app_handle);
+
+    match probe_pandoc(&path).await {
+        Ok(version) => PandocStatus {
+            available: true,
+            path,
+            version: Some(version),
+            formats: PandocFormat::ALL.to_vec(),
+            error: None,
+        },
+        Err(e) => PandocStatus {
+            available: false,
+            path,
+            version: None,
+            formats: Vec::new(),
+            error: Some(e),
+        },
+    }
+}
+
+// Export a note through Pandoc; returns the written file
+#[tauri::command]
+pub async fn export_with_pandoc<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    format: PandocFormat,
+    target_path: String,
+    options: Option<PandocOptions>,
+) -> Result<String, String> {
+    let _timing = crate::metrics_service::time_async_command("export_with_pandoc");
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+    let options = options.unwrap_or_default();
+
+    if let Err(e) = crate::autosave_service::flush_all(&app_handle) {
+        warn!("Failed to save pending notes before exporting: {}", e);
+    }
+
+    let markdown = crate::storage_service::read_note(&app_handle, tab_index)?
+        .ok_or_else(|| format!("Note {} is empty", tab_index))?;
+    let title = note_title(tab_index, &markdown);
+    let path = resolve_target_path(&target_path, &title, format.extension());
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create export directory: {}", e))?;
+        }
+    }
+
+    // `--opt=value` keeps values starting with `-` from being read as options
+    let mut args = vec![
+        "--from=markdown".to_string(),
+        format!("--to={}", format.writer()),
+        "--standalone".to_string(),
+        format!("--metadata=title:{}", title),
+        format!("--output={}", path.display()),
+    ];
+    // Images are linked relative to the notes folder (`assets/...`)
+    let storage_dir = crate::storage_service::get_current_storage_dir(&app_handle);
+    args.push(format!("--resource-path={}", storage_dir.display()));
+    if options.table_of_contents {
+        args.push("--toc".to_string());
+    }
+    if options.number_sections {
+        args.push("--number-sections".to_string());
+    }
+    if let Some(reference_doc) = options.reference_doc.filter(|doc| !doc.is_empty()) {
+        if !matches!(format, PandocFormat::Docx | PandocFormat::Odt) {
+            return Err("A reference document only applies to docx and odt exports".to_string());
+        }
+        if !PathBuf::from(&reference_doc).is_file() {
+            return Err(format!("Reference document not found: {}", reference_doc));
+        }
+        args.push(format!("--reference-doc={}", reference_doc));
+    }
+
+    let mut child = Command::new(pandoc_path(&app_handle))
+        .args(&args)
+        .current_dir(&storage_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start Pandoc: {}", e))?;
+
+    let mut stdin = child.stdin.take().ok_or("Failed to open Pandoc input")?;
+    // Frontmatter would be read as Pandoc metadata; only the title is passed
+    let body = crate::storage_service::split_frontmatter(&markdown)
+        .1
+        .to_string();
+    let writer = tokio::spawn(async move {
+        let result = stdin.write_all(body.as_bytes()).await;
+        drop(stdin);
+        result
+    });
+
+    let output = tokio::time::timeout(EXPORT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| "Pandoc took too long and was stopped".to_string())?
+        .map_err(|e| format!("Failed to run Pandoc: {}", e))?;
+    if let Ok(Err(e)) = writer.await {
+        warn!("Failed to pass the note to Pandoc: {}", e);
+    }
+
+    if !output.status.success() {
+        return Err(format!(
+            "Pandoc export failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    info!(
+        "Exported note {} as {:?} to {} with Pandoc",
+        tab_index,
+        format,
+        path.display()
+    );
+
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,120 @@
+// proxy_service.rs - Proxy for Jot's outgoing HTTP requests (LanguageTool,
+// link checks), for networks that only allow traffic through one
+//
+// The proxy address lives in settings.json; its password is kept in the
+// system keychain.
+use log::debug;
+use reqwest::{ClientBuilder, Proxy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Runtime};
+use url::Url;
+
+use crate::credential_manager;
+
+const PROXY_SERVICE: &str = "jot.proxy";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySettings {
+    pub enabled: bool,
+    // http://, https:// or socks5:// address, e.g. "socks5://localhost:1080"
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    // Hosts reached directly, comma separated as in NO_PROXY
+    #[serde(default)]
+    pub no_proxy: String,
+    // Only reported to the frontend; the password is in the keychain
+    #[serde(default)]
+    pub has_password: bool,
+}
+
+fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("settings.json")
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+fn load_proxy_settings<R: Runtime>(app_handle: &AppHandle<R>) -> Option<ProxySettings> {
+    serde_json::from_value(read_settings(app_handle)["proxy"].clone()).ok()
+}
+
+fn parse_proxy_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid proxy address: {}", e))?;
+    match parsed.scheme() {
+        "http" | "https" | "socks5" | "socks5h" => {}
+        scheme => return Err(format!("Unsupported proxy type: {}", scheme)),
+    }
+    if parsed.host_str().is_none() {
+        return Err("Proxy address has no host".to_string());
+    }
+    Ok(parsed)
+}
+
+// An HTTP client builder that goes through the configured proxy, if any.
+// Without one, reqwest still honors the system's HTTP(S)_PROXY variables.
+pub fn client_builder<R: Runtime>(app_handle: &AppHandle<R>) -> Result<ClientBuilder, String> {
+    let builder = reqwest::Client::builder();
+    let Some(proxy) = load_proxy_settings(app_handle).filter(|proxy| proxy.enabled) else {
+        return Ok(builder);
+    };
+
+    let url = parse_proxy_url(&proxy.url)?;
+    let mut http_proxy =
+        Proxy::all(url.as_str()).map_err(|e| format!("Invalid proxy address: {}", e))?;
+    if !proxy.username.is_empty() {
+        let password =
+            credential_manager::get_credential(PROXY_SERVICE, &proxy.username).unwrap_or_default();
+        http_proxy = http_proxy.basic_auth(&proxy.username, &password);
+    }
+    http_proxy = http_proxy.no_proxy(reqwest::NoProxy::from_string(&proxy.no_proxy));
+
+    debug!("Using proxy {}", url);
+    Ok(builder.proxy(http_proxy))
+}
+
+// Get the proxy settings, if configured
+#[tauri::command]
+pub fn get_proxy_settings<R: Runtime>(app_handle: AppHandle<R>) -> Option<ProxySettings> {
+    load_proxy_settings(&app_handle).map(|proxy| ProxySettings {
+        has_password: !proxy.username.is_empty()
+            && credential_manager::get_credential(PROXY_SERVICE, &proxy.username).is_ok(),
+        ..proxy
+    })
+}
+
+// Save the proxy settings; the password (if given) goes to the keychain
+#[tauri::command]
+pub fn set_proxy_settings<R: Runtime>(
+    app_handle: AppHandle<R>,
+    proxy: ProxySettings,
+    password: Option<String>,
+) -> Result<(), String> {
+    if proxy.enabled {
+        parse_proxy_url(&proxy.url)?;
+    }
+
+    if let Some(password) = password.filter(|password| !password.is_empty()) {
+        if proxy.username.is_empty() {
+            return Err("A proxy password needs a username".to_string());
+        }
+        credential_manager::store_credential(PROXY_SERVICE, &proxy.username, &password)?;
+    }
+
+    let value = serde_json::to_value(ProxySettings {
+        has_password: false,
+        ..proxy
+    })
+    .map_err(|e| format!("Failed to serialize proxy settings: {}", e))?;
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        settings["proxy"] = value;
+        Ok(())
+    })
+}
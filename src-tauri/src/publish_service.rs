@@ -0,0 +1,465 @@
+// publish_service.rs - Publish chosen tabs as a small static HTML site
+//
+// The site is an index page linking one page per tab, styled with a Jot
+// theme, written to an output directory to be served from anywhere. It can
+// be republished after every save of one of its tabs. Pages from an earlier
+// publish that are no longer part of the site are removed, but nothing else
+// in the directory is touched.
+use log::{error, info, warn};
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::export_service::{escape_html, note_title, render_body, HTML_STYLE};
+
+// Files written by the last publish, to clean up pages that went away
+const MANIFEST_FILE: &str = ".jot-site.json";
+
+// Publishes after quick successive saves must not write the site at once
+static PUBLISH_LOCK: Mutex<()> = Mutex::new(());
+
+// Saves within this long of each other are published together
+const PUBLISH_DELAY: Duration = Duration::from_secs(2);
+
+static PUBLISH_QUEUE: PublishQueue = PublishQueue::new();
+
+// Coalesces publishes after saves: a save only marks the site as out of date,
+// and a single worker publishes it until no save came in while it waited
+struct PublishQueue {
+    pending: AtomicBool,
+    running: AtomicBool,
+}
+
+impl PublishQueue {
+    const fn new() -> Self {
+        PublishQueue {
+            pending: AtomicBool::new(false),
+            running: AtomicBool::new(false),
+        }
+    }
+
+    // Mark the site out of date; true if the caller must start the worker
+    fn request(&self) -> bool {
+        self.pending.store(true, Ordering::SeqCst);
+        !self.running.swap(true, Ordering::SeqCst)
+    }
+
+    // For the worker after each wait: true to publish, false to stop
+    fn next(&self) -> bool {
+        loop {
+            if self.pending.swap(false, Ordering::SeqCst) {
+                return true;
+            }
+            self.running.store(false, Ordering::SeqCst);
+            // A save between the check and the store found the worker still
+            // running, so it's up to this one to publish it
+            if !self.pending.load(Ordering::SeqCst) || self.running.swap(true, Ordering::SeqCst) {
+                return false;
+            }
+        }
+    }
+}
+
+const SITE_STYLE: &str = r#"
+body { background: var(--bg-color, #ffffff); color: var(--text-color, #1f2328); }
+a { color: var(--link-color, #0969da); }
+nav { margin-bottom: 2rem; font-size: 0.9em; }
+nav a { margin-right: 1rem; }
+footer { margin-top: 3rem; font-size: 0.8em; opacity: 0.7; }
+"#;
+
+const DARK_STYLE: &str = r#"
+:root { --bg-color: #1e1e1e; --text-color: #d4d4d4; --link-color: #6cb6ff; }
+pre { background: #2d2d2d; }
+"#;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishConfig {
+    // Tabs to publish, in the order they are listed on the index
+    pub tabs: Vec<usize>,
+    pub output_dir: String,
+    // Shown on the index and in page titles; "Jot" if empty
+    #[serde(default)]
+    pub title: String,
+    // Theme id, built-in or user-installed; light if not set
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub publish_on_save: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedPage {
+    pub tab: usize,
+    pub title: String,
+    pub file: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishResult {
+    pub output_dir: String,
+    pub pages: Vec<PublishedPage>,
+    pub assets: usize,
+    pub removed: usize,
+}
+
+fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("settings.json")
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+fn load_config<R: Runtime>(app_handle: &AppHandle<R>) -> Option<PublishConfig> {
+    serde_json::from_value(read_settings(app_handle)["publish_site"].clone()).ok()
+}
+
+fn validate_config(config: &PublishConfig) -> Result<(), String> {
+    if config.tabs.is_empty() {
+        return Err("Choose at least one tab to publish".to_string());
+    }
+    if let Some(tab) = config.tabs.iter().find(|tab| **tab >= 7) {
+        return Err(format!("Invalid tab index: {}", tab));
+    }
+    if config.output_dir.trim().is_empty() {
+        return Err("Choose an output directory".to_string());
+    }
+    Ok(())
+}
+
+fn page_file_name(title: &str, taken: &BTreeSet<String>, tab: usize) -> String {
+    let stem: String = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let stem = stem
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    let file = format!("{}.html", stem);
+    if stem.is_empty() || stem == "index" || taken.contains(&file) {
+        format!(
+            "{}-{}.html",
+            if stem.is_empty() { "note" } else { &stem },
+            tab + 1
+        )
+    } else {
+        file
+    }
+}
+
+// assets/ files a note links or embeds
+fn referenced_assets(markdown: &str) -> BTreeSet<String> {
+    Parser::new(crate::storage_service::split_frontmatter(markdown).1)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image { dest_url, .. })
+            | Event::Start(Tag::Link { dest_url, .. }) => Some(dest_url.to_string()),
+            _ => None,
+        })
+        .filter_map(|url| url.strip_prefix("assets/").map(str::to_string))
+        .filter(|name| !name.contains("..") && !name.contains('/') && !name.contains('\\'))
+        .collect()
+}
+
+fn theme_style<R: Runtime>(app_handle: &AppHandle<R>, theme: Option<&str>) -> String {
+    let Some(theme) = theme.filter(|theme| !theme.is_empty()) else {
+        return String::new();
+    };
+    let content = match crate::theme_service::get_theme(app_handle.clone(), theme.to_string()) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("{}; publishing with the default theme", e);
+            return String::new();
+        }
+    };
+
+    let mut style = String::new();
+    if content.info.base == "dark" {
+        style.push_str(DARK_STYLE);
+    }
+    if !content.colors.is_empty() {
+        let mut colors: Vec<_> = content.colors.iter().collect();
+        colors.sort();
+        style.push_str(":root {");
+        for (key, value) in colors {
+            style.push_str(&format!(" --{}: {};", key, value));
+        }
+        style.push_str(" }\n");
+    }
+    if let Some(css) = content.css {
+        match crate::theme_service::validate_css(&css) {
+            Ok(()) => style.push_str(&css),
+            Err(e) => warn!("Publishing without the CSS of theme {}: {}", theme, e),
+        }
+    }
+    style
+}
+
+fn render_page(title: &str, style: &str, nav: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}{}{}</style>\n</head>\n<body>\n<nav>{}</nav>\n{}<footer>Updated {}</footer>\n</body>\n</html>\n",
+        escape_html(title),
+        HTML_STYLE,
+        SITE_STYLE,
+        style,
+        nav,
+        body,
+        escape_html(&chrono::Local::now().format("%Y-%m-%d %H:%M").to_string())
+    )
+}
+
+fn publish<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    config: &PublishConfig,
+) -> Result<PublishResult, String> {
+    validate_config(config)?;
+    let _guard = PUBLISH_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock site publishing: {}", e))?;
+    crate::autosave_service::flush_all(app_handle)?;
+
+    let output_dir = PathBuf::from(config.output_dir.trim());
+    fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    let site_title = match config.title.trim() {
+        "" => "Jot",
+        title => title,
+    };
+    let style = theme_style(app_handle, config.theme.as_deref());
+
+    let mut notes = Vec::new();
+    let mut files = BTreeSet::new();
+    for &tab in &config.tabs {
+        let Some(content) = crate::storage_service::read_note(app_handle, tab)? else {
+            continue;
+        };
+        let title = note_title(tab, &content);
+        let file = page_file_name(&title, &files, tab);
+        files.insert(file.clone());
+        notes.push((PublishedPage { tab, title, file }, content));
+    }
+
+    let nav: String = std::iter::once(format!(
+        "<a href=\"index.html\">{}</a>",
+        escape_html(site_title)
+    ))
+    .chain(
+        notes
+            .iter()
+            .map(|(page, _)| format!("<a href=\"{}\">{}</a>", page.file, escape_html(&page.title))),
+    )
+    .collect();
+
+    let mut assets = BTreeSet::new();
+    for (page, content) in &notes {
+        assets.extend(referenced_assets(content));
+        let html = render_page(
+            &format!("{} - {}", page.title, site_title),
+            &style,
+            &nav,
+            &render_body(content, None),
+        );
+        crate::storage_service::write_atomic(output_dir.join(&page.file), html)
+            .map_err(|e| format!("Failed to write {}: {}", page.file, e))?;
+    }
+
+    let links: String = notes
+        .iter()
+        .map(|(page, _)| {
+            format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                page.file,
+                escape_html(&page.title)
+            )
+        })
+        .collect();
+    let index_body = format!(
+        "<h1>{}</h1>\n<ul>\n{}</ul>\n",
+        escape_html(site_title),
+        links
+    );
+    crate::storage_service::write_atomic(
+        output_dir.join("index.html"),
+        render_page(site_title, &style, &nav, &index_body),
+    )
+    .map_err(|e| format!("Failed to write index.html: {}", e))?;
+    files.insert("index.html".to_string());
+
+    // Only the attachments the published notes use, not the whole folder
+    let assets_dir = crate::attachments::get_assets_dir(app_handle);
+    let mut copied = 0;
+    for name in &assets {
+        let source = assets_dir.join(name);
+        if !source.is_file() {
+            continue;
+        }
+        let relative = format!("{}/{}", crate::attachments::ASSETS_DIR_NAME, name);
+        let target = output_dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create assets directory: {}", e))?;
+        }
+        fs::copy(&source, &target).map_err(|e| format!("Failed to copy {}: {}", name, e))?;
+        files.insert(relative);
+        copied += 1;
+    }
+
+    let removed = remove_stale_files(&output_dir, &files);
+    let manifest = serde_json::to_string_pretty(&files)
+        .map_err(|e| format!("Failed to serialize site manifest: {}", e))?;
+    crate::storage_service::write_atomic(output_dir.join(MANIFEST_FILE), manifest)
+        .map_err(|e| format!("Failed to write site manifest: {}", e))?;
+
+    info!(
+        "Published {} pages and {} attachments to {}",
+        notes.len(),
+        copied,
+        output_dir.display()
+    );
+
+    Ok(PublishResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        pages: notes.into_iter().map(|(page, _)| page).collect(),
+        assets: copied,
+        removed,
+    })
+}
+
+// Remove the files the previous publish wrote that this one didn't
+fn remove_stale_files(output_dir: &Path, files: &BTreeSet<String>) -> usize {
+    let previous: BTreeSet<String> = fs::read_to_string(output_dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let mut removed = 0;
+    for file in previous.difference(files) {
+        // Only plain relative paths; anything else could point outside the site
+        let safe = !file.is_empty()
+            && Path::new(file)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !safe {
+            warn!("Ignoring unsafe path in the site manifest: {}", file);
+            continue;
+        }
+        match fs::remove_file(output_dir.join(file)) {
+            Ok(()) => removed += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove {} from the site: {}", file, e),
+        }
+    }
+    removed
+}
+
+// Republish in the background shortly after a tab of the site was saved, if
+// configured to; a burst of saves is published once
+pub fn on_note_saved<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize) {
+    let publishes = load_config(app_handle)
+        .is_some_and(|config| config.publish_on_save && config.tabs.contains(&tab_index));
+    if !publishes || !PUBLISH_QUEUE.request() {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(PUBLISH_DELAY).await;
+            if !PUBLISH_QUEUE.next() {
+                break;
+            }
+
+            let app_handle = app_handle.clone();
+            let published = tauri::async_runtime::spawn_blocking(move || {
+                // The configuration may have changed while waiting
+                match load_config(&app_handle).filter(|config| config.publish_on_save) {
+                    Some(config) => publish(&app_handle, &config).map(|_| ()),
+                    None => Ok(()),
+                }
+            })
+            .await;
+            match published {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!("Failed to publish site after saving: {}", e),
+                Err(e) => error!("Failed to publish site after saving: {}", e),
+            }
+        }
+    });
+}
+
+// Get the site configuration from the last publish, if any
+#[tauri::command]
+pub fn get_publish_config<R: Runtime>(app_handle: AppHandle<R>) -> Option<PublishConfig> {
+    load_config(&app_handle)
+}
+
+// Render the chosen tabs into a static site, and remember the configuration
+// for publishing after saves
+#[tauri::command]
+pub fn publish_site<R: Runtime>(
+    app_handle: AppHandle<R>,
+    config: PublishConfig,
+) -> Result<PublishResult, String> {
+    validate_config(&config)?;
+
+    let value = serde_json::to_value(&config)
+        .map_err(|e| format!("Failed to serialize site configuration: {}", e))?;
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        settings["publish_site"] = value;
+        Ok(())
+    })?;
+
+    publish(&app_handle, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_of_saves_is_one_publish() {
+        let queue = PublishQueue::new();
+        assert!(queue.request());
+        for _ in 0..50 {
+            assert!(!queue.request());
+        }
+        assert!(queue.next());
+        assert!(!queue.next());
+    }
+
+    #[test]
+    fn save_during_a_publish_publishes_again() {
+        let queue = PublishQueue::new();
+        assert!(queue.request());
+        assert!(queue.next());
+        // Saved while the worker was publishing
+        assert!(!queue.request());
+        assert!(queue.next());
+        assert!(!queue.next());
+    }
+
+    #[test]
+    fn save_after_the_worker_stopped_starts_a_new_one() {
+        let queue = PublishQueue::new();
+        assert!(queue.request());
+        assert!(queue.next());
+        assert!(!queue.next());
+        assert!(queue.request());
+    }
+}
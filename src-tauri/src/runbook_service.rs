@@ -0,0 +1,540 @@
+// runbook_service.rs - Run `sh`/`python` code blocks of a note and write the
+// output beneath them, turning notes into lightweight runbooks
+//
+// Off unless turned on with `set_code_execution_enabled`, which the generic
+// settings save refuses to change. Turning it on and every run are confirmed
+// in a native dialog opened from here, showing the exact code, so the webview
+// can't run anything on its own. This is not a sandbox: the code runs with
+// the user's permissions; only its working directory and environment are
+// restricted, and it is stopped after a timeout.
+use log::{info, warn};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
+
+use crate::storage_service;
+
+const RUN_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+const OUTPUT_INFO: &str = "output";
+// Code longer than this can't be shown in full for confirmation
+const MAX_CONFIRMED_CODE_CHARS: usize = 4000;
+// Environment passed through to the code; everything else is cleared
+const KEPT_ENV_VARS: [&str; 5] = ["PATH", "LANG", "LC_ALL", "SYSTEMROOT", "TERM"];
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeRunResult {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    pub truncated: bool,
+}
+
+struct FencedBlock {
+    info: String,
+    // Line indices of the opening and closing fence
+    start: usize,
+    end: usize,
+    code: String,
+}
+
+// Fenced code blocks of a note; an unclosed one runs to the end
+fn parse_fenced_blocks(lines: &[&str]) -> Vec<FencedBlock> {
+    let mut blocks = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let trimmed = lines[index].trim_start();
+        let Some(fence_char) = ['`', '~']
+            .into_iter()
+            .find(|c| trimmed.starts_with(&c.to_string().repeat(3)))
+        else {
+            index += 1;
+            continue;
+        };
+        let fence_len = trimmed.chars().take_while(|c| *c == fence_char).count();
+        let info = trimmed[fence_len..].trim().to_string();
+
+        let start = index;
+        let end = (start + 1..lines.len())
+            .find(|&line| {
+                let closing = lines[line].trim();
+                closing.len() >= fence_len && closing.chars().all(|c| c == fence_char)
+            })
+            .unwrap_or(lines.len());
+
+        blocks.push(FencedBlock {
+            info,
+            start,
+            end,
+            code: lines[start + 1..end].join("\n"),
+        });
+        index = end + 1;
+    }
+
+    blocks
+}
+
+fn language_of(block: &FencedBlock) -> Option<&'static str> {
+    match block
+        .info
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+        .as_str()
+    {
+        "sh" | "shell" => Some("sh"),
+        "bash" => Some("bash"),
+        "python" | "python3" | "py" => Some("python"),
+        _ => None,
+    }
+}
+
+// The runnable blocks, numbered in order; output blocks don't count
+fn find_runnable_block(content: &str, block_index: usize) -> Result<(String, String), String> {
+    let lines: Vec<&str> = content.lines().collect();
+    parse_fenced_blocks(&lines)
+        .iter()
+        .filter_map(|block| language_of(block).map(|language| (language, block)))
+        .nth(block_index)
+        .map(|(language, block)| (language.to_string(), block.code.clone()))
+        .ok_or_else(|| format!("No runnable code block {}", block_index))
+}
+
+fn is_enabled<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
+    let settings_path = crate::data_dir::get_app_data_dir(app_handle).join("settings.json");
+
+    fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|settings| settings["code_execution_enabled"].as_bool())
+        .unwrap_or(false)
+}
+
+fn interpreter(language: &str) -> (&'static str, &'static str) {
+    match language {
+        "bash" => ("bash", "-c"),
+        "python" if cfg!(windows) => ("python", "-c"),
+        "python" => ("python3", "-c"),
+        _ => ("sh", "-c"),
+    }
+}
+
+fn push_limited(buffer: &mut Vec<u8>, bytes: &[u8], truncated: &mut bool) {
+    let room = MAX_OUTPUT_BYTES.saturating_sub(buffer.len());
+    if bytes.len() > room {
+        *truncated = true;
+    }
+    buffer.extend_from_slice(&bytes[..bytes.len().min(room)]);
+}
+
+async fn execute<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    language: &str,
+    code: &str,
+) -> Result<CodeRunResult, String> {
+    let work_dir: PathBuf = std::env::temp_dir().join(format!(
+        "jot-run-{}",
+        chrono::Local::now().format("%Y%m%d_%H%M%S_%f")
+    ));
+    fs::create_dir_all(&work_dir)
+        .map_err(|e| format!("Failed to create working directory: {}", e))?;
+
+    let (program, flag) = interpreter(language);
+    let mut command = app_handle
+        .shell()
+        .command(program)
+        .args([flag, code])
+        .current_dir(&work_dir)
+        .env_clear()
+        .env("HOME", &work_dir)
+        .env("TMPDIR", &work_dir)
+        .env("TEMP", &work_dir)
+        .env("TMP", &work_dir)
+        .set_raw_out(true);
+    for name in KEPT_ENV_VARS {
+        if let Some(value) = std::env::var_os(name) {
+            command = command.env(name, value);
+        }
+    }
+
+    let (mut events, child) = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut truncated = false;
+    let mut exit_code = None;
+
+    let finished = tokio::time::timeout(RUN_TIMEOUT, async {
+        while let Some(event) = events.recv().await {
+            match event {
+                CommandEvent::Stdout(bytes) => push_limited(&mut stdout, &bytes, &mut truncated),
+                CommandEvent::Stderr(bytes) => push_limited(&mut stderr, &bytes, &mut truncated),
+                CommandEvent::Terminated(payload) => exit_code = payload.code,
+                CommandEvent::Error(e) => warn!("Code block run: {}", e),
+                _ => {}
+            }
+        }
+    })
+    .await
+    .is_ok();
+
+    if !finished {
+        if let Err(e) = child.kill() {
+            warn!("Failed to stop code block run: {}", e);
+        }
+    }
+    if let Err(e) = fs::remove_dir_all(&work_dir) {
+        warn!("Failed to remove {}: {}", work_dir.display(), e);
+    }
+
+    Ok(CodeRunResult {
+        exit_code,
+        stdout: String::from_utf8_lossy(&stdout).to_string(),
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+        timed_out: !finished,
+        truncated,
+    })
+}
+
+// The `output` block written beneath a code block
+fn format_output(result: &CodeRunResult) -> Vec<String> {
+    let mut text = result.stdout.clone();
+    if !result.stderr.is_empty() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&result.stderr);
+    }
+    let mut body: Vec<String> = text.lines().map(str::to_string).collect();
+
+    if result.truncated {
+        body.push("[output truncated]".to_string());
+    }
+    if result.timed_out {
+        body.push(format!("[stopped after {} seconds]", RUN_TIMEOUT.as_secs()));
+    } else if let Some(code) = result.exit_code.filter(|code| *code != 0) {
+        body.push(format!("[exit code {}]", code));
+    }
+
+    // Longer than any backtick run in the output, so it can't close early
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat((longest_run + 1).max(3));
+
+    let mut lines = vec![format!("{}{}", fence, OUTPUT_INFO)];
+    lines.extend(body);
+    lines.push(fence);
+    lines
+}
+
+// Put the output beneath the code block, replacing the previous output
+fn insert_output(content: &str, block_index: usize, output: Vec<String>) -> Result<String, String> {
+    let line_ending = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let blocks = parse_fenced_blocks(&lines);
+
+    let block = blocks
+        .iter()
+        .filter(|block| language_of(block).is_some())
+        .nth(block_index)
+        .ok_or_else(|| format!("No runnable code block {}", block_index))?;
+
+    let mut replace_from = (block.end + 1).min(lines.len());
+    let mut replace_to = replace_from;
+    let next_block = if lines
+        .get(replace_from)
+        .is_some_and(|line| line.trim().is_empty())
+    {
+        replace_from + 1
+    } else {
+        replace_from
+    };
+    if let Some(previous) = blocks
+        .iter()
+        .find(|candidate| candidate.start == next_block && candidate.info == OUTPUT_INFO)
+    {
+        replace_to = (previous.end + 1).min(lines.len());
+    } else {
+        replace_from = replace_to;
+    }
+
+    let mut updated: Vec<String> = lines[..replace_from]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    if block.end >= lines.len() {
+        // Close an unclosed block before writing beneath it
+        updated.push("```".to_string());
+    }
+    updated.push(String::new());
+    updated.extend(output);
+    updated.extend(lines[replace_to..].iter().map(|l| l.to_string()));
+
+    let mut result = updated.join(line_ending);
+    if content.ends_with('\n') {
+        result.push_str(line_ending);
+    }
+    Ok(result)
+}
+
+// Ask the user in a native dialog; false if they cancel or close it
+async fn confirm<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    title: &str,
+    message: String,
+    ok: &str,
+) -> bool {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app_handle
+        .dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            ok.to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            let _ = sender.send(confirmed);
+        });
+    receiver.await.unwrap_or(false)
+}
+
+fn write_enabled<R: Runtime>(app_handle: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    crate::data_dir::update_settings(app_handle, |settings| {
+        settings["code_execution_enabled"] = serde_json::json!(enabled);
+        Ok(())
+    })
+}
+
+// Turn running code blocks on (after the user confirms it) or off; returns
+// whether it is on
+#[tauri::command]
+pub async fn set_code_execution_enabled<R: Runtime>(
+    app_handle: AppHandle<R>,
+    enabled: bool,
+) -> Result<bool, String> {
+    let _timing = crate::metrics_service::time_async_command("set_code_execution_enabled");
+    if enabled
+        && !is_enabled(&app_handle)
+        && !confirm(
+            &app_handle,
+            "Allow running code blocks?",
+            "Jot will be able to run sh and python code blocks from your notes. \
+             The code runs with your account's permissions and can change or \
+             delete your files. Each run will still ask for confirmation."
+                .to_string(),
+            "Allow",
+        )
+        .await
+    {
+        return Ok(false);
+    }
+
+    write_enabled(&app_handle, enabled)?;
+    info!(
+        "Running code blocks {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(enabled)
+}
+
+// Run a code block after the user confirms it, and write its output beneath it
+#[tauri::command]
+pub async fn run_code_block<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    block_index: usize,
+) -> Result<CodeRunResult, String> {
+    let _timing = crate::metrics_service::time_async_command("run_code_block");
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+    if !is_enabled(&app_handle) {
+        return Err("Running code blocks is turned off in the settings".to_string());
+    }
+
+    crate::autosave_service::flush_all(&app_handle)?;
+    let content = storage_service::read_note(&app_handle, tab_index)?.unwrap_or_default();
+    let (language, confirmed_code) = find_runnable_block(&content, block_index)?;
+    if confirmed_code.chars().count() > MAX_CONFIRMED_CODE_CHARS {
+        return Err(format!(
+            "Code blocks longer than {} characters can't be run",
+            MAX_CONFIRMED_CODE_CHARS
+        ));
+    }
+
+    let confirmed = confirm(
+        &app_handle,
+        "Run this code block?",
+        format!(
+            "This {} code from note {} will run with your account's permissions:\n\n{}",
+            language,
+            tab_index + 1,
+            confirmed_code
+        ),
+        "Run",
+    )
+    .await;
+    if !confirmed {
+        return Err("The run was cancelled".to_string());
+    }
+
+    // Run exactly what was shown, even if the note changed meanwhile
+    crate::autosave_service::flush_all(&app_handle)?;
+    let content = storage_service::read_note(&app_handle, tab_index)?.unwrap_or_default();
+    let (language, code) = find_runnable_block(&content, block_index)?;
+    if code != confirmed_code {
+        return Err("The code block changed since it was confirmed".to_string());
+    }
+
+    info!(
+        "Running {} code block {} of note {}",
+        language, block_index, tab_index
+    );
+    let result = execute(&app_handle, &language, &code).await?;
+
+    // The note may have been edited while the code ran
+    crate::autosave_service::flush_all(&app_handle)?;
+    let content = storage_service::read_note(&app_handle, tab_index)?.unwrap_or_default();
+    let updated = insert_output(&content, block_index, format_output(&result))?;
+    storage_service::write_note_for_operation(&app_handle, tab_index, &updated, "Run code block")?;
+
+    crate::note_events::emit_note_updated(&app_handle, tab_index, &updated)?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks_of(content: &str) -> Vec<(String, usize, usize, String)> {
+        let lines: Vec<&str> = content.lines().collect();
+        parse_fenced_blocks(&lines)
+            .into_iter()
+            .map(|block| (block.info, block.start, block.end, block.code))
+            .collect()
+    }
+
+    fn output(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn nested_fence_stays_inside_the_longer_one() {
+        let content = "````markdown\n```sh\necho hi\n```\n````\n```sh\nls\n```";
+        assert_eq!(
+            blocks_of(content),
+            vec![
+                (
+                    "markdown".to_string(),
+                    0,
+                    4,
+                    "```sh\necho hi\n```".to_string()
+                ),
+                ("sh".to_string(), 5, 7, "ls".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn fence_is_only_closed_by_the_same_character() {
+        let content = "~~~py\nprint(1)\n```\n~~~";
+        assert_eq!(
+            blocks_of(content),
+            vec![("py".to_string(), 0, 3, "print(1)\n```".to_string())]
+        );
+    }
+
+    #[test]
+    fn unclosed_fence_runs_to_the_end() {
+        let content = "text\n```sh\necho hi\nls";
+        assert_eq!(
+            blocks_of(content),
+            vec![("sh".to_string(), 1, 4, "echo hi\nls".to_string())]
+        );
+    }
+
+    #[test]
+    fn runnable_blocks_skip_other_languages_and_output() {
+        let content = "```text\nnot code\n```\n```output\nold\n```\n```python\nprint(2)\n```";
+        assert_eq!(
+            find_runnable_block(content, 0),
+            Ok(("python".to_string(), "print(2)".to_string()))
+        );
+        assert!(find_runnable_block(content, 1).is_err());
+    }
+
+    #[test]
+    fn output_goes_beneath_the_block() {
+        let content = "# Deploy\n\n```sh\necho hi\n```\nafter\n";
+        let updated = insert_output(content, 0, output(&["```output", "hi", "```"])).unwrap();
+        assert_eq!(
+            updated,
+            "# Deploy\n\n```sh\necho hi\n```\n\n```output\nhi\n```\nafter\n"
+        );
+    }
+
+    #[test]
+    fn rerun_replaces_the_previous_output() {
+        let content = "```sh\necho hi\n```\n\n```output\nhi\n```\nafter\n";
+        let updated = insert_output(content, 0, output(&["```output", "bye", "```"])).unwrap();
+        assert_eq!(
+            updated,
+            "```sh\necho hi\n```\n\n```output\nbye\n```\nafter\n"
+        );
+
+        // Running again with the same output changes nothing
+        let again = insert_output(&updated, 0, output(&["```output", "bye", "```"])).unwrap();
+        assert_eq!(again, updated);
+    }
+
+    #[test]
+    fn rerun_of_second_block_keeps_the_first_output() {
+        let content = "```sh\na\n```\n\n```output\n1\n```\n```sh\nb\n```\n";
+        let updated = insert_output(content, 1, output(&["```output", "2", "```"])).unwrap();
+        assert_eq!(
+            updated,
+            "```sh\na\n```\n\n```output\n1\n```\n```sh\nb\n```\n\n```output\n2\n```\n"
+        );
+    }
+
+    #[test]
+    fn output_of_an_unclosed_block_closes_it_first() {
+        let updated = insert_output("```sh\necho hi", 0, output(&["```output", "hi", "```"]));
+        assert_eq!(
+            updated,
+            Ok("```sh\necho hi\n```\n\n```output\nhi\n```".to_string())
+        );
+    }
+
+    #[test]
+    fn output_fence_is_longer_than_backticks_in_the_output() {
+        let result = CodeRunResult {
+            exit_code: Some(1),
+            stdout: "```\n".to_string(),
+            stderr: "failed".to_string(),
+            timed_out: false,
+            truncated: false,
+        };
+        assert_eq!(
+            format_output(&result),
+            output(&["````output", "```", "failed", "[exit code 1]", "````"])
+        );
+    }
+}
@@ -0,0 +1,239 @@
+// self_test_service.rs - One-shot smoke test of a build: save, load, back up
+// and restore notes in a throwaway profile and report what worked
+//
+// The test runs in a separate process started with --self-test and
+// JOT_DATA_DIR pointing at a temporary directory, so the user's notes and
+// settings are never touched. Packagers can run `jot --self-test` directly;
+// it prints the report as JSON and exits with 1 if a step failed.
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+pub const SELF_TEST_FLAG: &str = "--self-test";
+
+const DATA_DIR_ENV: &str = "JOT_DATA_DIR";
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestStep {
+    pub name: String,
+    pub status: StepStatus,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub version: String,
+    pub platform: String,
+    pub steps: Vec<SelfTestStep>,
+}
+
+fn sample_note(tab_index: usize) -> String {
+    format!(
+        "---\ntitle: Self test {}\n---\n# Self test {}\n\n- [ ] task\n- ünïcödé ✓\n",
+        tab_index + 1,
+        tab_index + 1
+    )
+}
+
+struct Steps {
+    steps: Vec<SelfTestStep>,
+}
+
+impl Steps {
+    fn record(&mut self, name: &str, started: Instant, result: Result<String, String>) {
+        let (status, detail) = match result {
+            Ok(detail) => (StepStatus::Passed, detail),
+            Err(e) => (StepStatus::Failed, e),
+        };
+        self.steps.push(SelfTestStep {
+            name: name.to_string(),
+            status,
+            detail,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        self.steps.push(SelfTestStep {
+            name: name.to_string(),
+            status: StepStatus::Skipped,
+            detail: reason.to_string(),
+            duration_ms: 0,
+        });
+    }
+}
+
+fn check_profile(app_handle: &AppHandle) -> Result<String, String> {
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
+    let expected = std::env::var_os(DATA_DIR_ENV).map(PathBuf::from);
+    if expected.as_deref() != Some(app_dir.as_path()) {
+        return Err(format!(
+            "Refusing to run outside a temporary profile (data directory is {})",
+            app_dir.display()
+        ));
+    }
+    if crate::data_dir::is_read_only() {
+        return Err(format!("{} is not writable", app_dir.display()));
+    }
+    Ok(app_dir.display().to_string())
+}
+
+fn save_notes(app_handle: &AppHandle) -> Result<String, String> {
+    for tab_index in 0..7 {
+        crate::save_note(app_handle.clone(), tab_index, sample_note(tab_index))?;
+    }
+    Ok("Saved 7 notes".to_string())
+}
+
+fn load_notes(app_handle: &AppHandle) -> Result<String, String> {
+    let notes = crate::load_notes(app_handle.clone())?;
+    for tab_index in 0..7 {
+        let note = notes
+            .iter()
+            .find(|note| note.tab_index == tab_index)
+            .ok_or_else(|| format!("Note {} was not loaded", tab_index))?;
+        if note.content != sample_note(tab_index) {
+            return Err(format!("Note {} does not match what was saved", tab_index));
+        }
+    }
+    Ok(format!("Loaded {} notes", notes.len()))
+}
+
+async fn back_up_and_restore(app_handle: &AppHandle) -> Result<String, String> {
+    let backup_path = crate::backup_service::create_backup(app_handle.clone()).await?;
+
+    crate::save_note(
+        app_handle.clone(),
+        0,
+        "changed after the backup".to_string(),
+    )?;
+    crate::backup_service::restore_backup(app_handle.clone(), backup_path.clone(), None).await?;
+
+    let restored = crate::storage_service::read_note(app_handle, 0)?.unwrap_or_default();
+    if restored != sample_note(0) {
+        return Err("The restored note does not match the backup".to_string());
+    }
+    Ok(format!("Restored {}", backup_path))
+}
+
+// Run every step against the current (temporary) profile
+async fn run_steps(app_handle: &AppHandle) -> SelfTestReport {
+    let mut steps = Steps { steps: Vec::new() };
+
+    let started = Instant::now();
+    let profile = check_profile(app_handle);
+    let profile_ok = profile.is_ok();
+    steps.record("Temporary profile", started, profile);
+
+    if profile_ok {
+        let started = Instant::now();
+        steps.record("Save notes", started, save_notes(app_handle));
+        let started = Instant::now();
+        steps.record("Load notes", started, load_notes(app_handle));
+        let started = Instant::now();
+        steps.record(
+            "Back up and restore",
+            started,
+            back_up_and_restore(app_handle).await,
+        );
+    }
+    steps.skip("Sync", "This build has no sync backend");
+
+    SelfTestReport {
+        passed: steps
+            .steps
+            .iter()
+            .all(|step| step.status != StepStatus::Failed),
+        version: app_handle.package_info().version.to_string(),
+        platform: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        steps: steps.steps,
+    }
+}
+
+// Entry point of `jot --self-test`: runs the steps without opening a window,
+// prints the report and exits
+pub fn run_from_command_line(mut context: tauri::Context<tauri::Wry>) -> ! {
+    // Without a profile from the caller, use a fresh one and clean it up
+    let own_profile = std::env::var_os(DATA_DIR_ENV).is_none();
+    let profile_dir = std::env::temp_dir().join(format!("jot-self-test-{}", std::process::id()));
+    if own_profile {
+        std::env::set_var(DATA_DIR_ENV, &profile_dir);
+    }
+
+    context.config_mut().app.windows.clear();
+    context.config_mut().app.tray_icon = None;
+    let app = match tauri::Builder::default().build(context) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("Failed to start Jot for the self test: {}", e);
+            std::process::exit(2);
+        }
+    };
+    crate::data_dir::init(app.handle());
+
+    let report = tauri::async_runtime::block_on(run_steps(app.handle()));
+    crate::autosave_service::flush_on_exit(app.handle());
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Failed to serialize self test report: {}", e),
+    }
+    if own_profile {
+        let _ = fs::remove_dir_all(&profile_dir);
+    }
+    std::process::exit(if report.passed { 0 } else { 1 });
+}
+
+// Run the self test in a separate process against a temporary profile
+#[tauri::command]
+pub async fn run_self_test() -> Result<SelfTestReport, String> {
+    let _timing = crate::metrics_service::time_async_command("run_self_test");
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Failed to find the Jot executable: {}", e))?;
+    let profile_dir = std::env::temp_dir().join(format!(
+        "jot-self-test-{}",
+        chrono::Local::now().format("%Y%m%d%H%M%S%3f")
+    ));
+
+    info!("Running self test in {}", profile_dir.display());
+    let mut command = tokio::process::Command::new(exe);
+    command
+        .arg(SELF_TEST_FLAG)
+        .env(DATA_DIR_ENV, &profile_dir)
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(SELF_TEST_TIMEOUT, command.output()).await;
+    let _ = fs::remove_dir_all(&profile_dir);
+
+    let output = output
+        .map_err(|_| format!("Self test timed out after {:?}", SELF_TEST_TIMEOUT))?
+        .map_err(|e| format!("Failed to run self test: {}", e))?;
+    let report: SelfTestReport = serde_json::from_slice(&output.stdout).map_err(|e| {
+        format!(
+            "Failed to read self test report: {} ({})",
+            e,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    })?;
+
+    info!(
+        "Self test {}",
+        if report.passed { "passed" } else { "failed" }
+    );
+    Ok(report)
+}
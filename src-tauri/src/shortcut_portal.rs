@@ -0,0 +1,200 @@
+// shortcut_portal.rs - Global shortcut through the XDG Desktop Portal
+//
+// Wayland compositors don't let applications grab keys; the GlobalShortcuts
+// portal asks the compositor to bind the shortcut (usually after the user
+// confirms it) and signals us when it's pressed.
+use futures_util::StreamExt;
+use log::{info, warn};
+use std::collections::HashMap;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, Proxy};
+
+const PORTAL_BUS: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SHORTCUTS_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+const TOGGLE_SHORTCUT_ID: &str = "toggle-window";
+
+// `Control+Shift+J` -> `CTRL+SHIFT+j`, as in the XDG shortcuts spec
+fn to_portal_trigger(shortcut: &str) -> String {
+    shortcut
+        .split('+')
+        .map(|part| match part.to_lowercase().as_str() {
+            "control" | "ctrl" => "CTRL".to_string(),
+            "shift" => "SHIFT".to_string(),
+            "alt" => "ALT".to_string(),
+            "super" | "meta" | "cmd" | "command" => "LOGO".to_string(),
+            key => key.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+fn value_to_string(value: &OwnedValue) -> Option<String> {
+    match &**value {
+        Value::Str(s) => Some(s.to_string()),
+        Value::ObjectPath(path) => Some(path.to_string()),
+        _ => None,
+    }
+}
+
+// Call a portal method that answers through a Request object's Response signal
+async fn portal_request<B>(
+    connection: &Connection,
+    method: &str,
+    body: &B,
+    token: &str,
+) -> Result<HashMap<String, OwnedValue>, String>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    // Subscribe before calling, so a fast answer isn't missed
+    let sender = connection
+        .unique_name()
+        .map(|name| name.trim_start_matches(':').replace('.', "_"))
+        .ok_or("No D-Bus connection name")?;
+    let request_path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+
+    let request = Proxy::new(
+        connection,
+        PORTAL_BUS,
+        request_path.as_str(),
+        REQUEST_INTERFACE,
+    )
+    .await
+    .map_err(|e| format!("Failed to create portal request: {}", e))?;
+    let mut responses = request
+        .receive_signal("Response")
+        .await
+        .map_err(|e| format!("Failed to listen for portal response: {}", e))?;
+
+    let portal = Proxy::new(connection, PORTAL_BUS, PORTAL_PATH, SHORTCUTS_INTERFACE)
+        .await
+        .map_err(|e| format!("Failed to connect to the shortcuts portal: {}", e))?;
+    portal
+        .call_method(method, body)
+        .await
+        .map_err(|e| format!("Shortcuts portal {} failed: {}", method, e))?;
+
+    let response = responses
+        .next()
+        .await
+        .ok_or("Shortcuts portal closed without answering")?;
+    let (code, results): (u32, HashMap<String, OwnedValue>) = response
+        .body()
+        .deserialize()
+        .map_err(|e| format!("Invalid portal response: {}", e))?;
+
+    match code {
+        0 => Ok(results),
+        1 => Err("The shortcut was declined".to_string()),
+        _ => Err(format!("Shortcuts portal {} failed", method)),
+    }
+}
+
+// Bind the show/hide shortcut through the portal and call `on_activated`
+// whenever it's pressed. Returns the trigger the compositor reports.
+pub async fn register<F>(
+    shortcut: &str,
+    description: &str,
+    on_activated: F,
+) -> Result<String, String>
+where
+    F: Fn() + Send + 'static,
+{
+    let connection = Connection::session()
+        .await
+        .map_err(|e| format!("No D-Bus session: {}", e))?;
+
+    let token = format!("jot{}", std::process::id());
+
+    let session_options = HashMap::from([
+        ("handle_token", Value::from(format!("{}_session", token))),
+        ("session_handle_token", Value::from(token.clone())),
+    ]);
+    let results = portal_request(
+        &connection,
+        "CreateSession",
+        &(session_options,),
+        &format!("{}_session", token),
+    )
+    .await?;
+    let session_handle = results
+        .get("session_handle")
+        .and_then(value_to_string)
+        .ok_or("Shortcuts portal returned no session")?;
+    let session_path = OwnedObjectPath::try_from(session_handle.as_str())
+        .map_err(|e| format!("Invalid portal session {}: {}", session_handle, e))?;
+
+    let shortcuts = vec![(
+        TOGGLE_SHORTCUT_ID,
+        HashMap::from([
+            ("description", Value::from(description)),
+            (
+                "preferred_trigger",
+                Value::from(to_portal_trigger(shortcut)),
+            ),
+        ]),
+    )];
+    let bind_options = HashMap::from([("handle_token", Value::from(format!("{}_bind", token)))]);
+    let results = portal_request(
+        &connection,
+        "BindShortcuts",
+        &(session_path.clone(), shortcuts, "", bind_options),
+        &format!("{}_bind", token),
+    )
+    .await?;
+
+    // What the compositor actually bound, which the user may have changed
+    let trigger = results
+        .get("shortcuts")
+        .and_then(|value| {
+            <Vec<(String, HashMap<String, OwnedValue>)>>::try_from(value.try_clone().ok()?).ok()
+        })
+        .and_then(|bound| {
+            bound
+                .into_iter()
+                .find(|(id, _)| id == TOGGLE_SHORTCUT_ID)
+                .and_then(|(_, properties)| {
+                    properties
+                        .get("trigger_description")
+                        .and_then(value_to_string)
+                })
+        })
+        .filter(|trigger| !trigger.is_empty())
+        .unwrap_or_else(|| shortcut.to_string());
+
+    let portal = Proxy::new(&connection, PORTAL_BUS, PORTAL_PATH, SHORTCUTS_INTERFACE)
+        .await
+        .map_err(|e| format!("Failed to connect to the shortcuts portal: {}", e))?;
+    let mut activations = portal
+        .receive_signal("Activated")
+        .await
+        .map_err(|e| format!("Failed to listen for shortcut activations: {}", e))?;
+
+    info!(
+        "Global shortcut bound through the desktop portal: {}",
+        trigger
+    );
+
+    // The session lives as long as this connection, so the task keeps it
+    tauri::async_runtime::spawn(async move {
+        let _connection = connection;
+        while let Some(signal) = activations.next().await {
+            match signal
+                .body()
+                .deserialize::<(OwnedObjectPath, String, u64, HashMap<String, OwnedValue>)>()
+            {
+                Ok((session, id, _, _)) if session == session_path && id == TOGGLE_SHORTCUT_ID => {
+                    on_activated()
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Invalid shortcut activation: {}", e),
+            }
+        }
+        warn!("Desktop portal shortcut session ended");
+    });
+
+    Ok(trigger)
+}
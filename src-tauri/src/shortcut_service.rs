@@ -0,0 +1,242 @@
+// shortcut_service.rs - Global show/hide shortcut with platform defaults and fallbacks
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fs;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+const SHORTCUT_SETTING: &str = "global_shortcut";
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    pub shortcut: String,
+    pub requested: String,
+    pub fallback: bool,
+    // "native" (key grab) or "portal" (XDG Desktop Portal)
+    pub backend: String,
+    // Key grabs under Wayland only fire while a Jot (XWayland) window is focused
+    pub reliable: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutReport {
+    pub session_type: String,
+    pub active: Option<ShortcutStatus>,
+    pub message: Option<String>,
+}
+
+static ACTIVE_SHORTCUT: Lazy<Mutex<Option<ShortcutStatus>>> = Lazy::new(|| Mutex::new(None));
+static SHORTCUT_MESSAGE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+// "wayland", "x11", or "native" on other platforms
+pub fn session_type() -> String {
+    if !cfg!(target_os = "linux") {
+        return "native".to_string();
+    }
+
+    match std::env::var("XDG_SESSION_TYPE") {
+        Ok(session) if !session.is_empty() => session.to_lowercase(),
+        _ if std::env::var_os("WAYLAND_DISPLAY").is_some() => "wayland".to_string(),
+        _ => "x11".to_string(),
+    }
+}
+
+fn set_message(message: Option<String>) {
+    if let Ok(mut current) = SHORTCUT_MESSAGE.lock() {
+        *current = message;
+    }
+}
+
+fn activate(app_handle: &AppHandle, status: ShortcutStatus) {
+    if let Err(e) = app_handle.emit("shortcut-registered", &status) {
+        error!("Failed to emit shortcut-registered event: {}", e);
+    }
+    if let Ok(mut active) = ACTIVE_SHORTCUT.lock() {
+        *active = Some(status);
+    }
+}
+
+// Linux input method frameworks (ibus, fcitx) advertise themselves via the
+// environment; they commonly claim Ctrl+Shift combinations
+fn input_method_active() -> bool {
+    cfg!(target_os = "linux")
+        && ["XMODIFIERS", "GTK_IM_MODULE", "QT_IM_MODULE"]
+            .iter()
+            .any(|var| std::env::var(var).is_ok_and(|value| !value.is_empty()))
+}
+
+// Default shortcuts in order of preference for this platform
+fn default_shortcuts() -> Vec<&'static str> {
+    if cfg!(target_os = "macos") {
+        vec!["Super+Shift+J", "Super+Alt+J", "Control+Alt+J"]
+    } else if input_method_active() {
+        vec!["Control+Alt+J", "Alt+Shift+J", "Control+Shift+J"]
+    } else {
+        vec!["Control+Shift+J", "Control+Alt+J", "Alt+Shift+J"]
+    }
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    let settings_path = crate::data_dir::get_app_data_dir(app_handle).join("settings.json");
+
+    fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+fn save_shortcut_setting<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    shortcut: &str,
+) -> Result<(), String> {
+    crate::data_dir::update_settings(app_handle, |settings| {
+        settings[SHORTCUT_SETTING] = serde_json::json!(shortcut);
+        Ok(())
+    })
+}
+
+fn shortcut_candidates(app_handle: &AppHandle) -> (Vec<String>, bool) {
+    let configured = read_settings(app_handle)[SHORTCUT_SETTING]
+        .as_str()
+        .filter(|shortcut| !shortcut.is_empty())
+        .map(str::to_string);
+    let first_run = configured.is_none();
+
+    let mut candidates: Vec<String> = configured.into_iter().collect();
+    for shortcut in default_shortcuts() {
+        if !candidates.iter().any(|c| c.eq_ignore_ascii_case(shortcut)) {
+            candidates.push(shortcut.to_string());
+        }
+    }
+
+    (candidates, first_run)
+}
+
+// Register the configured shortcut, or on first run the platform default.
+// Under Wayland the desktop portal is asked first, since key grabs only work
+// there while Jot is focused. Otherwise, if a shortcut is already taken, fall
+// back to the next candidate. Which shortcut ended up active is reported with
+// a `shortcut-registered` event.
+pub fn register_toggle_shortcut(app_handle: &AppHandle) {
+    #[cfg(target_os = "linux")]
+    if session_type() == "wayland" {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let (candidates, _) = shortcut_candidates(&app_handle);
+            let requested = candidates[0].clone();
+            let description = crate::i18n_service::translate(&app_handle, "tray.toggle", &[]);
+
+            let toggle_handle = app_handle.clone();
+            match crate::shortcut_portal::register(&requested, &description, move || {
+                crate::toggle_window(&toggle_handle)
+            })
+            .await
+            {
+                Ok(trigger) => {
+                    set_message(None);
+                    activate(
+                        &app_handle,
+                        ShortcutStatus {
+                            shortcut: trigger,
+                            requested,
+                            fallback: false,
+                            backend: "portal".to_string(),
+                            reliable: true,
+                        },
+                    );
+                }
+                Err(e) => {
+                    warn!("Desktop portal shortcut unavailable: {}", e);
+                    register_native(&app_handle);
+                    set_message(Some(format!(
+                        "The desktop doesn't offer global shortcuts to apps ({}); the shortcut \
+                         only works while Jot is focused. Bind a system shortcut to run Jot instead.",
+                        e
+                    )));
+                }
+            }
+        });
+        return;
+    }
+
+    register_native(app_handle);
+}
+
+fn register_native(app_handle: &AppHandle) {
+    let (candidates, first_run) = shortcut_candidates(app_handle);
+    let requested = candidates[0].clone();
+    let reliable = session_type() != "wayland";
+
+    for (index, candidate) in candidates.iter().enumerate() {
+        match app_handle.global_shortcut().register(candidate.as_str()) {
+            Ok(()) => {
+                let status = ShortcutStatus {
+                    shortcut: candidate.clone(),
+                    requested: requested.clone(),
+                    fallback: index > 0,
+                    backend: "native".to_string(),
+                    reliable,
+                };
+
+                if status.fallback {
+                    warn!(
+                        "Shortcut {} is unavailable, using {} instead",
+                        requested, candidate
+                    );
+                } else {
+                    info!("Registered global shortcut {}", candidate);
+                }
+
+                // Remember the first-run choice so it stays stable
+                if first_run {
+                    if let Err(e) = save_shortcut_setting(app_handle, candidate) {
+                        warn!("{}", e);
+                    }
+                }
+
+                activate(app_handle, status);
+                return;
+            }
+            Err(e) => warn!("Failed to register shortcut {}: {}", candidate, e),
+        }
+    }
+
+    error!(
+        "No global shortcut could be registered (tried {:?})",
+        candidates
+    );
+    set_message(Some(format!(
+        "None of the shortcuts {} could be registered",
+        candidates.join(", ")
+    )));
+    if let Err(e) = app_handle.emit("shortcut-registration-failed", &candidates) {
+        error!("Failed to emit shortcut-registration-failed event: {}", e);
+    }
+}
+
+// The shortcut that is currently registered, if any
+#[tauri::command]
+pub fn get_active_shortcut() -> Option<ShortcutStatus> {
+    ACTIVE_SHORTCUT
+        .lock()
+        .ok()
+        .and_then(|active| active.clone())
+}
+
+// What actually works on this desktop: session type, active shortcut and
+// backend, and an explanation if it's limited or failed
+#[tauri::command]
+pub fn get_shortcut_status() -> ShortcutReport {
+    ShortcutReport {
+        session_type: session_type(),
+        active: get_active_shortcut(),
+        message: SHORTCUT_MESSAGE
+            .lock()
+            .ok()
+            .and_then(|message| message.clone()),
+    }
+}
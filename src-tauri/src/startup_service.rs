@@ -0,0 +1,167 @@
+// startup_service.rs - Which tab is shown on launch and when the window is
+// summoned with the global shortcut
+//
+// Each can keep the last active tab, always open one tab, or open today's
+// weekday tab. Weekdays map to tabs Monday first (tab 0 by default), in the
+// configured time zone or the local one.
+use chrono::{Datelike, Weekday};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Runtime};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TabBehavior {
+    #[default]
+    Last,
+    Fixed,
+    Weekday,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupBehavior {
+    pub on_launch: TabBehavior,
+    pub on_show: TabBehavior,
+    // Tab opened by the `fixed` behavior
+    pub fixed_tab: usize,
+    // Tab for each weekday, Monday first
+    pub weekday_tabs: Vec<usize>,
+    // IANA time zone deciding what "today" is, e.g. "Europe/Berlin"; None
+    // for the system's
+    pub timezone: Option<String>,
+}
+
+fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("settings.json")
+}
+
+fn read_settings<R: Runtime>(app_handle: &AppHandle<R>) -> serde_json::Value {
+    fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+fn parse_behavior(value: &serde_json::Value) -> TabBehavior {
+    serde_json::from_value(value.clone()).unwrap_or_default()
+}
+
+fn parse_timezone(name: &str) -> Result<chrono_tz::Tz, String> {
+    name.parse()
+        .map_err(|_| format!("Unknown time zone: {}", name))
+}
+
+pub fn load_startup_behavior(settings: &serde_json::Value) -> StartupBehavior {
+    let weekday_tabs: Vec<usize> = settings["startup_weekday_tabs"]
+        .as_array()
+        .map(|tabs| {
+            tabs.iter()
+                .filter_map(|tab| tab.as_u64().map(|tab| tab as usize))
+                .collect()
+        })
+        .filter(|tabs: &Vec<usize>| tabs.len() == 7 && tabs.iter().all(|tab| *tab < 7))
+        .unwrap_or_else(|| (0..7).collect());
+
+    StartupBehavior {
+        on_launch: parse_behavior(&settings["startup_tab"]),
+        on_show: parse_behavior(&settings["show_tab"]),
+        fixed_tab: settings["startup_fixed_tab"]
+            .as_u64()
+            .map(|tab| tab as usize)
+            .filter(|tab| *tab < 7)
+            .unwrap_or(0),
+        weekday_tabs,
+        timezone: settings["startup_timezone"]
+            .as_str()
+            .filter(|name| !name.is_empty())
+            .map(str::to_string),
+    }
+}
+
+fn today(timezone: Option<&str>) -> Weekday {
+    match timezone.map(parse_timezone) {
+        Some(Ok(tz)) => chrono::Utc::now().with_timezone(&tz).weekday(),
+        Some(Err(e)) => {
+            warn!("{}; using the local time zone", e);
+            chrono::Local::now().weekday()
+        }
+        None => chrono::Local::now().weekday(),
+    }
+}
+
+// The tab a behavior opens; None keeps the last active one
+fn resolve(behavior: TabBehavior, startup: &StartupBehavior) -> Option<usize> {
+    match behavior {
+        TabBehavior::Last => None,
+        TabBehavior::Fixed => Some(startup.fixed_tab),
+        TabBehavior::Weekday => {
+            let weekday = today(startup.timezone.as_deref());
+            startup
+                .weekday_tabs
+                .get(weekday.num_days_from_monday() as usize)
+                .copied()
+        }
+    }
+}
+
+// The tab to open on launch, if not the last active one
+pub fn launch_tab(settings: &serde_json::Value) -> Option<usize> {
+    let startup = load_startup_behavior(settings);
+    resolve(startup.on_launch, &startup)
+}
+
+// Switch tabs, if configured, when the window is summoned
+pub fn on_window_shown<R: Runtime>(app_handle: &AppHandle<R>) {
+    let startup = load_startup_behavior(&read_settings(app_handle));
+    if let Some(tab) = resolve(startup.on_show, &startup) {
+        if let Err(e) = app_handle.emit("activate-tab", tab) {
+            warn!("Failed to emit activate-tab event: {}", e);
+        }
+    }
+}
+
+// Get what happens on launch and when the window is summoned
+#[tauri::command]
+pub fn get_startup_behavior<R: Runtime>(app_handle: AppHandle<R>) -> StartupBehavior {
+    load_startup_behavior(&read_settings(&app_handle))
+}
+
+// Choose what happens on launch and when the window is summoned
+#[tauri::command]
+pub fn set_startup_behavior<R: Runtime>(
+    app_handle: AppHandle<R>,
+    behavior: StartupBehavior,
+) -> Result<(), String> {
+    if behavior.fixed_tab >= 7 {
+        return Err(format!("Invalid tab index: {}", behavior.fixed_tab));
+    }
+    if behavior.weekday_tabs.len() != 7 {
+        return Err("Weekday tabs must list a tab for each day of the week".to_string());
+    }
+    if let Some(tab) = behavior.weekday_tabs.iter().find(|tab| **tab >= 7) {
+        return Err(format!("Invalid tab index: {}", tab));
+    }
+    let timezone = behavior
+        .timezone
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| parse_timezone(name.trim()).map(|_| name.trim().to_string()))
+        .transpose()?;
+
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        settings["startup_tab"] = serde_json::json!(behavior.on_launch);
+        settings["show_tab"] = serde_json::json!(behavior.on_show);
+        settings["startup_fixed_tab"] = serde_json::json!(behavior.fixed_tab);
+        settings["startup_weekday_tabs"] = serde_json::json!(behavior.weekday_tabs);
+        settings["startup_timezone"] = serde_json::json!(timezone);
+        Ok(())
+    })?;
+
+    info!(
+        "Startup tab set to {:?}, on show {:?}",
+        behavior.on_launch, behavior.on_show
+    );
+    Ok(())
+}
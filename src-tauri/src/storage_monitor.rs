@@ -0,0 +1,375 @@
+// storage_monitor.rs - Free space and availability of the storage locations
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::storage_service;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_LOW_SPACE_WARNING_MB: u64 = 100;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageLocationStatus {
+    pub path: String,
+    pub custom: bool,
+    pub available: bool,
+    pub free_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub low_space: bool,
+    pub tabs: Vec<usize>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileReport {
+    pub restored: Vec<usize>,
+    pub conflicts: Vec<String>,
+}
+
+// Last seen (available, low_space) per location, to only report transitions
+static LAST_STATUS: Lazy<Mutex<HashMap<String, (bool, bool)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn low_space_threshold<R: Runtime>(app_handle: &AppHandle<R>) -> u64 {
+    let settings_path = crate::data_dir::get_app_data_dir(app_handle).join("settings.json");
+    let settings: serde_json::Value = fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    settings["low_space_warning_mb"]
+        .as_u64()
+        .unwrap_or(DEFAULT_LOW_SPACE_WARNING_MB)
+        * 1024
+        * 1024
+}
+
+// Every configured storage location with the tabs stored there
+fn storage_locations<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<(PathBuf, bool, Vec<usize>)> {
+    let mut locations: Vec<(PathBuf, bool, Vec<usize>)> = Vec::new();
+
+    for tab_index in 0..7 {
+        let (dir, custom) = match storage_service::get_configured_tab_dir(app_handle, tab_index) {
+            Some(dir) => (dir, true),
+            None => (storage_service::get_default_storage_dir(app_handle), false),
+        };
+
+        match locations
+            .iter_mut()
+            .find(|(existing, _, _)| *existing == dir)
+        {
+            Some((_, _, tabs)) => tabs.push(tab_index),
+            None => locations.push((dir, custom, vec![tab_index])),
+        }
+    }
+
+    locations
+}
+
+// Check free space and availability of every storage location
+pub fn check_storage<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<StorageLocationStatus> {
+    let threshold = low_space_threshold(app_handle);
+
+    storage_locations(app_handle)
+        .into_iter()
+        .map(|(dir, custom, tabs)| {
+            // The default directory is local app data, it doesn't go away
+            let available = !custom || storage_service::is_storage_available(&dir);
+            let free_bytes = available.then(|| fs2::available_space(&dir).ok()).flatten();
+            let total_bytes = available.then(|| fs2::total_space(&dir).ok()).flatten();
+
+            StorageLocationStatus {
+                path: dir.to_string_lossy().to_string(),
+                custom,
+                available,
+                free_bytes,
+                total_bytes,
+                low_space: free_bytes.is_some_and(|free| free < threshold),
+                tabs,
+            }
+        })
+        .collect()
+}
+
+fn decoded_equal<R: Runtime>(app_handle: &AppHandle<R>, a: &[u8], b: &[u8]) -> bool {
+    if a == b {
+        return true;
+    }
+    // Encrypted copies of the same text differ byte-wise
+    match (
+        storage_service::decode_note_bytes(app_handle, a),
+        storage_service::decode_note_bytes(app_handle, b),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn move_held_assets(holding_assets: &Path, target_assets: &Path) -> Result<(), String> {
+    let entries = fs::read_dir(holding_assets)
+        .map_err(|e| format!("Failed to read held attachments: {}", e))?;
+
+    fs::create_dir_all(target_assets)
+        .map_err(|e| format!("Failed to create assets directory: {}", e))?;
+
+    for entry in entries.filter_map(Result::ok) {
+        let source = entry.path();
+        if !source.is_file() {
+            continue;
+        }
+        let target = target_assets.join(entry.file_name());
+        // Attachments are content-addressed, an existing file is the same image
+        if !target.exists() {
+            let data =
+                fs::read(&source).map_err(|e| format!("Failed to read held attachment: {}", e))?;
+            storage_service::write_atomic(&target, &data)
+                .map_err(|e| format!("Failed to restore attachment: {}", e))?;
+        }
+        fs::remove_file(&source).map_err(|e| format!("Failed to remove held attachment: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Move notes written to the holding area back to their storage location once
+// it's available again. If the note changed there in the meantime, the other
+// version is kept next to it as a conflict copy.
+pub fn reconcile_holding_area<R: Runtime>(
+    app_handle: &AppHandle<R>,
+) -> Result<ReconcileReport, String> {
+    let holding_dir = storage_service::get_holding_dir(app_handle);
+    let mut report = ReconcileReport::default();
+    if !holding_dir.exists() {
+        return Ok(report);
+    }
+
+    for tab_index in 0..7 {
+        let held_path = holding_dir.join(format!("note_{}.md", tab_index));
+        if !held_path.exists() {
+            continue;
+        }
+
+        let target_path = storage_service::get_note_path(app_handle, tab_index);
+        if target_path.parent() == Some(holding_dir.as_path()) {
+            // Still unavailable
+            continue;
+        }
+
+        let held = fs::read(&held_path)
+            .map_err(|e| format!("Failed to read held note {}: {}", tab_index, e))?;
+
+        if target_path.exists() {
+            let current = fs::read(&target_path)
+                .map_err(|e| format!("Failed to read note {}: {}", tab_index, e))?;
+
+            if !decoded_equal(app_handle, &held, &current) {
+                let conflict_name = format!(
+                    "note_{}.conflict-{}.md",
+                    tab_index,
+                    chrono::Local::now().format("%Y%m%d-%H%M%S")
+                );
+                let conflict_path = target_path.with_file_name(&conflict_name);
+                storage_service::write_atomic(&conflict_path, &current)
+                    .map_err(|e| format!("Failed to save conflict copy: {}", e))?;
+                warn!(
+                    "Note {} changed while storage was unavailable, kept the other version as {}",
+                    tab_index, conflict_name
+                );
+                report.conflicts.push(conflict_name);
+            }
+        }
+
+        storage_service::write_atomic(&target_path, &held)
+            .map_err(|e| format!("Failed to restore note {}: {}", tab_index, e))?;
+        fs::remove_file(&held_path)
+            .map_err(|e| format!("Failed to remove held note {}: {}", tab_index, e))?;
+
+        info!("Moved held note {} back to {:?}", tab_index, target_path);
+        report.restored.push(tab_index);
+    }
+
+    let holding_assets = holding_dir.join(crate::attachments::ASSETS_DIR_NAME);
+    let target_assets = crate::attachments::get_assets_dir(app_handle);
+    if holding_assets.exists() && target_assets != holding_assets {
+        move_held_assets(&holding_assets, &target_assets)?;
+        let _ = fs::remove_dir(&holding_assets);
+    }
+
+    // Only succeeds once everything has been moved out
+    let _ = fs::remove_dir(&holding_dir);
+
+    Ok(report)
+}
+
+fn run_check<R: Runtime>(app_handle: &AppHandle<R>) {
+    let statuses = check_storage(app_handle);
+    let mut availability_changed = false;
+
+    if let Ok(mut last_status) = LAST_STATUS.lock() {
+        for status in &statuses {
+            // Assume locations were fine before the first check
+            let (was_available, was_low) = last_status
+                .insert(status.path.clone(), (status.available, status.low_space))
+                .unwrap_or((true, false));
+
+            if status.available != was_available {
+                availability_changed = true;
+                let event = if status.available {
+                    info!("Storage location {} is available again", status.path);
+                    "storage-available"
+                } else {
+                    warn!(
+                        "Storage location {} is unavailable, holding notes locally",
+                        status.path
+                    );
+                    "storage-unavailable"
+                };
+                if let Err(e) = app_handle.emit(event, status) {
+                    error!("Failed to emit {} event: {}", event, e);
+                }
+            }
+
+            if status.low_space && !was_low {
+                warn!(
+                    "Low disk space at {}: {:?} bytes free",
+                    status.path, status.free_bytes
+                );
+                if let Err(e) = app_handle.emit("storage-low-space", status) {
+                    error!("Failed to emit storage-low-space event: {}", e);
+                }
+            }
+        }
+    }
+
+    match reconcile_holding_area(app_handle) {
+        Ok(report) if !report.restored.is_empty() => {
+            availability_changed = true;
+            if let Err(e) = app_handle.emit("storage-reconciled", &report) {
+                error!("Failed to emit storage-reconciled event: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to reconcile held notes: {}", e),
+    }
+
+    if availability_changed {
+        if let Err(e) = storage_service::start_note_watcher(app_handle) {
+            warn!("Failed to restart note watcher: {}", e);
+        }
+    }
+}
+
+// Periodically check the storage locations in the background
+pub fn start_storage_monitor<R: Runtime>(app_handle: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app_handle.clone();
+            // Network mounts can block, keep this off the async workers
+            if let Err(e) = tokio::task::spawn_blocking(move || run_check(&handle)).await {
+                error!("Storage check failed: {}", e);
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// Free space and availability of every storage location
+#[tauri::command]
+pub fn get_storage_health<R: Runtime>(app_handle: AppHandle<R>) -> Vec<StorageLocationStatus> {
+    check_storage(&app_handle)
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexingExclusion {
+    // Storage directories marked as not to be indexed by Windows Search
+    pub marked: Vec<String>,
+    pub failed: Vec<String>,
+    // PowerShell command (run as administrator) excluding them from Defender
+    // scans, which Jot can't do itself
+    pub defender_command: String,
+}
+
+// Keep Windows Search away from the storage directories, and explain how to
+// exclude them from Defender. Both hold notes open while scanning, which makes
+// saves fail with sharing violations.
+#[tauri::command]
+pub fn exclude_storage_from_indexing<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<IndexingExclusion, String> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // Don't flash a console window
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+        let dirs: Vec<PathBuf> = storage_locations(&app_handle)
+            .into_iter()
+            .map(|(dir, _, _)| dir)
+            .filter(|dir| dir.is_dir())
+            .collect();
+
+        let mut marked = Vec::new();
+        let mut failed = Vec::new();
+        for dir in &dirs {
+            // +I sets "not content indexed"; /D and /S cover the files and
+            // folders already inside
+            let status = std::process::Command::new("attrib")
+                .arg("+I")
+                .arg(dir)
+                .creation_flags(CREATE_NO_WINDOW)
+                .status()
+                .and_then(|_| {
+                    std::process::Command::new("attrib")
+                        .arg("+I")
+                        .arg(dir.join("*"))
+                        .args(["/S", "/D"])
+                        .creation_flags(CREATE_NO_WINDOW)
+                        .status()
+                });
+            match status {
+                Ok(status) if status.success() => marked.push(dir.to_string_lossy().to_string()),
+                Ok(status) => {
+                    warn!("attrib exited with {} for {}", status, dir.display());
+                    failed.push(dir.to_string_lossy().to_string());
+                }
+                Err(e) => {
+                    warn!("Failed to run attrib for {}: {}", dir.display(), e);
+                    failed.push(dir.to_string_lossy().to_string());
+                }
+            }
+        }
+
+        let defender_command = format!(
+            "Add-MpPreference -ExclusionPath {}",
+            dirs.iter()
+                .map(|dir| format!("'{}'", dir.to_string_lossy().replace('\'', "''")))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+
+        info!(
+            "Excluded {} storage directories from indexing",
+            marked.len()
+        );
+
+        Ok(IndexingExclusion {
+            marked,
+            failed,
+            defender_command,
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = app_handle;
+        Err("Indexing exclusion is only needed on Windows".to_string())
+    }
+}
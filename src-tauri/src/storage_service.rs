@@ -1,26 +1,243 @@
 // src/storage_service.rs - Using settings.json for configuration
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
 use log::{error, info, warn};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::{fs, path::PathBuf};
-use tauri::{AppHandle, Manager, Runtime};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::credential_manager;
+
+// Keyring service holding the note encryption passphrase
+const ENCRYPTION_SERVICE: &str = "jot.encryption";
+
+// Encrypted note layout: MAGIC | salt | nonce | ciphertext
+const ENCRYPTED_MAGIC: &[u8] = b"JOTENC1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+// Distinguishes temp files of concurrent atomic writes within this process
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Hash of each note's content as last written by Jot, so the file watcher can
+// tell our own saves apart from external edits
+static KNOWN_NOTE_HASHES: Lazy<Mutex<HashMap<usize, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Tabs whose editor holds edits not yet saved, as reported by the frontend
+static DIRTY_NOTES: Lazy<Mutex<HashSet<usize>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// External changes held back because the tab was dirty when they arrived
+static PENDING_EXTERNAL_CHANGES: Lazy<Mutex<HashMap<usize, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Active watcher on the storage directory; replacing it stops the old one
+static NOTE_WATCHER: Lazy<Mutex<Option<RecommendedWatcher>>> = Lazy::new(|| Mutex::new(None));
+
+// Windows errors raised while another process (Defender, the search indexer,
+// a sync client) has the file open: sharing and lock violations, and access
+// denied for a file pending deletion
+#[cfg(windows)]
+fn is_transient_lock_error(e: &std::io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(32) | Some(33) | Some(5))
+}
+
+// Retry a file operation that failed because the file is briefly held open
+// elsewhere. Elsewhere than Windows files aren't locked that way, so it just
+// runs the operation.
+pub fn retry_if_locked<T>(mut op: impl FnMut() -> std::io::Result<T>) -> std::io::Result<T> {
+    #[cfg(windows)]
+    {
+        let mut delay = std::time::Duration::from_millis(50);
+        for _ in 0..5 {
+            match op() {
+                Err(e) if is_transient_lock_error(&e) => {
+                    warn!("File is in use, retrying in {:?}: {}", delay, e);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+        op().map_err(|e| {
+            if is_transient_lock_error(&e) {
+                std::io::Error::new(
+                    e.kind(),
+                    "The file is in use by another program, often antivirus or search \
+                     indexing. Try again in a moment, or exclude the notes folder from \
+                     scanning and indexing",
+                )
+            } else {
+                e
+            }
+        })
+    }
+    #[cfg(not(windows))]
+    op()
+}
+
+// Write a file atomically: write to a temp file in the same directory, fsync it,
+// then rename it over the target so a crash never leaves a half-written file
+pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path has no file name")
+    })?;
+
+    let temp_path = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| {
+        let mut file = retry_if_locked(|| fs::File::create(&temp_path))?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        drop(file);
+        retry_if_locked(|| fs::rename(&temp_path, path))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Persist the rename itself; directories can't be opened this way on Windows
+    #[cfg(unix)]
+    if let Ok(dir_handle) = fs::File::open(dir) {
+        let _ = dir_handle.sync_all();
+    }
+
+    Ok(())
+}
 
 // Get default storage directory
 pub fn get_default_storage_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
-    app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory")
+    crate::data_dir::get_app_data_dir(app_handle)
 }
 
 // Get settings file path
 fn get_settings_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data directory");
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
 
     app_dir.join("settings.json")
 }
 
+// Marker file written into custom storage directories. A directory without it
+// (and without notes) is most likely an empty mountpoint whose network share
+// has gone away, so Jot must not write through to it.
+const STORAGE_MARKER: &str = ".jot_storage";
+
+// Local holding area for notes while a custom storage directory is unavailable
+const HOLDING_DIR_NAME: &str = "holding";
+
+pub fn get_holding_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    get_default_storage_dir(app_handle).join(HOLDING_DIR_NAME)
+}
+
+pub fn mark_storage_dir(dir: &Path) -> std::io::Result<()> {
+    fs::write(dir.join(STORAGE_MARKER), "")
+}
+
+// Whether a custom storage directory is present and really ours
+pub fn is_storage_available(dir: &Path) -> bool {
+    if dir.join(STORAGE_MARKER).exists() {
+        return true;
+    }
+
+    // Directories set up before the marker existed still hold notes
+    let has_notes = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .any(|entry| note_index_from_path(&entry.path()).is_some())
+        })
+        .unwrap_or(false);
+    if has_notes {
+        if let Err(e) = mark_storage_dir(dir) {
+            warn!("Failed to mark storage directory {:?}: {}", dir, e);
+        }
+    }
+
+    has_notes
+}
+
+// Whether a path is inside iCloud Drive, where macOS may evict files to
+// dataless placeholders that are only downloaded again when read
+pub fn is_icloud_path(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.contains("/Library/Mobile Documents/") || path.ends_with("/Library/Mobile Documents")
+}
+
+// Whether a file is an evicted iCloud placeholder (SF_DATALESS)
+#[cfg(target_os = "macos")]
+fn is_dataless(path: &Path) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x4000_0000;
+
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.st_flags() & SF_DATALESS != 0)
+}
+
+// Download an evicted iCloud file before reading it. Reading a placeholder
+// also downloads it, but can block for a long time or fail with a timeout, so
+// ask for it explicitly and wait a bounded time.
+pub fn ensure_materialized(path: &Path) {
+    #[cfg(target_os = "macos")]
+    {
+        if !is_dataless(path) {
+            return;
+        }
+
+        info!("Downloading evicted iCloud file {:?}", path);
+        if let Err(e) = std::process::Command::new("brctl")
+            .arg("download")
+            .arg(path)
+            .status()
+        {
+            warn!("Failed to request download of {:?}: {}", path, e);
+            return;
+        }
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        while is_dataless(path) {
+            if std::time::Instant::now() >= deadline {
+                warn!("Timed out waiting for iCloud to download {:?}", path);
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = path;
+}
+
+// Warning shown in the storage settings for directories with known pitfalls
+pub fn storage_path_warning(path: &Path) -> Option<String> {
+    is_icloud_path(path).then(|| {
+        "This folder is in iCloud Drive. macOS may remove notes from this Mac to save \
+         space and download them again when opened, which can make loading slow while \
+         offline. Turn off \"Optimize Mac Storage\" or keep the folder downloaded to avoid it."
+            .to_string()
+    })
+}
+
 // Validate a user-provided storage path
 fn validate_storage_path(path: &str) -> Result<PathBuf, String> {
     let path_buf = PathBuf::from(path);
@@ -49,46 +266,495 @@ fn validate_storage_path(path: &str) -> Result<PathBuf, String> {
         Err(e) => return Err(format!("Directory is not writable: {}", e)),
     }
 
+    mark_storage_dir(&path_buf).map_err(|e| format!("Directory is not writable: {}", e))?;
+
+    if let Some(warning) = storage_path_warning(&path_buf) {
+        warn!("Storage path {:?}: {}", path_buf, warning);
+    }
+
     Ok(path_buf)
 }
 
+// The custom storage directory from settings, if one is configured
+pub fn get_custom_storage_dir<R: Runtime>(app_handle: &AppHandle<R>) -> Option<PathBuf> {
+    let settings: serde_json::Value = fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    if !settings["using_custom_storage"].as_bool().unwrap_or(false) {
+        return None;
+    }
+
+    settings["custom_storage_path"]
+        .as_str()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
 // Get the current storage directory based on configuration
 pub fn get_current_storage_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
-    // Load settings from the main settings file
+    match get_custom_storage_dir(app_handle) {
+        Some(custom_path) if is_storage_available(&custom_path) => custom_path,
+        // Custom storage is gone (e.g. unmounted); keep notes locally until it's back
+        Some(_) => get_holding_dir(app_handle),
+        None => get_default_storage_dir(app_handle),
+    }
+}
+
+// Per-tab storage overrides from settings.json ("tab_storage_paths": {"0": "/path"})
+pub fn get_tab_storage_overrides<R: Runtime>(app_handle: &AppHandle<R>) -> HashMap<usize, PathBuf> {
+    let settings: serde_json::Value = fs::read_to_string(get_settings_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    settings["tab_storage_paths"]
+        .as_object()
+        .map(|paths| {
+            paths
+                .iter()
+                .filter_map(|(tab, path)| {
+                    let tab_index = tab.parse::<usize>().ok()?;
+                    let path = path.as_str().filter(|p| !p.is_empty())?;
+                    Some((tab_index, PathBuf::from(path)))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Where settings say a tab's note lives, ignoring availability (None = default dir)
+pub fn get_configured_tab_dir<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+) -> Option<PathBuf> {
+    get_tab_storage_overrides(app_handle)
+        .remove(&tab_index)
+        .or_else(|| get_custom_storage_dir(app_handle))
+}
+
+// Get the storage directory for one tab, honouring its override if any
+pub fn get_tab_storage_dir<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize) -> PathBuf {
+    match get_tab_storage_overrides(app_handle).remove(&tab_index) {
+        Some(path) if is_storage_available(&path) => path,
+        Some(_) => get_holding_dir(app_handle),
+        None => get_current_storage_dir(app_handle),
+    }
+}
+
+// Get the path to a specific note file
+pub fn get_note_path<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize) -> PathBuf {
+    let storage_dir = get_tab_storage_dir(app_handle, tab_index);
+    storage_dir.join(format!("note_{}.md", tab_index))
+}
+
+// Whether notes should be written encrypted
+fn is_encryption_enabled<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
     let settings_path = get_settings_path(app_handle);
+    fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|settings| settings["encrypt_notes"].as_bool())
+        .unwrap_or(false)
+}
 
-    if settings_path.exists() {
-        if let Ok(content) = fs::read_to_string(&settings_path) {
-            if let Ok(settings) = serde_json::from_str::<serde_json::Value>(&content) {
-                // Check if using custom storage
-                if let Some(using_custom) = settings["using_custom_storage"].as_bool() {
-                    if using_custom {
-                        // Get custom path if set
-                        if let Some(path) = settings["custom_storage_path"].as_str() {
-                            if !path.is_empty() {
-                                let custom_path = PathBuf::from(path);
-                                if custom_path.exists() || fs::create_dir_all(&custom_path).is_ok()
-                                {
-                                    return custom_path;
-                                } else {
-                                    warn!("Custom storage path is invalid or cannot be created, falling back to default");
-                                }
-                            }
-                        }
+fn get_encryption_passphrase<R: Runtime>(app_handle: &AppHandle<R>) -> Result<String, String> {
+    let app_id = app_handle.config().identifier.clone();
+    credential_manager::get_credential(ENCRYPTION_SERVICE, &app_id)
+        .map_err(|e| format!("Encryption passphrase unavailable: {}", e))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(ENCRYPTED_MAGIC)
+}
+
+fn encrypt_content(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| format!("Failed to initialize cipher: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| format!("Failed to encrypt note: {}", e))?;
+
+    let mut output =
+        Vec::with_capacity(ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(ENCRYPTED_MAGIC);
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+fn decrypt_content(passphrase: &str, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let payload = &bytes[ENCRYPTED_MAGIC.len()..];
+    if payload.len() < SALT_LEN + NONCE_LEN {
+        return Err("Encrypted note is truncated".to_string());
+    }
+
+    let (salt, rest) = payload.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let key = derive_key(passphrase, salt);
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| format!("Failed to initialize cipher: {}", e))?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt note: wrong passphrase or corrupted file".to_string())
+}
+
+// Turn raw bytes from disk back into what was stored, decrypting when needed
+pub fn decode_bytes<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    bytes: &[u8],
+) -> Result<Vec<u8>, String> {
+    if is_encrypted(bytes) {
+        let passphrase = get_encryption_passphrase(app_handle)?;
+        decrypt_content(&passphrase, bytes)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+// Encode bytes for disk, encrypting them if note encryption is enabled
+pub fn encode_bytes<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    bytes: &[u8],
+) -> Result<Vec<u8>, String> {
+    if is_encryption_enabled(app_handle) {
+        let passphrase = get_encryption_passphrase(app_handle)?;
+        encrypt_content(&passphrase, bytes)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+// Turn raw note bytes from disk into text, decrypting when needed
+pub fn decode_note_bytes<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    bytes: &[u8],
+) -> Result<String, String> {
+    let encrypted = is_encrypted(bytes);
+    let plaintext = decode_bytes(app_handle, bytes)?;
+    String::from_utf8(plaintext).map_err(|e| {
+        if encrypted {
+            format!("Decrypted note is not valid UTF-8: {}", e)
+        } else {
+            format!("Note is not valid UTF-8: {}", e)
+        }
+    })
+}
+
+// Encode note content for disk, encrypting it if note encryption is enabled
+pub fn encode_note_content<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    content: &str,
+) -> Result<Vec<u8>, String> {
+    encode_bytes(app_handle, content.as_bytes())
+}
+
+// Read a note, transparently decrypting it. Returns None if the note doesn't exist.
+pub fn read_note<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+) -> Result<Option<String>, String> {
+    let path = get_note_path(app_handle, tab_index);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    ensure_materialized(&path);
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read note {}: {}", tab_index, e))?;
+    decode_note_bytes(app_handle, &bytes).map(Some)
+}
+
+// Write a note, encrypting it when encryption at rest is enabled
+pub fn write_note<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: &str,
+) -> Result<(), String> {
+    write_note_journaled(app_handle, tab_index, content, None)
+}
+
+// Write a note rewritten by an operation (formatting, imports, ...) rather
+// than edited by the user, so undo_last_operation can revert it by name
+pub fn write_note_for_operation<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: &str,
+    operation: &str,
+) -> Result<(), String> {
+    write_note_journaled(app_handle, tab_index, content, Some(operation))
+}
+
+fn write_note_journaled<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: &str,
+    operation: Option<&str>,
+) -> Result<(), String> {
+    // What's being replaced, for the undo journal
+    let previous = read_note(app_handle, tab_index)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
+    write_note_unjournaled(app_handle, tab_index, content)?;
+    crate::journal_service::record_save(app_handle, tab_index, &previous, content, operation);
+
+    Ok(())
+}
+
+// Write a note without recording it in the undo journal, e.g. when undoing
+pub fn write_note_unjournaled<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: &str,
+) -> Result<(), String> {
+    let path = get_note_path(app_handle, tab_index);
+    crate::data_dir::ensure_writable(app_handle, &path)?;
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            // e.g. the holding area on first use
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create storage directory: {}", e))?;
+        }
+    }
+
+    let bytes = encode_note_content(app_handle, content)?;
+
+    // Record before writing so the watcher never sees an unknown version
+    remember_note_content(tab_index, content);
+
+    // A save means the editor content is now what's on disk
+    if let Ok(mut dirty) = DIRTY_NOTES.lock() {
+        dirty.remove(&tab_index);
+    }
+    if let Ok(mut pending) = PENDING_EXTERNAL_CHANGES.lock() {
+        if pending.remove(&tab_index).is_some() {
+            warn!(
+                "Unsaved edits in note {} replaced an external change",
+                tab_index
+            );
+        }
+    }
+
+    write_atomic(path, &bytes).map_err(|e| format!("Failed to save note: {}", e))?;
+    crate::integrity_service::record_checksum(app_handle, tab_index, &bytes);
+    crate::metrics_service::record_note_write(tab_index, bytes.len());
+
+    Ok(())
+}
+
+// Metadata from a YAML frontmatter block at the top of a note
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NoteMetadata {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+// Accept `tags: [a, b]`, a YAML list, or Obsidian-style `tags: a, b`
+fn deserialize_tags<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Single(String),
+    }
+
+    Ok(match Option::<Tags>::deserialize(deserializer)? {
+        Some(Tags::List(tags)) => tags,
+        Some(Tags::Single(tags)) => tags
+            .split([',', ' '])
+            .map(|tag| tag.trim().trim_start_matches('#'))
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => Vec::new(),
+    })
+}
+
+// Split a note into its frontmatter YAML (without the --- fences) and the body
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(yaml), body);
+        }
+        offset += line.len();
+    }
+
+    // No closing fence, so it's not frontmatter
+    (None, content)
+}
+
+pub fn parse_frontmatter(content: &str) -> Result<NoteMetadata, String> {
+    match split_frontmatter(content).0 {
+        Some(yaml) if !yaml.trim().is_empty() => {
+            serde_yaml::from_str(yaml).map_err(|e| format!("Failed to parse frontmatter: {}", e))
+        }
+        _ => Ok(NoteMetadata::default()),
+    }
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Record content as known; returns true if it differs from what we knew
+fn remember_note_content(tab_index: usize, content: &str) -> bool {
+    let hash = content_hash(content);
+    if let Ok(mut known) = KNOWN_NOTE_HASHES.lock() {
+        known.insert(tab_index, hash) != Some(hash)
+    } else {
+        true
+    }
+}
+
+// Parse the tab index out of a `note_{i}.md` path
+pub fn note_index_from_path(path: &Path) -> Option<usize> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("note_")?
+        .strip_suffix(".md")?
+        .parse::<usize>()
+        .ok()
+}
+
+fn is_same_path(a: &Path, b: &Path) -> bool {
+    a == b
+        || matches!(
+            (a.canonicalize(), b.canonicalize()),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+fn handle_watch_event<R: Runtime>(app_handle: &AppHandle<R>, event: notify::Event) {
+    if !(event.kind.is_create() || event.kind.is_modify()) {
+        return;
+    }
+
+    for path in &event.paths {
+        let Some(tab_index) = note_index_from_path(path) else {
+            continue;
+        };
+        // A note_{i}.md in a directory that isn't tab i's storage location
+        if !is_same_path(path, &get_note_path(app_handle, tab_index)) {
+            continue;
+        }
+
+        match read_note(app_handle, tab_index) {
+            Ok(Some(content)) => {
+                if !remember_note_content(tab_index, &content) {
+                    continue;
+                }
+
+                info!("Note {} was modified outside of Jot", tab_index);
+                crate::integrity_service::record_note_file(app_handle, tab_index);
+                if is_note_dirty(tab_index) {
+                    // Don't clobber unsaved typing; hold the change until the tab is clean
+                    warn!(
+                        "Deferring external change to note {}: editor has unsaved edits",
+                        tab_index
+                    );
+                    if let Ok(mut pending) = PENDING_EXTERNAL_CHANGES.lock() {
+                        pending.insert(tab_index, content);
                     }
+                    if let Err(e) = app_handle.emit("note-external-change-deferred", tab_index) {
+                        error!("Failed to emit deferred change event: {}", e);
+                    }
+                } else if let Err(e) =
+                    crate::note_events::emit_note_updated(app_handle, tab_index, &content)
+                {
+                    error!("{}", e);
                 }
             }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to reload externally modified note: {}", e),
         }
     }
+}
 
-    // Default path if not using custom or if custom path is invalid
-    get_default_storage_dir(app_handle)
+pub fn mark_note_dirty(tab_index: usize) {
+    if let Ok(mut dirty) = DIRTY_NOTES.lock() {
+        dirty.insert(tab_index);
+    }
 }
 
-// Get the path to a specific note file
-pub fn get_note_path<R: Runtime>(app_handle: &AppHandle<R>, tab_index: usize) -> PathBuf {
-    let storage_dir = get_current_storage_dir(app_handle);
-    storage_dir.join(format!("note_{}.md", tab_index))
+pub fn is_note_dirty(tab_index: usize) -> bool {
+    DIRTY_NOTES
+        .lock()
+        .map(|dirty| dirty.contains(&tab_index))
+        .unwrap_or(false)
+}
+
+// Watch every tab's storage directory for external note edits. Calling this
+// again (e.g. after a storage path changed) replaces the previous watcher.
+pub fn start_note_watcher<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), String> {
+    let mut storage_dirs: Vec<PathBuf> = (0..7)
+        .map(|tab_index| get_tab_storage_dir(app_handle, tab_index))
+        .collect();
+    storage_dirs.sort();
+    storage_dirs.dedup();
+    let handle = app_handle.clone();
+
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => handle_watch_event(&handle, event),
+            Err(e) => warn!("Note watcher error: {}", e),
+        })
+        .map_err(|e| format!("Failed to create note watcher: {}", e))?;
+
+    for storage_dir in storage_dirs.iter().filter(|dir| dir.is_dir()) {
+        watcher
+            .watch(storage_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {:?}: {}", storage_dir, e))?;
+    }
+
+    if let Ok(mut current) = NOTE_WATCHER.lock() {
+        *current = Some(watcher);
+    }
+
+    info!("Watching {:?} for external note edits", storage_dirs);
+    Ok(())
+}
+
+// Persist the encrypt_notes flag in settings.json
+fn set_encryption_flag<R: Runtime>(app_handle: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    crate::data_dir::update_settings(app_handle, |settings| {
+        settings["encrypt_notes"] = serde_json::json!(enabled);
+        Ok(())
+    })
 }
 
 // Move notes to a new location
@@ -119,18 +785,23 @@ pub async fn migrate_notes<R: Runtime>(
             .map_err(|e| format!("Failed to create new storage directory: {}", e))?;
     }
 
-    // Copy all note files to the new location
+    // Copy all note files to the new location, except tabs stored elsewhere
+    let overrides = get_tab_storage_overrides(app_handle);
     for tab_index in 0..7 {
+        if overrides.contains_key(&tab_index) {
+            continue;
+        }
+
         let old_note_path = old_dir.join(format!("note_{}.md", tab_index));
         let new_note_path = new_dir.join(format!("note_{}.md", tab_index));
 
         if old_note_path.exists() {
-            // Read the old note
-            let content = fs::read_to_string(&old_note_path)
+            // Read the old note as raw bytes, so encrypted notes stay encrypted
+            let content = fs::read(&old_note_path)
                 .map_err(|e| format!("Failed to read note {}: {}", tab_index, e))?;
 
             // Write to the new location
-            fs::write(&new_note_path, &content).map_err(|e| {
+            write_atomic(&new_note_path, &content).map_err(|e| {
                 format!("Failed to write note {} to new location: {}", tab_index, e)
             })?;
 
@@ -161,10 +832,15 @@ pub fn get_storage_settings<R: Runtime>(
         let settings: serde_json::Value = serde_json::from_str(&content)
             .map_err(|e| format!("Failed to parse settings JSON: {}", e))?;
 
+        let warning = settings["custom_storage_path"]
+            .as_str()
+            .and_then(|path| storage_path_warning(Path::new(path)));
+
         return Ok(serde_json::json!({
             "customPath": settings["custom_storage_path"],
             "defaultPath": default_path,
-            "isUsingCustom": settings["using_custom_storage"].as_bool().unwrap_or(false)
+            "isUsingCustom": settings["using_custom_storage"].as_bool().unwrap_or(false),
+            "warning": warning
         }));
     }
 
@@ -172,7 +848,8 @@ pub fn get_storage_settings<R: Runtime>(
     Ok(serde_json::json!({
         "customPath": null,
         "defaultPath": default_path,
-        "isUsingCustom": false
+        "isUsingCustom": false,
+        "warning": null
     }))
 }
 
@@ -181,54 +858,729 @@ pub async fn set_storage_path<R: Runtime>(
     app_handle: AppHandle<R>,
     path: Option<String>,
 ) -> Result<(), String> {
+    let _timing = crate::metrics_service::time_async_command("set_storage_path");
     let old_storage_dir = get_current_storage_dir(&app_handle);
 
-    // Load current settings
-    let settings_path = get_settings_path(&app_handle);
-    let mut settings: serde_json::Value = if settings_path.exists() {
-        let content = fs::read_to_string(&settings_path)
-            .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    // Validate path before saving it
+    let path = match path {
+        Some(path_str) => {
+            validate_storage_path(&path_str).map_err(|e| format!("Invalid storage path: {}", e))?;
+            Some(path_str)
+        }
+        None => None,
+    };
 
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse settings JSON: {}", e))?
-    } else {
-        serde_json::json!({
-            "theme": "light",
-            "fontSize": "medium",
-            "activeTab": 0
-        })
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        settings["using_custom_storage"] = serde_json::json!(path.is_some());
+        settings["custom_storage_path"] = serde_json::json!(path);
+        Ok(())
+    })?;
+
+    // If path changed, migrate notes
+    let new_storage_dir = get_current_storage_dir(&app_handle);
+
+    // Only migrate if the directories are different
+    if old_storage_dir != new_storage_dir {
+        migrate_notes(&app_handle, &old_storage_dir, &new_storage_dir).await?;
+
+        if let Err(e) = start_note_watcher(&app_handle) {
+            warn!("Failed to watch new storage directory: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+// Get the per-tab storage overrides
+#[tauri::command]
+pub fn get_tab_storage_paths<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<HashMap<usize, String>, String> {
+    Ok(get_tab_storage_overrides(&app_handle)
+        .into_iter()
+        .map(|(tab_index, path)| (tab_index, path.to_string_lossy().to_string()))
+        .collect())
+}
+
+// Give one tab its own storage directory, or pass None to use the shared one again
+#[tauri::command]
+pub async fn set_tab_storage_path<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    path: Option<String>,
+) -> Result<(), String> {
+    let _timing = crate::metrics_service::time_async_command("set_tab_storage_path");
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    let old_note_path = get_note_path(&app_handle, tab_index);
+
+    let new_dir = match &path {
+        Some(path_str) => Some(
+            validate_storage_path(path_str).map_err(|e| format!("Invalid storage path: {}", e))?,
+        ),
+        None => None,
     };
 
-    // Validate path if provided
-    if let Some(path_str) = path.clone() {
-        // Validate the path before setting it
-        match validate_storage_path(&path_str) {
-            Ok(_) => {
-                settings["custom_storage_path"] = serde_json::json!(path_str);
-                settings["using_custom_storage"] = serde_json::json!(true);
+    crate::data_dir::update_settings(&app_handle, |settings| {
+        let mut overrides = settings["tab_storage_paths"]
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        match &new_dir {
+            Some(dir) => {
+                overrides.insert(
+                    tab_index.to_string(),
+                    serde_json::json!(dir.to_string_lossy()),
+                );
             }
-            Err(e) => {
-                return Err(format!("Invalid storage path: {}", e));
+            None => {
+                overrides.remove(&tab_index.to_string());
             }
         }
+        settings["tab_storage_paths"] = serde_json::Value::Object(overrides);
+        Ok(())
+    })?;
+
+    let new_note_path = get_note_path(&app_handle, tab_index);
+    if old_note_path == new_note_path {
+        return Ok(());
+    }
+
+    match crate::backup_service::create_backup(app_handle.clone()).await {
+        Ok(backup_path) => {
+            info!(
+                "Created backup before moving note {}: {}",
+                tab_index, backup_path
+            );
+            tauri::Emitter::emit(&app_handle, "backup-created", backup_path).unwrap();
+        }
+        Err(e) => error!("Warning: Failed to create backup before moving note: {}", e),
+    }
+
+    if new_note_path.exists() {
+        // e.g. a synced folder that already has this note; use it as is
+        info!(
+            "Note {} already exists at {:?}, keeping it",
+            tab_index, new_note_path
+        );
+    } else if old_note_path.exists() {
+        // Raw bytes, so encrypted notes stay encrypted
+        let content = fs::read(&old_note_path)
+            .map_err(|e| format!("Failed to read note {}: {}", tab_index, e))?;
+
+        write_atomic(&new_note_path, &content)
+            .map_err(|e| format!("Failed to write note {} to new location: {}", tab_index, e))?;
+
+        info!("Moved note {} to {:?}", tab_index, new_note_path);
+    }
+
+    if let Err(e) = start_note_watcher(&app_handle) {
+        warn!("Failed to watch new storage directory: {}", e);
+    }
+
+    tauri::Emitter::emit(&app_handle, "storage-changed", ()).unwrap();
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_encryption_status<R: Runtime>(app_handle: AppHandle<R>) -> serde_json::Value {
+    serde_json::json!({
+        "enabled": is_encryption_enabled(&app_handle),
+        "hasPassphrase": get_encryption_passphrase(&app_handle).is_ok()
+    })
+}
+
+// A file written through the note encoder, decoded
+enum EncodedFile {
+    Note(usize, String),
+    Other(PathBuf, Vec<u8>),
+}
+
+// Files written through the note encoder besides the notes themselves: notes
+// held while their storage was unavailable, daily note archives and undo
+// journals
+fn other_encoded_files<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<PathBuf> {
+    let note_paths: Vec<PathBuf> = (0..7)
+        .map(|tab_index| get_note_path(app_handle, tab_index))
+        .collect();
+    let list = |dir: PathBuf, keep: &dyn Fn(&Path) -> bool| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file() && keep(path))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut files = list(get_holding_dir(app_handle), &|path| {
+        note_index_from_path(path).is_some() && !note_paths.contains(&path.to_path_buf())
+    });
+
+    let archive_name = crate::archive_service::ARCHIVE_DIR_NAME;
+    let mut archive_dirs = vec![
+        get_default_storage_dir(app_handle).join(archive_name),
+        get_holding_dir(app_handle).join(archive_name),
+        crate::archive_service::get_archive_dir(app_handle),
+    ];
+    archive_dirs.dedup();
+    for dir in archive_dirs {
+        files.extend(list(dir, &|path| {
+            path.extension().is_some_and(|ext| ext == "md")
+        }));
+    }
+
+    files.extend(list(
+        crate::journal_service::get_journal_dir(app_handle),
+        &|path| path.extension().is_some_and(|ext| ext == "json"),
+    ));
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+// Decode every file written through the note encoder with the current
+// settings, before any of them is rewritten
+fn read_encoded_files<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Vec<EncodedFile>, String> {
+    let mut files = Vec::new();
+    for tab_index in 0..7 {
+        if let Some(content) = read_note(app_handle, tab_index)? {
+            files.push(EncodedFile::Note(tab_index, content));
+        }
+    }
+    for path in other_encoded_files(app_handle) {
+        ensure_materialized(&path);
+        let bytes =
+            fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let plaintext = decode_bytes(app_handle, &bytes)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        files.push(EncodedFile::Other(path, plaintext));
+    }
+    Ok(files)
+}
+
+// Write decoded files back, encoded with the current settings
+fn write_encoded_files<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    files: Vec<EncodedFile>,
+) -> Result<(), String> {
+    for file in files {
+        match file {
+            // Same content, so not a save to record in the undo journal
+            EncodedFile::Note(tab_index, content) => {
+                write_note_unjournaled(app_handle, tab_index, &content)?
+            }
+            EncodedFile::Other(path, plaintext) => {
+                let bytes = encode_bytes(app_handle, &plaintext)?;
+                write_atomic(&path, bytes)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Whether anything on disk still needs the passphrase to be read
+fn has_encrypted_files<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
+    let encrypted = |path: &Path| fs::read(path).is_ok_and(|bytes| is_encrypted(&bytes));
+
+    (0..7).any(|tab_index| encrypted(&get_note_path(app_handle, tab_index)))
+        || other_encoded_files(app_handle)
+            .iter()
+            .any(|path| encrypted(path))
+        || crate::backup_service::backups_contain_encrypted_notes(app_handle)
+}
+
+// Turn on encryption at rest and encrypt the notes, held notes, archives and
+// undo journals
+#[tauri::command]
+pub fn enable_note_encryption<R: Runtime>(
+    app_handle: AppHandle<R>,
+    passphrase: String,
+) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase must not be empty".to_string());
+    }
+    // Files and backups encrypted with the current passphrase would become
+    // unreadable under a new one
+    if is_encryption_enabled(&app_handle) && get_encryption_passphrase(&app_handle).is_ok() {
+        return Err("Note encryption is already enabled".to_string());
+    }
+
+    crate::autosave_service::flush_all(&app_handle)?;
+    let _journals = crate::journal_service::lock_journals();
+
+    // Read everything with the current settings before switching over
+    let files = read_encoded_files(&app_handle)?;
+
+    let app_id = app_handle.config().identifier.clone();
+    credential_manager::store_credential(ENCRYPTION_SERVICE, &app_id, &passphrase)?;
+    set_encryption_flag(&app_handle, true)?;
+
+    write_encoded_files(&app_handle, files)?;
+
+    info!("Note encryption enabled");
+    Ok(())
+}
+
+// Turn off encryption at rest and decrypt the notes, held notes, archives and
+// undo journals. The passphrase is kept while anything, e.g. a backup, still
+// needs it.
+#[tauri::command]
+pub fn disable_note_encryption<R: Runtime>(app_handle: AppHandle<R>) -> Result<(), String> {
+    crate::autosave_service::flush_all(&app_handle)?;
+    let _journals = crate::journal_service::lock_journals();
+
+    let files = read_encoded_files(&app_handle)?;
+
+    set_encryption_flag(&app_handle, false)?;
+
+    write_encoded_files(&app_handle, files)?;
+
+    if has_encrypted_files(&app_handle) {
+        warn!("Keeping the encryption passphrase: encrypted backups or files still need it");
     } else {
-        settings["custom_storage_path"] = serde_json::json!(null);
-        settings["using_custom_storage"] = serde_json::json!(false);
+        let app_id = app_handle.config().identifier.clone();
+        if let Err(e) = credential_manager::delete_credential(ENCRYPTION_SERVICE, &app_id) {
+            warn!("Failed to remove encryption passphrase: {}", e);
+        }
     }
 
-    // Save updated settings
-    let json_str = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    info!("Note encryption disabled");
+    Ok(())
+}
 
-    fs::write(&settings_path, json_str).map_err(|e| format!("Failed to save settings: {}", e))?;
+#[derive(Serialize, Debug)]
+pub struct MarkdownFileInfo {
+    pub path: String,
+    pub size: u64,
+}
 
-    // If path changed, migrate notes
-    let new_storage_dir = get_current_storage_dir(&app_handle);
+#[derive(Serialize, Debug)]
+pub struct MarkdownImportPreview {
+    pub tab_index: usize,
+    pub source: String,
+    pub source_size: u64,
+    pub will_overwrite: bool,
+    pub current_size: usize,
+    pub current_first_line: Option<String>,
+}
 
-    // Only migrate if the directories are different
-    if old_storage_dir != new_storage_dir {
-        migrate_notes(&app_handle, &old_storage_dir, &new_storage_dir).await?;
+// Resolve a file inside the import folder, refusing anything that escapes it
+fn resolve_import_file(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to open import folder: {}", e))?;
+    let path = root
+        .join(relative)
+        .canonicalize()
+        .map_err(|e| format!("Failed to open {}: {}", relative, e))?;
+
+    if !path.starts_with(&root) {
+        return Err(format!("{} is outside the import folder", relative));
+    }
+    if !path.is_file() {
+        return Err(format!("{} is not a file", relative));
+    }
+
+    Ok(path)
+}
+
+fn collect_markdown_files(root: &Path, dir: &Path, files: &mut Vec<MarkdownFileInfo>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        // Skip hidden entries such as .obsidian, .git and .trash
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_markdown_files(root, &path, files);
+        } else if matches!(path.extension(), Some(ext) if ext.eq_ignore_ascii_case("md")) {
+            let relative = path
+                .strip_prefix(root)
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or(name);
+            files.push(MarkdownFileInfo {
+                path: relative,
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            });
+        }
+    }
+}
+
+// List markdown files in a folder (e.g. an Obsidian vault) available for import
+#[tauri::command]
+pub fn list_markdown_files(path: String) -> Result<Vec<MarkdownFileInfo>, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    let mut files = Vec::new();
+    collect_markdown_files(&root, &root, &mut files);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(files)
+}
+
+// Show which tabs an import mapping (tab index -> file) would overwrite
+#[tauri::command]
+pub fn preview_markdown_import<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+    mapping: HashMap<usize, String>,
+) -> Result<Vec<MarkdownImportPreview>, String> {
+    let root = PathBuf::from(&path);
+    let mut preview = Vec::new();
+
+    for (tab_index, relative) in mapping {
+        if tab_index >= 7 {
+            return Err(format!("Invalid tab index: {}", tab_index));
+        }
+
+        let source = resolve_import_file(&root, &relative)?;
+        let current = read_note(&app_handle, tab_index)?.unwrap_or_default();
+
+        preview.push(MarkdownImportPreview {
+            tab_index,
+            source: relative,
+            source_size: fs::metadata(&source).map(|m| m.len()).unwrap_or(0),
+            will_overwrite: !current.trim().is_empty(),
+            current_size: current.len(),
+            current_first_line: current
+                .lines()
+                .find(|line| !line.trim().is_empty())
+                .map(|line| line.chars().take(80).collect()),
+        });
+    }
+
+    preview.sort_by_key(|item| item.tab_index);
+    Ok(preview)
+}
+
+// Load files from a markdown folder into tabs according to the mapping
+#[tauri::command]
+pub async fn import_markdown_folder<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+    mapping: HashMap<usize, String>,
+) -> Result<usize, String> {
+    let _timing = crate::metrics_service::time_async_command("import_markdown_folder");
+    let root = PathBuf::from(&path);
+
+    // Read and validate everything before touching any note
+    let mut imports = Vec::new();
+    for (tab_index, relative) in &mapping {
+        if *tab_index >= 7 {
+            return Err(format!("Invalid tab index: {}", tab_index));
+        }
+        let source = resolve_import_file(&root, relative)?;
+        let content = fs::read_to_string(&source)
+            .map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+        imports.push((*tab_index, content));
+    }
+
+    match crate::backup_service::create_backup(app_handle.clone()).await {
+        Ok(backup_path) => {
+            info!("Created backup before markdown import: {}", backup_path);
+            tauri::Emitter::emit(&app_handle, "backup-created", backup_path).unwrap();
+        }
+        Err(e) => {
+            return Err(format!("Failed to create backup before import: {}", e));
+        }
+    }
+
+    for (tab_index, content) in &imports {
+        write_note_for_operation(&app_handle, *tab_index, content, "Import markdown")?;
+        crate::note_events::emit_note_updated(&app_handle, *tab_index, content)?;
+    }
+
+    info!("Imported {} notes from {}", imports.len(), path);
+    Ok(imports.len())
+}
+
+// Let the frontend register whether a tab has unsaved edits, so background
+// writers never replace content the user is still typing
+#[tauri::command]
+pub fn set_note_dirty<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    dirty: bool,
+) -> Result<(), String> {
+    if let Ok(mut dirty_notes) = DIRTY_NOTES.lock() {
+        if dirty {
+            dirty_notes.insert(tab_index);
+        } else {
+            dirty_notes.remove(&tab_index);
+        }
+    }
+
+    if dirty {
+        return Ok(());
+    }
+
+    // The tab is clean again: deliver any change we held back
+    let pending = PENDING_EXTERNAL_CHANGES
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.remove(&tab_index));
+    if let Some(content) = pending {
+        crate::note_events::emit_note_updated(&app_handle, tab_index, &content)?;
     }
 
     Ok(())
 }
+
+// Get the frontmatter metadata of a note
+#[tauri::command]
+pub fn get_note_metadata<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+) -> Result<NoteMetadata, String> {
+    match read_note(&app_handle, tab_index)? {
+        Some(content) => parse_frontmatter(&content),
+        None => Ok(NoteMetadata::default()),
+    }
+}
+
+// Buffered edits and a backup first: both commands below replace note content
+async fn prepare_note_rewrite<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    source: usize,
+    target: usize,
+) -> Result<String, String> {
+    if source >= 7 || target >= 7 {
+        return Err(format!("Invalid tab index: {}", source.max(target)));
+    }
+    if source == target {
+        return Err("Source and target must be different notes".to_string());
+    }
+
+    crate::autosave_service::flush_all(app_handle)?;
+
+    match crate::backup_service::create_backup(app_handle.clone()).await {
+        Ok(backup_path) => {
+            info!("Created backup before rewriting notes: {}", backup_path);
+            tauri::Emitter::emit(app_handle, "backup-created", backup_path).unwrap();
+        }
+        Err(e) => {
+            return Err(format!("Failed to create backup: {}", e));
+        }
+    }
+
+    Ok(read_note(app_handle, source)?.unwrap_or_default())
+}
+
+fn write_and_announce<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+    content: &str,
+    operation: &str,
+) -> Result<(), String> {
+    write_note_for_operation(app_handle, tab_index, content, operation)?;
+    crate::note_events::emit_note_updated(app_handle, tab_index, content)
+}
+
+// Copy a note into another tab, replacing its content
+#[tauri::command]
+pub async fn duplicate_note<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab: usize,
+    target_tab: usize,
+) -> Result<(), String> {
+    let _timing = crate::metrics_service::time_async_command("duplicate_note");
+    let content = prepare_note_rewrite(&app_handle, tab, target_tab).await?;
+    write_and_announce(&app_handle, target_tab, &content, "Duplicate note")?;
+
+    info!("Duplicated note {} into note {}", tab, target_tab);
+    Ok(())
+}
+
+// Append a note to another one and empty the source
+#[tauri::command]
+pub async fn merge_notes<R: Runtime>(
+    app_handle: AppHandle<R>,
+    source: usize,
+    target: usize,
+    separator: Option<String>,
+) -> Result<(), String> {
+    let _timing = crate::metrics_service::time_async_command("merge_notes");
+    let source_content = prepare_note_rewrite(&app_handle, source, target).await?;
+    if source_content.trim().is_empty() {
+        return Ok(());
+    }
+
+    let target_content = read_note(&app_handle, target)?.unwrap_or_default();
+    let merged = if target_content.trim().is_empty() {
+        source_content
+    } else {
+        let separator = separator.unwrap_or_else(|| "\n\n".to_string());
+        format!(
+            "{}{}{}",
+            target_content.trim_end_matches('\n'),
+            separator,
+            source_content
+        )
+    };
+
+    // Target first: if that fails, the source is still intact
+    write_and_announce(&app_handle, target, &merged, "Merge notes")?;
+    write_and_announce(&app_handle, source, "", "Merge notes")?;
+
+    info!("Merged note {} into note {}", source, target);
+    Ok(())
+}
+
+// A slice of a note; offsets are UTF-8 byte offsets into the note's text
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteChunk {
+    pub content: String,
+    pub offset: u64,
+    pub next_offset: u64,
+    pub total_size: u64,
+    pub done: bool,
+}
+
+// Chunk size limit, so a single call can't pull a whole multi-MB note
+const MAX_CHUNK_SIZE: u64 = 1024 * 1024;
+
+// Cut a byte range out of text, moving both ends forward to character boundaries
+fn slice_text(text: &str, offset: usize, length: usize) -> (usize, usize) {
+    let mut start = offset.min(text.len());
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let mut end = start.saturating_add(length).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    (start, end)
+}
+
+// Read part of a plain-text note file without loading all of it
+fn read_plain_chunk(path: &Path, offset: u64, length: u64) -> Result<NoteChunk, String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open note: {}", e))?;
+    let total_size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read note size: {}", e))?
+        .len();
+    let offset = offset.min(total_size);
+
+    // A character is at most 4 bytes: read a few extra bytes to find the
+    // boundaries, as the start may move up to 3 bytes and the end 3 more
+    let window_start = offset.saturating_sub(3);
+    file.seek(SeekFrom::Start(window_start))
+        .map_err(|e| format!("Failed to read note: {}", e))?;
+    let mut buffer = Vec::new();
+    file.take(offset - window_start + length + 6)
+        .read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read note: {}", e))?;
+
+    let is_continuation = |byte: &u8| (byte & 0b1100_0000) == 0b1000_0000;
+    let mut start = (offset - window_start) as usize;
+    while buffer.get(start).is_some_and(is_continuation) {
+        start += 1;
+    }
+    let mut end = (start + length as usize).min(buffer.len());
+    while buffer.get(end).is_some_and(is_continuation) {
+        end += 1;
+    }
+
+    let content = String::from_utf8(buffer[start..end].to_vec())
+        .map_err(|e| format!("Note is not valid UTF-8: {}", e))?;
+    let next_offset = window_start + end as u64;
+
+    Ok(NoteChunk {
+        content,
+        offset: window_start + start as u64,
+        next_offset,
+        total_size,
+        done: next_offset >= total_size,
+    })
+}
+
+// Load a single note, e.g. lazily when its tab is opened
+#[tauri::command]
+pub fn load_note<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+) -> Result<Option<String>, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    // Buffered edits are newer than the file
+    if let Err(e) = crate::autosave_service::flush_all(&app_handle) {
+        warn!("Failed to save pending notes before loading: {}", e);
+    }
+
+    read_note(&app_handle, tab_index)
+}
+
+// Load part of a note, so the editor can page through very large notes
+#[tauri::command]
+pub fn load_note_chunk<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    offset: u64,
+    length: u64,
+) -> Result<NoteChunk, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+    let length = length.min(MAX_CHUNK_SIZE);
+
+    if let Err(e) = crate::autosave_service::flush_all(&app_handle) {
+        warn!("Failed to save pending notes before loading: {}", e);
+    }
+
+    let path = get_note_path(&app_handle, tab_index);
+    if !path.exists() {
+        return Ok(NoteChunk {
+            content: String::new(),
+            offset: 0,
+            next_offset: 0,
+            total_size: 0,
+            done: true,
+        });
+    }
+
+    ensure_materialized(&path);
+    let mut header = [0u8; ENCRYPTED_MAGIC.len()];
+    let header_len = {
+        use std::io::Read;
+        fs::File::open(&path)
+            .and_then(|mut file| file.read(&mut header))
+            .map_err(|e| format!("Failed to read note {}: {}", tab_index, e))?
+    };
+    if !is_encrypted(&header[..header_len]) {
+        return read_plain_chunk(&path, offset, length);
+    }
+
+    // Encrypted notes can only be decrypted as a whole
+    let text = read_note(&app_handle, tab_index)?.unwrap_or_default();
+    let (start, end) = slice_text(&text, offset as usize, length as usize);
+    Ok(NoteChunk {
+        content: text[start..end].to_string(),
+        offset: start as u64,
+        next_offset: end as u64,
+        total_size: text.len() as u64,
+        done: end >= text.len(),
+    })
+}
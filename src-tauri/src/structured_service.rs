@@ -0,0 +1,383 @@
+// structured_service.rs - Tabs holding structured entries (contacts, habits,
+// ...) described by a schema in their frontmatter, for form-like editing
+//
+// The schema:
+//
+//   ---
+//   structure:
+//     key: name
+//     fields:
+//       - { name: name, type: text, required: true }
+//       - { name: email, type: text }
+//       - { name: done, type: boolean }
+//   ---
+//
+// Each entry is a `## <key>` section with one `- field: value` line per other
+// field. Text before the first entry is kept as it is, and so are lines of an
+// entry that aren't fields. Entries are written back with fields in schema
+// order, so saving an unchanged entry doesn't change the file.
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Runtime};
+
+use crate::storage_service;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Text,
+    Number,
+    Boolean,
+    // YYYY-MM-DD
+    Date,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldSchema {
+    pub name: String,
+    #[serde(rename = "type", default = "default_field_type")]
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub required: bool,
+}
+
+fn default_field_type() -> FieldType {
+    FieldType::Text
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Structure {
+    // Field naming each entry, used as its heading; the first field by default
+    #[serde(default)]
+    pub key: Option<String>,
+    pub fields: Vec<FieldSchema>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StructureFrontmatter {
+    structure: Option<Structure>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StructuredEntry {
+    pub key: String,
+    pub values: Map<String, Value>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StructuredNote {
+    pub key: String,
+    pub fields: Vec<FieldSchema>,
+    pub entries: Vec<StructuredEntry>,
+}
+
+struct ParsedEntry {
+    entry: StructuredEntry,
+    // Lines of the section that aren't fields
+    other_lines: Vec<String>,
+}
+
+impl Structure {
+    fn key_field(&self) -> Result<&str, String> {
+        let key = match &self.key {
+            Some(key) => key.as_str(),
+            None => self
+                .fields
+                .first()
+                .map(|field| field.name.as_str())
+                .ok_or("The structure has no fields")?,
+        };
+        if !self.fields.iter().any(|field| field.name == key) {
+            return Err(format!("Key {} is not one of the fields", key));
+        }
+        Ok(key)
+    }
+}
+
+fn parse_structure(content: &str) -> Result<Structure, String> {
+    let yaml = storage_service::split_frontmatter(content)
+        .0
+        .ok_or("The note has no frontmatter declaring a structure")?;
+    let frontmatter: StructureFrontmatter =
+        serde_yaml::from_str(yaml).map_err(|e| format!("Failed to parse frontmatter: {}", e))?;
+    let structure = frontmatter
+        .structure
+        .ok_or("The note's frontmatter declares no structure")?;
+
+    for (index, field) in structure.fields.iter().enumerate() {
+        let valid_name = !field.name.is_empty()
+            && field
+                .name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == ' ');
+        if !valid_name {
+            return Err(format!("Invalid field name: {:?}", field.name));
+        }
+        if structure.fields[..index]
+            .iter()
+            .any(|other| other.name == field.name)
+        {
+            return Err(format!("Field {} is declared twice", field.name));
+        }
+    }
+    structure.key_field()?;
+
+    Ok(structure)
+}
+
+// A field value as written in the note, typed by the schema
+fn parse_value(field_type: FieldType, text: &str) -> Value {
+    let text = text.trim();
+    match field_type {
+        FieldType::Number => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(|number| match text.parse::<i64>() {
+                Ok(integer) => Value::from(integer),
+                Err(_) => Value::Number(number),
+            })
+            .unwrap_or_else(|| Value::String(text.to_string())),
+        FieldType::Boolean => match text.to_lowercase().as_str() {
+            "true" | "yes" | "x" => Value::Bool(true),
+            "false" | "no" | "" => Value::Bool(false),
+            _ => Value::String(text.to_string()),
+        },
+        FieldType::Text | FieldType::Date => Value::String(text.to_string()),
+    }
+}
+
+// Check a submitted value against its field and render it for the note
+fn format_value(field: &FieldSchema, value: &Value) -> Result<String, String> {
+    let text = match (field.field_type, value) {
+        (FieldType::Text, Value::String(text)) => text.trim().to_string(),
+        (FieldType::Number, Value::Number(number)) => number.to_string(),
+        (FieldType::Boolean, Value::Bool(flag)) => flag.to_string(),
+        (FieldType::Date, Value::String(date)) => {
+            chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("{} must be a date (YYYY-MM-DD): {}", field.name, date))?;
+            date.trim().to_string()
+        }
+        (field_type, value) => {
+            return Err(format!(
+                "{} must be {:?}, got {}",
+                field.name, field_type, value
+            ))
+        }
+    };
+
+    if text.contains('\n') {
+        return Err(format!("{} must be a single line", field.name));
+    }
+    Ok(text)
+}
+
+// The text before the first entry, and the entries
+fn parse_entries(structure: &Structure, body: &str) -> Result<(String, Vec<ParsedEntry>), String> {
+    let key_field = structure.key_field()?;
+    let mut preamble = String::new();
+    let mut entries: Vec<ParsedEntry> = Vec::new();
+
+    for line in body.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            let mut values = Map::new();
+            values.insert(
+                key_field.to_string(),
+                Value::String(heading.trim().to_string()),
+            );
+            entries.push(ParsedEntry {
+                entry: StructuredEntry {
+                    key: heading.trim().to_string(),
+                    values,
+                },
+                other_lines: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some(current) = entries.last_mut() else {
+            preamble.push_str(line);
+            preamble.push('\n');
+            continue;
+        };
+
+        let field = line
+            .strip_prefix("- ")
+            .and_then(|item| item.split_once(':'))
+            .and_then(|(name, value)| {
+                structure
+                    .fields
+                    .iter()
+                    .find(|field| field.name == name.trim() && field.name != key_field)
+                    .map(|field| (field, value))
+            });
+        match field {
+            Some((field, value)) => {
+                current
+                    .entry
+                    .values
+                    .insert(field.name.clone(), parse_value(field.field_type, value));
+            }
+            None if line.trim().is_empty() => {}
+            None => current.other_lines.push(line.to_string()),
+        }
+    }
+
+    Ok((preamble, entries))
+}
+
+fn render_entry(structure: &Structure, entry: &ParsedEntry) -> Result<String, String> {
+    let key_field = structure.key_field()?;
+    let mut section = format!("## {}\n", entry.entry.key);
+    for field in structure
+        .fields
+        .iter()
+        .filter(|field| field.name != key_field)
+    {
+        if let Some(value) = entry.entry.values.get(&field.name) {
+            // Values read from the note may not match their type; keep them
+            let text = format_value(field, value).or_else(|e| match value {
+                Value::String(text) => Ok(text.clone()),
+                _ => Err(e),
+            })?;
+            section.push_str(&format!("- {}: {}\n", field.name, text));
+        }
+    }
+    for line in &entry.other_lines {
+        section.push_str(line);
+        section.push('\n');
+    }
+    Ok(section)
+}
+
+// Rebuild the note from its frontmatter, preamble and entries
+fn render_note(
+    content: &str,
+    structure: &Structure,
+    preamble: &str,
+    entries: &[ParsedEntry],
+) -> Result<String, String> {
+    let body = storage_service::split_frontmatter(content).1;
+    let mut rendered = content[..content.len() - body.len()].to_string();
+
+    let preamble = preamble.trim_end();
+    if !preamble.is_empty() {
+        rendered.push_str(preamble);
+        rendered.push_str("\n\n");
+    }
+    let sections = entries
+        .iter()
+        .map(|entry| render_entry(structure, entry))
+        .collect::<Result<Vec<_>, _>>()?;
+    rendered.push_str(&sections.join("\n"));
+
+    Ok(rendered)
+}
+
+fn read_structured<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    tab_index: usize,
+) -> Result<(String, Structure), String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    crate::autosave_service::flush_all(app_handle)?;
+    let content = storage_service::read_note(app_handle, tab_index)?.unwrap_or_default();
+    let structure = parse_structure(&content)?;
+    Ok((content, structure))
+}
+
+// Read the entries of a structured tab, with its schema
+#[tauri::command]
+pub fn get_structured_entries<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+) -> Result<StructuredNote, String> {
+    let (content, structure) = read_structured(&app_handle, tab_index)?;
+    let body = storage_service::split_frontmatter(&content).1;
+    let (_, entries) = parse_entries(&structure, body)?;
+
+    Ok(StructuredNote {
+        key: structure.key_field()?.to_string(),
+        fields: structure.fields,
+        entries: entries.into_iter().map(|parsed| parsed.entry).collect(),
+    })
+}
+
+// Add an entry to a structured tab, or replace the one with the same key
+#[tauri::command]
+pub fn upsert_entry<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    values: Map<String, Value>,
+) -> Result<StructuredEntry, String> {
+    let (content, structure) = read_structured(&app_handle, tab_index)?;
+    let key_field = structure.key_field()?;
+
+    if let Some(name) = values
+        .keys()
+        .find(|name| !structure.fields.iter().any(|field| &field.name == *name))
+    {
+        return Err(format!("Unknown field: {}", name));
+    }
+    for field in &structure.fields {
+        match values.get(&field.name) {
+            None | Some(Value::Null) if field.required || field.name == key_field => {
+                return Err(format!("{} is required", field.name));
+            }
+            None | Some(Value::Null) => {}
+            Some(value) => {
+                format_value(field, value)?;
+            }
+        }
+    }
+    let key = format_value(
+        structure
+            .fields
+            .iter()
+            .find(|field| field.name == key_field)
+            .ok_or("The structure has no key field")?,
+        &values[key_field],
+    )?;
+    if key.is_empty() {
+        return Err(format!("{} is required", key_field));
+    }
+
+    let body = storage_service::split_frontmatter(&content).1;
+    let (preamble, mut entries) = parse_entries(&structure, body)?;
+
+    let entry = StructuredEntry {
+        key: key.clone(),
+        values: values
+            .into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .collect(),
+    };
+    match entries.iter_mut().find(|parsed| parsed.entry.key == key) {
+        Some(existing) => existing.entry = entry.clone(),
+        None => entries.push(ParsedEntry {
+            entry: entry.clone(),
+            other_lines: Vec::new(),
+        }),
+    }
+
+    let updated = render_note(&content, &structure, &preamble, &entries)?;
+    if updated != content {
+        storage_service::write_note_for_operation(
+            &app_handle,
+            tab_index,
+            &updated,
+            "Update entry",
+        )?;
+        crate::note_events::emit_note_updated(&app_handle, tab_index, &updated)?;
+        info!("Saved entry {} of note {}", key, tab_index);
+    }
+
+    Ok(entry)
+}
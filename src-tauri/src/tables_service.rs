@@ -0,0 +1,399 @@
+// tables_service.rs - Align markdown tables and turn pasted CSV into tables,
+// in the backend so large pastes don't stall the editor
+use log::info;
+use tauri::{AppHandle, Runtime};
+
+use crate::storage_service;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+// Split a table row into trimmed cells; `\|` and pipes in `code` stay in the cell
+fn split_row(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_code = false;
+    let mut chars = line.trim().chars().peekable();
+
+    if chars.peek() == Some(&'|') {
+        chars.next();
+    }
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                cell.push(c);
+                if let Some(next) = chars.next() {
+                    cell.push(next);
+                }
+            }
+            '`' => {
+                in_code = !in_code;
+                cell.push(c);
+            }
+            '|' if !in_code => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    // A row without a closing pipe still has a last cell
+    if !cell.trim().is_empty() || !line.trim_end().ends_with('|') {
+        cells.push(cell.trim().to_string());
+    }
+
+    cells
+}
+
+fn parse_alignment(cell: &str) -> Option<Alignment> {
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+        return None;
+    }
+    Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Alignment::Center,
+        (true, false) => Alignment::Left,
+        (false, true) => Alignment::Right,
+        (false, false) => Alignment::None,
+    })
+}
+
+fn parse_delimiter_row(line: &str) -> Option<Vec<Alignment>> {
+    if !line.contains('-') {
+        return None;
+    }
+    let cells = split_row(line);
+    if cells.is_empty() {
+        return None;
+    }
+    cells.iter().map(|cell| parse_alignment(cell)).collect()
+}
+
+fn pad(text: &str, width: usize, alignment: Alignment) -> String {
+    let fill = width.saturating_sub(text.chars().count());
+    match alignment {
+        Alignment::Right => format!("{}{}", " ".repeat(fill), text),
+        Alignment::Center => format!(
+            "{}{}{}",
+            " ".repeat(fill / 2),
+            text,
+            " ".repeat(fill - fill / 2)
+        ),
+        Alignment::None | Alignment::Left => format!("{}{}", text, " ".repeat(fill)),
+    }
+}
+
+fn delimiter_cell(width: usize, alignment: Alignment) -> String {
+    match alignment {
+        Alignment::None => "-".repeat(width),
+        Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+        Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+        Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+    }
+}
+
+// Lay out a table with padded columns: header, delimiter row, body rows
+fn render_table(indent: &str, rows: &[Vec<String>], alignments: &[Alignment]) -> Vec<String> {
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .chain([alignments.len()])
+        .max()
+        .unwrap_or(0);
+    let alignment = |column: usize| alignments.get(column).copied().unwrap_or(Alignment::None);
+
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let render_row = |cells: Vec<String>| format!("{}| {} |", indent, cells.join(" | "));
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (index, row) in rows.iter().enumerate() {
+        lines.push(render_row(
+            (0..columns)
+                .map(|column| {
+                    let text = row.get(column).map(String::as_str).unwrap_or_default();
+                    pad(text, widths[column], alignment(column))
+                })
+                .collect(),
+        ));
+        if index == 0 {
+            lines.push(render_row(
+                (0..columns)
+                    .map(|column| delimiter_cell(widths[column], alignment(column)))
+                    .collect(),
+            ));
+        }
+    }
+
+    lines
+}
+
+// Reformat every table outside code blocks; returns the text and how many
+// tables were found
+fn format_tables(content: &str) -> (String, usize) {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut tables = 0;
+    let mut in_code_block = false;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+
+        // A header row followed by a delimiter row with as many cells
+        let delimiter = (!in_code_block && line.contains('|'))
+            .then(|| {
+                lines
+                    .get(index + 1)
+                    .and_then(|next| parse_delimiter_row(next))
+            })
+            .flatten()
+            .filter(|alignments| alignments.len() == split_row(line).len());
+        let Some(alignments) = delimiter else {
+            output.push(line.to_string());
+            index += 1;
+            continue;
+        };
+
+        let indent = &line[..line.len() - trimmed.len()];
+        let mut rows = vec![split_row(line)];
+        index += 2;
+        while index < lines.len() && lines[index].contains('|') && !lines[index].trim().is_empty() {
+            rows.push(split_row(lines[index]));
+            index += 1;
+        }
+
+        output.extend(render_table(indent, &rows, &alignments));
+        tables += 1;
+    }
+
+    let line_ending = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut formatted = output.join(line_ending);
+    if content.ends_with('\n') {
+        formatted.push_str(line_ending);
+    }
+    (formatted, tables)
+}
+
+// Tab for spreadsheet pastes, otherwise whichever of `;` and `,` is more common
+fn detect_delimiter(text: &str) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+    if first_line.contains('\t') {
+        '\t'
+    } else if first_line.matches(';').count() > first_line.matches(',').count() {
+        ';'
+    } else {
+        ','
+    }
+}
+
+// Parse CSV with quoted fields (`"a, b"`, `""` for a quote, line breaks inside quotes)
+fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+        } else if c == '"' && field.is_empty() {
+            in_quotes = true;
+        } else if c == delimiter {
+            row.push(std::mem::take(&mut field));
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            row.push(std::mem::take(&mut field));
+            rows.push(std::mem::take(&mut row));
+        } else {
+            field.push(c);
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    // Blank lines aren't rows
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+// Make a CSV field safe inside a table cell
+fn escape_cell(field: &str) -> String {
+    field
+        .trim()
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+// Align the columns of every markdown table in a note; returns how many
+// tables there were
+#[tauri::command]
+pub fn format_markdown_tables<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+) -> Result<usize, String> {
+    if tab_index >= 7 {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    crate::autosave_service::flush_all(&app_handle)?;
+    let content = storage_service::read_note(&app_handle, tab_index)?.unwrap_or_default();
+
+    let (formatted, tables) = format_tables(&content);
+    if formatted == content {
+        return Ok(tables);
+    }
+
+    storage_service::write_note_for_operation(&app_handle, tab_index, &formatted, "Format tables")?;
+    info!("Formatted {} table(s) in note {}", tables, tab_index);
+
+    crate::note_events::emit_note_updated(&app_handle, tab_index, &formatted)?;
+
+    Ok(tables)
+}
+
+// Convert CSV (or tab-separated text from a spreadsheet) into a markdown
+// table; the first row is the header. The delimiter is detected if not given.
+#[tauri::command]
+pub fn csv_to_markdown_table(text: String, delimiter: Option<String>) -> Result<String, String> {
+    let delimiter = match delimiter.as_deref() {
+        None | Some("") => detect_delimiter(&text),
+        Some("\\t") | Some("tab") => '\t',
+        Some(delimiter) => {
+            let mut chars = delimiter.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c != '"' && c != '\n' => c,
+                _ => return Err(format!("Invalid CSV delimiter: {:?}", delimiter)),
+            }
+        }
+    };
+
+    let rows: Vec<Vec<String>> = parse_csv(&text, delimiter)
+        .iter()
+        .map(|row| row.iter().map(|field| escape_cell(field)).collect())
+        .collect();
+    if rows.is_empty() {
+        return Err("No CSV rows to convert".to_string());
+    }
+
+    Ok(render_table("", &rows, &[]).join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_csv_keeps_quoted_commas_in_one_field() {
+        let rows = parse_csv("name,notes\n\"Smith, J\",\"a, b\"\n", ',');
+        assert_eq!(
+            rows,
+            vec![row(&["name", "notes"]), row(&["Smith, J", "a, b"])]
+        );
+    }
+
+    #[test]
+    fn parse_csv_unescapes_doubled_quotes() {
+        let rows = parse_csv("\"say \"\"hi\"\"\",x\r\n\"\"\"\",y", ',');
+        assert_eq!(rows, vec![row(&["say \"hi\"", "x"]), row(&["\"", "y"])]);
+    }
+
+    #[test]
+    fn parse_csv_keeps_line_breaks_inside_quotes() {
+        let rows = parse_csv("a;b\n\"one\ntwo\";3\n", ';');
+        assert_eq!(rows, vec![row(&["a", "b"]), row(&["one\ntwo", "3"])]);
+    }
+
+    #[test]
+    fn parse_csv_keeps_ragged_rows_and_skips_blank_lines() {
+        let rows = parse_csv("a,b,c\n1\n\n2,3\n", ',');
+        assert_eq!(
+            rows,
+            vec![row(&["a", "b", "c"]), row(&["1"]), row(&["2", "3"])]
+        );
+    }
+
+    #[test]
+    fn csv_table_pads_ragged_rows() {
+        let table = csv_to_markdown_table("a,b,c\n1\n2,3".to_string(), None).unwrap();
+        assert_eq!(
+            table,
+            "| a   | b   | c   |\n\
+             | --- | --- | --- |\n\
+             | 1   |     |     |\n\
+             | 2   | 3   |     |"
+        );
+    }
+
+    #[test]
+    fn csv_table_escapes_pipes_and_line_breaks() {
+        let table = csv_to_markdown_table("h\n\"a|b\nc\"".to_string(), None).unwrap();
+        assert_eq!(table, "| h         |\n| --------- |\n| a\\|b<br>c |");
+    }
+
+    #[test]
+    fn format_tables_keeps_alignment_markers() {
+        let (formatted, tables) = format_tables("|a|b|c|d|\n|:-|-:|:-:|-|\n|long cell|x|y|z|\n");
+        assert_eq!(tables, 1);
+        assert_eq!(
+            formatted,
+            "| a         |   b |  c  | d   |\n\
+             | :-------- | --: | :-: | --- |\n\
+             | long cell |   x |  y  | z   |\n"
+        );
+    }
+
+    #[test]
+    fn format_tables_pads_ragged_rows_and_keeps_escaped_pipes() {
+        let (formatted, _) = format_tables("| a | b |\n|---|---|\n| x \\| y |\n| 1 | 2 | 3 |");
+        assert_eq!(
+            formatted,
+            "| a      | b   |     |\n\
+             | ------ | --- | --- |\n\
+             | x \\| y |     |     |\n\
+             | 1      | 2   | 3   |"
+        );
+    }
+
+    #[test]
+    fn format_tables_skips_code_blocks() {
+        let content = "```\n|a|b|\n|-|-|\n```\n";
+        assert_eq!(format_tables(content), (content.to_string(), 0));
+    }
+}
@@ -0,0 +1,225 @@
+// tabs_service.rs - Tab order and pinned notes, kept in tabs.json, and each
+// tab's editor state (cursor, selection, scroll), kept in editor_state.json
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub const TAB_COUNT: usize = 7;
+// Editor state arrives on every cursor move; it's written once they pause
+const EDITOR_STATE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TabLayout {
+    // Tab indices in display order
+    pub order: Vec<usize>,
+    pub pinned: Vec<usize>,
+}
+
+impl Default for TabLayout {
+    fn default() -> Self {
+        TabLayout {
+            order: (0..TAB_COUNT).collect(),
+            pinned: Vec::new(),
+        }
+    }
+}
+
+impl TabLayout {
+    // Display order: pinned tabs first, each group in the saved order
+    pub fn display_order(&self) -> Vec<usize> {
+        let (pinned, unpinned): (Vec<usize>, Vec<usize>) =
+            self.order.iter().partition(|tab| self.pinned.contains(tab));
+        pinned.into_iter().chain(unpinned).collect()
+    }
+
+    // Repair a hand-edited or outdated file: every tab exactly once
+    fn normalized(mut self) -> Self {
+        let mut order = Vec::with_capacity(TAB_COUNT);
+        for tab in self.order.into_iter().chain(0..TAB_COUNT) {
+            if tab < TAB_COUNT && !order.contains(&tab) {
+                order.push(tab);
+            }
+        }
+        self.order = order;
+
+        self.pinned.retain(|tab| *tab < TAB_COUNT);
+        self.pinned.sort_unstable();
+        self.pinned.dedup();
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EditorState {
+    // Character offsets into the note
+    pub cursor: usize,
+    // Other end of the selection, if there is one
+    pub anchor: Option<usize>,
+    pub scroll_top: f64,
+    pub scroll_left: f64,
+}
+
+// Editor state reported but not yet written
+static PENDING_EDITOR_STATE: Lazy<Mutex<HashMap<usize, EditorState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static EDITOR_STATE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn get_layout_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("tabs.json")
+}
+
+pub fn load_layout<R: Runtime>(app_handle: &AppHandle<R>) -> TabLayout {
+    fs::read_to_string(get_layout_path(app_handle))
+        .ok()
+        .and_then(|content| {
+            serde_json::from_str::<TabLayout>(&content)
+                .map_err(|e| warn!("Ignoring invalid tabs.json: {}", e))
+                .ok()
+        })
+        .unwrap_or_default()
+        .normalized()
+}
+
+fn save_layout<R: Runtime>(app_handle: &AppHandle<R>, layout: &TabLayout) -> Result<(), String> {
+    let path = get_layout_path(app_handle);
+    crate::data_dir::ensure_writable(app_handle, &path)?;
+
+    let json_str = serde_json::to_string_pretty(layout)
+        .map_err(|e| format!("Failed to serialize tab layout: {}", e))?;
+    crate::storage_service::write_atomic(path, json_str)
+        .map_err(|e| format!("Failed to save tab layout: {}", e))?;
+
+    app_handle
+        .emit("tab-layout-changed", layout)
+        .map_err(|e| format!("Failed to emit tab-layout-changed event: {}", e))
+}
+
+// Get the tab order and pinned tabs
+#[tauri::command]
+pub fn get_tab_layout<R: Runtime>(app_handle: AppHandle<R>) -> TabLayout {
+    load_layout(&app_handle)
+}
+
+// Save a new tab order; it must list every tab exactly once
+#[tauri::command]
+pub fn set_tab_order<R: Runtime>(
+    app_handle: AppHandle<R>,
+    order: Vec<usize>,
+) -> Result<(), String> {
+    let mut sorted = order.clone();
+    sorted.sort_unstable();
+    if sorted != (0..TAB_COUNT).collect::<Vec<_>>() {
+        return Err(format!(
+            "Tab order must list each tab from 0 to {} once: {:?}",
+            TAB_COUNT - 1,
+            order
+        ));
+    }
+
+    let mut layout = load_layout(&app_handle);
+    layout.order = order;
+    save_layout(&app_handle, &layout)?;
+
+    info!("Tab order set to {:?}", layout.order);
+    Ok(())
+}
+
+// Pin a note so it's listed before unpinned ones, or unpin it
+#[tauri::command]
+pub fn pin_note<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    pinned: bool,
+) -> Result<(), String> {
+    if tab_index >= TAB_COUNT {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    let mut layout = load_layout(&app_handle);
+    layout.pinned.retain(|tab| *tab != tab_index);
+    if pinned {
+        layout.pinned.push(tab_index);
+        layout.pinned.sort_unstable();
+    }
+
+    save_layout(&app_handle, &layout)
+}
+
+fn get_editor_state_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join("editor_state.json")
+}
+
+// Saved editor state per tab, including any not yet written
+pub fn load_editor_states<R: Runtime>(app_handle: &AppHandle<R>) -> HashMap<usize, EditorState> {
+    let mut states: HashMap<usize, EditorState> =
+        fs::read_to_string(get_editor_state_path(app_handle))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+    if let Ok(pending) = PENDING_EDITOR_STATE.lock() {
+        states.extend(pending.iter().map(|(tab, state)| (*tab, state.clone())));
+    }
+    states.retain(|tab, _| *tab < TAB_COUNT);
+    states
+}
+
+// Write reported editor state now, e.g. on exit
+pub fn flush_editor_state<R: Runtime>(app_handle: &AppHandle<R>) {
+    let states = load_editor_states(app_handle);
+    let pending = PENDING_EDITOR_STATE
+        .lock()
+        .map(|mut pending| std::mem::take(&mut *pending))
+        .unwrap_or_default();
+    if pending.is_empty() {
+        return;
+    }
+
+    let path = get_editor_state_path(app_handle);
+    let result = crate::data_dir::ensure_writable(app_handle, &path).and_then(|_| {
+        let json_str = serde_json::to_string_pretty(&states)
+            .map_err(|e| format!("Failed to serialize editor state: {}", e))?;
+        crate::storage_service::write_atomic(path, json_str)
+            .map_err(|e| format!("Failed to save editor state: {}", e))
+    });
+    if let Err(e) = result {
+        // Only convenience is lost
+        error!("{}", e);
+    }
+}
+
+// Remember where the editor is in a tab, so it reopens there; written after a
+// short pause since it's reported on every cursor move
+#[tauri::command]
+pub fn save_editor_state<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab_index: usize,
+    state: EditorState,
+) -> Result<(), String> {
+    if tab_index >= TAB_COUNT {
+        return Err(format!("Invalid tab index: {}", tab_index));
+    }
+
+    PENDING_EDITOR_STATE
+        .lock()
+        .map_err(|e| format!("Failed to buffer editor state: {}", e))?
+        .insert(tab_index, state);
+
+    let generation = EDITOR_STATE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(EDITOR_STATE_DELAY).await;
+        if EDITOR_STATE_GENERATION.load(Ordering::SeqCst) == generation {
+            flush_editor_state(&app_handle);
+        }
+    });
+
+    Ok(())
+}
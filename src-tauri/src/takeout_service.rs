@@ -0,0 +1,867 @@
+// takeout_service.rs - Whole-app data export and import
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Emitter, Runtime};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+// Bump when the archive layout changes so importers can adapt
+pub const TAKEOUT_FORMAT_VERSION: u32 = 1;
+
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+// Below the backups directory, so the snapshots stay out of the backup list
+const SNAPSHOT_DIR: &str = "pre-import";
+
+// Directories living next to the notes: (category, directory name)
+const STORAGE_DIRS: [(&str, &str); 2] = [("attachments", "assets"), ("archives", "archive")];
+
+// Directories living in the app data directory: (category, directory name)
+const APP_DATA_DIRS: [(&str, &str); 3] = [
+    ("history", crate::journal_service::JOURNAL_DIR_NAME),
+    ("themes", crate::theme_service::THEMES_DIR_NAME),
+    ("locales", crate::i18n_service::LOCALES_DIR_NAME),
+];
+
+// Categories written through the note encoder, so encrypted along with the
+// notes; exported decrypted like the notes themselves
+const ENCODED_CATEGORIES: [&str; 2] = ["history", "archives"];
+
+// Settings keys containing any of these fragments are never exported
+const SECRET_KEY_FRAGMENTS: [&str; 5] = ["password", "secret", "token", "api_key", "apikey"];
+
+// Settings tied to this machine that an import never overwrites
+const MACHINE_SPECIFIC_KEYS: [&str; 5] = [
+    "custom_storage_path",
+    "using_custom_storage",
+    "tab_storage_paths",
+    "encrypt_notes",
+    // Only turned on after a native confirmation on this machine
+    "code_execution_enabled",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TakeoutEntry {
+    pub category: String,
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TakeoutManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: String,
+    pub entries: Vec<TakeoutEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    // Overlay the archive onto current data, keeping anything it doesn't contain
+    Merge,
+    // Make each selected category match the archive exactly
+    Replace,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportItem {
+    pub category: String,
+    pub path: String,
+    // "create", "overwrite", "merge" or "delete"
+    pub action: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportPreview {
+    pub app_version: String,
+    pub created_at: String,
+    pub items: Vec<ImportItem>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportReport {
+    pub backup_path: String,
+    // Takeout archive of everything the import overwrote or deleted
+    pub snapshot_path: String,
+    pub imported: usize,
+    pub deleted: usize,
+}
+
+// Where an archive entry ends up on disk
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ImportTarget {
+    Note(usize),
+    Settings,
+    File(PathBuf),
+}
+
+struct PlannedItem {
+    item: ImportItem,
+    target: ImportTarget,
+    // Archive entry to read from; None for deletions
+    source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BackupInfo {
+    filename: String,
+    size: u64,
+    modified: Option<String>,
+}
+
+// Remove credential-like values from a settings object, recursively
+fn strip_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|key, _| {
+                let key = key.to_lowercase();
+                !SECRET_KEY_FRAGMENTS
+                    .iter()
+                    .any(|fragment| key.contains(fragment))
+            });
+            for nested in map.values_mut() {
+                strip_secrets(nested);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                strip_secrets(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Add a single in-memory file to the archive and record it in the manifest
+fn add_bytes(
+    zip: &mut ZipWriter<fs::File>,
+    options: SimpleFileOptions,
+    entries: &mut Vec<TakeoutEntry>,
+    category: &str,
+    archive_path: &str,
+    bytes: &[u8],
+) -> Result<(), String> {
+    zip.start_file(archive_path, options)
+        .map_err(|e| format!("Failed to add {} to export: {}", archive_path, e))?;
+    zip.write_all(bytes)
+        .map_err(|e| format!("Failed to write {} to export: {}", archive_path, e))?;
+
+    entries.push(TakeoutEntry {
+        category: category.to_string(),
+        path: archive_path.to_string(),
+        size: bytes.len() as u64,
+    });
+
+    Ok(())
+}
+
+// Recursively add a directory to the archive under the given prefix
+fn add_dir<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    zip: &mut ZipWriter<fs::File>,
+    options: SimpleFileOptions,
+    entries: &mut Vec<TakeoutEntry>,
+    category: &str,
+    dir: &Path,
+    prefix: &str,
+) -> Result<(), String> {
+    let read_dir = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+
+    for entry in read_dir.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let archive_path = format!("{}/{}", prefix, name);
+
+        if path.is_dir() {
+            add_dir(
+                app_handle,
+                zip,
+                options,
+                entries,
+                category,
+                &path,
+                &archive_path,
+            )?;
+        } else if path.is_file() {
+            let mut bytes =
+                fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if ENCODED_CATEGORIES.contains(&category) {
+                bytes = crate::storage_service::decode_bytes(app_handle, &bytes)?;
+            }
+            add_bytes(zip, options, entries, category, &archive_path, &bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Write the manifest last, so it describes every entry added before it
+fn finish_archive<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    mut zip: ZipWriter<fs::File>,
+    options: SimpleFileOptions,
+    entries: Vec<TakeoutEntry>,
+) -> Result<(), String> {
+    let manifest = TakeoutManifest {
+        format_version: TAKEOUT_FORMAT_VERSION,
+        app_version: app_handle.package_info().version.to_string(),
+        created_at: chrono::Local::now().to_rfc3339(),
+        entries,
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize export manifest: {}", e))?;
+
+    zip.start_file(MANIFEST_FILENAME, options)
+        .map_err(|e| format!("Failed to add manifest to export: {}", e))?;
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize export: {}", e))?;
+    Ok(())
+}
+
+// Collect filename, size and modification time of every backup archive
+fn collect_backup_info(backups_dir: &Path) -> Vec<BackupInfo> {
+    let Ok(read_dir) = fs::read_dir(backups_dir) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<BackupInfo> = read_dir
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && matches!(path.extension(), Some(ext) if ext == "zip"))
+        .map(|path| {
+            let metadata = fs::metadata(&path).ok();
+            BackupInfo {
+                filename: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                modified: metadata
+                    .and_then(|m| m.modified().ok())
+                    .map(|time| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339()),
+            }
+        })
+        .collect();
+
+    backups.sort_by(|a, b| b.filename.cmp(&a.filename));
+    backups
+}
+
+// Resolve the export target: a directory gets a generated filename
+fn resolve_target_path(path: &str) -> PathBuf {
+    let target = PathBuf::from(path);
+    if target.is_dir() {
+        let datetime = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+        target.join(format!("jot_takeout_{}.zip", datetime))
+    } else {
+        target
+    }
+}
+
+// Export notes, settings and all auxiliary data into a single zip with a manifest
+#[tauri::command]
+pub async fn export_everything<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+) -> Result<String, String> {
+    let _timing = crate::metrics_service::time_async_command("export_everything");
+    crate::autosave_service::flush_before_backup(&app_handle);
+
+    let app_dir = crate::data_dir::get_app_data_dir(&app_handle);
+    let storage_dir = crate::storage_service::get_current_storage_dir(&app_handle);
+    let target_path = resolve_target_path(&path);
+
+    if let Some(parent) = target_path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create export directory: {}", e))?;
+        }
+    }
+
+    let file = fs::File::create(&target_path)
+        .map_err(|e| format!("Failed to create export file: {}", e))?;
+
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+    let mut entries = Vec::new();
+
+    // Notes
+    for i in 0..7 {
+        // Notes are exported decrypted so the archive is readable anywhere
+        if let Some(content) = crate::storage_service::read_note(&app_handle, i)? {
+            let archive_path = format!("notes/note_{}.md", i);
+            add_bytes(
+                &mut zip,
+                options,
+                &mut entries,
+                "notes",
+                &archive_path,
+                content.as_bytes(),
+            )?;
+        }
+    }
+
+    // Settings, with anything credential-like removed
+    let settings_path = app_dir.join("settings.json");
+    if settings_path.exists() {
+        let content = fs::read_to_string(&settings_path)
+            .map_err(|e| format!("Failed to read settings file: {}", e))?;
+
+        match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(mut settings) => {
+                strip_secrets(&mut settings);
+                let json_str = serde_json::to_string_pretty(&settings)
+                    .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+                add_bytes(
+                    &mut zip,
+                    options,
+                    &mut entries,
+                    "settings",
+                    "settings/settings.json",
+                    json_str.as_bytes(),
+                )?;
+            }
+            Err(e) => warn!("Skipping unparseable settings file in export: {}", e),
+        }
+    }
+
+    // Auxiliary directories
+    for (category, dir_name) in STORAGE_DIRS {
+        let dir = storage_dir.join(dir_name);
+        if dir.is_dir() {
+            add_dir(
+                &app_handle,
+                &mut zip,
+                options,
+                &mut entries,
+                category,
+                &dir,
+                category,
+            )?;
+        }
+    }
+    for (category, dir_name) in APP_DATA_DIRS {
+        let dir = app_dir.join(dir_name);
+        if dir.is_dir() {
+            add_dir(
+                &app_handle,
+                &mut zip,
+                options,
+                &mut entries,
+                category,
+                &dir,
+                category,
+            )?;
+        }
+    }
+
+    // Backup metadata only, not the archives themselves
+    let backups = collect_backup_info(&app_dir.join("backups"));
+    let backups_json = serde_json::to_string_pretty(&backups)
+        .map_err(|e| format!("Failed to serialize backup metadata: {}", e))?;
+    add_bytes(
+        &mut zip,
+        options,
+        &mut entries,
+        "backups",
+        "backups/backups.json",
+        backups_json.as_bytes(),
+    )?;
+
+    let count = entries.len();
+    finish_archive(&app_handle, zip, options, entries)?;
+
+    info!("Exported {} files to {}", count, target_path.display());
+
+    Ok(target_path.to_string_lossy().to_string())
+}
+
+// Reject absolute paths and parent-directory components inside an archive
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+// Only the app's tabs; a crafted `notes/note_999.md` isn't importable
+fn parse_note_index(path: &str) -> Option<usize> {
+    path.strip_prefix("notes/note_")?
+        .strip_suffix(".md")?
+        .parse::<usize>()
+        .ok()
+        .filter(|index| *index < crate::tabs_service::TAB_COUNT)
+}
+
+// Map a manifest entry to its destination, or None if it isn't importable
+fn resolve_import_target(
+    entry: &TakeoutEntry,
+    storage_dir: &Path,
+    app_dir: &Path,
+) -> Option<ImportTarget> {
+    match entry.category.as_str() {
+        "notes" => parse_note_index(&entry.path).map(ImportTarget::Note),
+        "settings" if entry.path == "settings/settings.json" => Some(ImportTarget::Settings),
+        category => {
+            let (base, dir_name) = STORAGE_DIRS
+                .iter()
+                .map(|(c, d)| (storage_dir, *c, *d))
+                .chain(APP_DATA_DIRS.iter().map(|(c, d)| (app_dir, *c, *d)))
+                .find(|(_, c, _)| *c == category)
+                .map(|(base, _, dir_name)| (base, dir_name))?;
+
+            let relative = entry.path.strip_prefix(&format!("{}/", category))?;
+            if !is_safe_relative(relative) {
+                warn!("Skipping unsafe path in import archive: {}", entry.path);
+                return None;
+            }
+
+            Some(ImportTarget::File(base.join(dir_name).join(relative)))
+        }
+    }
+}
+
+// Recursively list files below a directory
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for entry in read_dir.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(list_files(&path));
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn read_manifest(archive: &mut zip::ZipArchive<fs::File>) -> Result<TakeoutManifest, String> {
+    let mut file = archive
+        .by_name(MANIFEST_FILENAME)
+        .map_err(|_| "Not a Jot export: manifest.json is missing".to_string())?;
+
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read export manifest: {}", e))?;
+
+    let manifest: TakeoutManifest = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse export manifest: {}", e))?;
+
+    if manifest.format_version > TAKEOUT_FORMAT_VERSION {
+        return Err(format!(
+            "Export format version {} is newer than supported version {}",
+            manifest.format_version, TAKEOUT_FORMAT_VERSION
+        ));
+    }
+
+    Ok(manifest)
+}
+
+fn open_archive(path: &str) -> Result<zip::ZipArchive<fs::File>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open export file: {}", e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("Failed to read export archive: {}", e))
+}
+
+// Work out every change an import would make, without touching disk
+fn plan_import<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    manifest: &TakeoutManifest,
+    mode: ImportMode,
+    categories: &Option<Vec<String>>,
+) -> Vec<PlannedItem> {
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
+    let storage_dir = crate::storage_service::get_current_storage_dir(app_handle);
+    let is_selected = |category: &str| {
+        categories
+            .as_ref()
+            .is_none_or(|selected| selected.iter().any(|c| c == category))
+    };
+
+    let mut planned = Vec::new();
+    let mut incoming: HashSet<ImportTarget> = HashSet::new();
+
+    for entry in &manifest.entries {
+        if !is_selected(&entry.category) {
+            continue;
+        }
+        let Some(target) = resolve_import_target(entry, &storage_dir, &app_dir) else {
+            continue;
+        };
+
+        let exists = match &target {
+            ImportTarget::Note(index) => {
+                crate::storage_service::get_note_path(app_handle, *index).exists()
+            }
+            ImportTarget::Settings => app_dir.join("settings.json").exists(),
+            ImportTarget::File(path) => path.exists(),
+        };
+        let action = match (&target, exists, mode) {
+            (_, false, _) => "create",
+            (ImportTarget::Settings, true, ImportMode::Merge) => "merge",
+            (_, true, _) => "overwrite",
+        };
+
+        incoming.insert(target.clone());
+        planned.push(PlannedItem {
+            item: ImportItem {
+                category: entry.category.clone(),
+                path: entry.path.clone(),
+                action: action.to_string(),
+            },
+            target,
+            source: Some(entry.path.clone()),
+        });
+    }
+
+    if mode == ImportMode::Replace {
+        // Local notes the archive doesn't contain
+        if is_selected("notes") {
+            for index in 0..7 {
+                let target = ImportTarget::Note(index);
+                let path = crate::storage_service::get_note_path(app_handle, index);
+                if path.exists() && !incoming.contains(&target) {
+                    planned.push(PlannedItem {
+                        item: ImportItem {
+                            category: "notes".to_string(),
+                            path: format!("notes/note_{}.md", index),
+                            action: "delete".to_string(),
+                        },
+                        target,
+                        source: None,
+                    });
+                }
+            }
+        }
+
+        // Local files in selected directories the archive doesn't contain
+        let dirs = STORAGE_DIRS
+            .iter()
+            .map(|(c, d)| (*c, storage_dir.join(d)))
+            .chain(APP_DATA_DIRS.iter().map(|(c, d)| (*c, app_dir.join(d))));
+        for (category, dir) in dirs {
+            if !is_selected(category) {
+                continue;
+            }
+            for path in list_files(&dir) {
+                let target = ImportTarget::File(path.clone());
+                if incoming.contains(&target) {
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(&dir)
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default();
+                planned.push(PlannedItem {
+                    item: ImportItem {
+                        category: category.to_string(),
+                        path: format!("{}/{}", category, relative),
+                        action: "delete".to_string(),
+                    },
+                    target,
+                    source: None,
+                });
+            }
+        }
+    }
+
+    planned
+}
+
+// Combine imported settings with the local ones according to the mode
+fn combine_settings(
+    local: serde_json::Value,
+    imported: serde_json::Value,
+    mode: ImportMode,
+) -> serde_json::Value {
+    let mut result = match mode {
+        ImportMode::Merge => {
+            let mut merged = local.clone();
+            if let (Some(target), Some(source)) = (merged.as_object_mut(), imported.as_object()) {
+                for (key, value) in source {
+                    target.insert(key.clone(), value.clone());
+                }
+            }
+            merged
+        }
+        ImportMode::Replace => imported,
+    };
+
+    // Storage location, encryption and code execution always stay as
+    // configured on this machine
+    if let Some(target) = result.as_object_mut() {
+        for key in MACHINE_SPECIFIC_KEYS {
+            match local.get(key) {
+                Some(value) => {
+                    target.insert(key.to_string(), value.clone());
+                }
+                None => {
+                    target.remove(key);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+// Save everything the import overwrites or deletes, as it is on disk (notes
+// stay encrypted, settings keep their secrets), under its archive path
+fn snapshot_before_import<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    planned: &[PlannedItem],
+) -> Result<PathBuf, String> {
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
+    let snapshot_dir = app_dir.join("backups").join(SNAPSHOT_DIR);
+    fs::create_dir_all(&snapshot_dir)
+        .map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+
+    let datetime = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let snapshot_path = snapshot_dir.join(format!("jot_pre_import_{}.zip", datetime));
+    let file = fs::File::create(&snapshot_path)
+        .map_err(|e| format!("Failed to create snapshot file: {}", e))?;
+
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+    let mut entries = Vec::new();
+
+    for planned in planned {
+        let path = match &planned.target {
+            ImportTarget::Note(index) => crate::storage_service::get_note_path(app_handle, *index),
+            ImportTarget::Settings => app_dir.join("settings.json"),
+            ImportTarget::File(path) => path.clone(),
+        };
+        if !path.is_file() {
+            continue;
+        }
+
+        let bytes =
+            fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        add_bytes(
+            &mut zip,
+            options,
+            &mut entries,
+            &planned.item.category,
+            &planned.item.path,
+            &bytes,
+        )?;
+    }
+
+    finish_archive(app_handle, zip, options, entries)?;
+    Ok(snapshot_path)
+}
+
+fn read_archive_entry(
+    archive: &mut zip::ZipArchive<fs::File>,
+    name: &str,
+) -> Result<Vec<u8>, String> {
+    let mut file = archive
+        .by_name(name)
+        .map_err(|e| format!("Missing {} in export archive: {}", name, e))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {} from export archive: {}", name, e))?;
+    Ok(bytes)
+}
+
+// Journal files are only replaced while no save is recording into them
+fn is_history(planned: &PlannedItem) -> bool {
+    planned.item.category == "history"
+}
+
+fn apply_item<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    archive: &mut zip::ZipArchive<fs::File>,
+    planned: &PlannedItem,
+    mode: ImportMode,
+) -> Result<(), String> {
+    let Some(source) = &planned.source else {
+        // Deletion
+        let path = match &planned.target {
+            ImportTarget::Note(index) => crate::storage_service::get_note_path(app_handle, *index),
+            ImportTarget::Settings => return Ok(()),
+            ImportTarget::File(path) => path.clone(),
+        };
+        let _journals = is_history(planned).then(crate::journal_service::lock_journals);
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        if let ImportTarget::Note(index) = planned.target {
+            crate::integrity_service::forget_checksum(app_handle, index);
+            crate::note_events::emit_note_updated(app_handle, index, "")?;
+        }
+        return Ok(());
+    };
+
+    let bytes = read_archive_entry(archive, source)?;
+
+    match &planned.target {
+        ImportTarget::Note(index) => {
+            let content = String::from_utf8(bytes)
+                .map_err(|e| format!("Imported note {} is not valid UTF-8: {}", index, e))?;
+            crate::storage_service::write_note_for_operation(
+                app_handle,
+                *index,
+                &content,
+                "Import data",
+            )?;
+            crate::note_events::emit_note_updated(app_handle, *index, &content)?;
+        }
+        ImportTarget::Settings => {
+            let imported: serde_json::Value = serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Failed to parse imported settings: {}", e))?;
+            crate::data_dir::update_settings(app_handle, |settings| {
+                *settings = combine_settings(settings.take(), imported, mode);
+                Ok(())
+            })?;
+        }
+        ImportTarget::File(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create directory: {}", e))?;
+            }
+            let bytes = if ENCODED_CATEGORIES.contains(&planned.item.category.as_str()) {
+                crate::storage_service::encode_bytes(app_handle, &bytes)?
+            } else {
+                bytes
+            };
+            let _journals = is_history(planned).then(crate::journal_service::lock_journals);
+            crate::storage_service::write_atomic(path, bytes)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Show what an import would create, overwrite or delete
+#[tauri::command]
+pub fn preview_import<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+    mode: ImportMode,
+    categories: Option<Vec<String>>,
+) -> Result<ImportPreview, String> {
+    let mut archive = open_archive(&path)?;
+    let manifest = read_manifest(&mut archive)?;
+
+    let items = plan_import(&app_handle, &manifest, mode, &categories)
+        .into_iter()
+        .map(|planned| planned.item)
+        .collect();
+
+    Ok(ImportPreview {
+        app_version: manifest.app_version,
+        created_at: manifest.created_at,
+        items,
+    })
+}
+
+// Import a takeout archive, after saving everything it is about to replace
+#[tauri::command]
+pub async fn import_everything<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+    mode: ImportMode,
+    categories: Option<Vec<String>>,
+) -> Result<ImportReport, String> {
+    let _timing = crate::metrics_service::time_async_command("import_everything");
+    let mut archive = open_archive(&path)?;
+    let manifest = read_manifest(&mut archive)?;
+    let planned = plan_import(&app_handle, &manifest, mode, &categories);
+
+    // Refuse to import without a safety net: a backup of the notes for the
+    // backup list, and a snapshot of everything else the import replaces
+    let backup_path = crate::backup_service::create_backup(app_handle.clone())
+        .await
+        .map_err(|e| format!("Failed to create pre-import backup: {}", e))?;
+    info!("Created backup before import: {}", backup_path);
+    app_handle
+        .emit("backup-created", backup_path.clone())
+        .unwrap_or_else(|e| error!("Failed to emit backup-created event: {}", e));
+
+    let snapshot_path = snapshot_before_import(&app_handle, &planned)
+        .map_err(|e| format!("Failed to create pre-import snapshot: {}", e))?;
+    info!(
+        "Saved data replaced by the import to {}",
+        snapshot_path.display()
+    );
+
+    let mut imported = 0;
+    let mut deleted = 0;
+    for item in &planned {
+        apply_item(&app_handle, &mut archive, item, mode)?;
+        if item.source.is_some() {
+            imported += 1;
+        } else {
+            deleted += 1;
+        }
+    }
+
+    info!(
+        "Imported {} files and removed {} from {} ({:?} mode)",
+        imported, deleted, path, mode
+    );
+    app_handle
+        .emit("data-imported", ())
+        .map_err(|e| format!("Failed to emit import event: {}", e))?;
+
+    Ok(ImportReport {
+        backup_path,
+        snapshot_path: snapshot_path.to_string_lossy().to_string(),
+        imported,
+        deleted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(category: &str, path: &str) -> TakeoutEntry {
+        TakeoutEntry {
+            category: category.to_string(),
+            path: path.to_string(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn note_paths_map_to_tabs() {
+        assert_eq!(parse_note_index("notes/note_0.md"), Some(0));
+        assert_eq!(parse_note_index("notes/note_6.md"), Some(6));
+        assert_eq!(parse_note_index("notes/note_x.md"), None);
+        assert_eq!(parse_note_index("notes/sub/note_1.md"), None);
+    }
+
+    #[test]
+    fn note_indexes_past_the_last_tab_are_not_importable() {
+        assert_eq!(parse_note_index("notes/note_7.md"), None);
+        assert_eq!(parse_note_index("notes/note_999.md"), None);
+
+        let dir = Path::new("/data");
+        assert_eq!(
+            resolve_import_target(&entry("notes", "notes/note_999.md"), dir, dir),
+            None
+        );
+        assert_eq!(
+            resolve_import_target(&entry("notes", "notes/note_3.md"), dir, dir),
+            Some(ImportTarget::Note(3))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+// tasks_service.rs - `- [ ]` / `- [x]` checkboxes across all notes, for a
+// unified todo view
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Runtime};
+
+use crate::storage_service;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub tab: usize,
+    // 1-based line in the note file
+    pub line: usize,
+    pub text: String,
+    pub done: bool,
+}
+
+// Byte offset of the checkbox mark (the space or `x`) and whether it's checked
+fn parse_task_line(line: &str) -> Option<(usize, bool)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+
+    let rest = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))
+        .or_else(|| trimmed.strip_prefix("+ "))?;
+    let done = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    // `- [ ]` alone or followed by the task text
+    if !rest[3..].is_empty() && !rest[3..].starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    Some((indent + 2 + 1, done))
+}
+
+// Find the tasks in a note, skipping fenced code blocks
+fn parse_tasks(tab: usize, content: &str) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        if let Some((mark, done)) = parse_task_line(line) {
+            tasks.push(Task {
+                tab,
+                line: line_index + 1,
+                text: line[mark + 2..].trim().to_string(),
+                done,
+            });
+        }
+    }
+
+    tasks
+}
+
+// List the checkbox tasks of all notes, in tab order
+#[tauri::command]
+pub fn get_tasks<R: Runtime>(app_handle: AppHandle<R>) -> Result<Vec<Task>, String> {
+    // Buffered edits are newer than the files
+    if let Err(e) = crate::autosave_service::flush_all(&app_handle) {
+        warn!("Failed to save pending notes before reading tasks: {}", e);
+    }
+
+    let mut tasks = Vec::new();
+    for tab in 0..7 {
+        if let Some(content) = storage_service::read_note(&app_handle, tab)? {
+            tasks.extend(parse_tasks(tab, &content));
+        }
+    }
+
+    Ok(tasks)
+}
+
+// Check or uncheck the task on a line of a note; returns the updated task
+#[tauri::command]
+pub fn toggle_task<R: Runtime>(
+    app_handle: AppHandle<R>,
+    tab: usize,
+    line: usize,
+) -> Result<Task, String> {
+    if tab >= 7 {
+        return Err(format!("Invalid tab index: {}", tab));
+    }
+
+    crate::autosave_service::flush_all(&app_handle)?;
+
+    let content = storage_service::read_note(&app_handle, tab)?
+        .ok_or_else(|| format!("Note {} doesn't exist", tab + 1))?;
+
+    // Edit the line in place, keeping the note's line endings
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+    let target = line
+        .checked_sub(1)
+        .and_then(|index| lines.get_mut(index))
+        .ok_or_else(|| format!("Note {} has no line {}", tab + 1, line))?;
+
+    let (mark, done) = parse_task_line(target)
+        .ok_or_else(|| format!("Line {} of note {} is not a task", line, tab + 1))?;
+    target.replace_range(mark..mark + 1, if done { " " } else { "x" });
+
+    let task = Task {
+        tab,
+        line,
+        text: target.trim_end()[mark + 2..].trim().to_string(),
+        done: !done,
+    };
+
+    let updated = lines.concat();
+    storage_service::write_note_for_operation(&app_handle, tab, &updated, "Toggle task")?;
+
+    info!(
+        "Marked task on line {} of note {} as {}",
+        line,
+        tab,
+        if task.done { "done" } else { "open" }
+    );
+
+    crate::note_events::emit_note_updated(&app_handle, tab, &updated)?;
+
+    Ok(task)
+}
@@ -0,0 +1,517 @@
+// theme_service.rs - Built-in and user-installed themes, and appearance settings validation
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+
+pub const THEMES_DIR_NAME: &str = "themes";
+
+const BUILTIN_THEMES: [&str; 2] = ["light", "dark"];
+const FONT_SIZES: [&str; 3] = ["small", "medium", "large"];
+
+// Themes are small; anything bigger is not a theme
+const MAX_THEME_SIZE: u64 = 256 * 1024;
+
+// A user theme: `<id>.json` in the themes directory, optionally with `<id>.css`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThemeDefinition {
+    pub name: String,
+    // Built-in theme this one extends, so unset colors stay readable
+    #[serde(default = "default_base")]
+    pub base: String,
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+}
+
+fn default_base() -> String {
+    "light".to_string()
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeInfo {
+    pub id: String,
+    pub name: String,
+    pub base: String,
+    pub builtin: bool,
+    pub has_css: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeContent {
+    pub info: ThemeInfo,
+    pub colors: HashMap<String, String>,
+    pub css: Option<String>,
+}
+
+pub fn get_themes_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    crate::data_dir::get_app_data_dir(app_handle).join(THEMES_DIR_NAME)
+}
+
+// Theme ids double as file names
+fn theme_id_from_path(path: &Path) -> Result<String, String> {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let id: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if id.is_empty() {
+        return Err(format!("Invalid theme file name: {}", path.display()));
+    }
+    if BUILTIN_THEMES.contains(&id.as_str()) {
+        return Err(format!("Theme id {} is reserved for a built-in theme", id));
+    }
+
+    Ok(id)
+}
+
+// Colors end up in CSS custom properties; keep them to plain values
+fn validate_color(key: &str, value: &str) -> Result<(), String> {
+    let key_ok = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !key_ok {
+        return Err(format!("Invalid color name: {}", key));
+    }
+
+    let value_ok = !value.trim().is_empty()
+        && !value
+            .chars()
+            .any(|c| matches!(c, ';' | '{' | '}' | '<' | '>' | '\\' | '"' | '\''));
+    if !value_ok {
+        return Err(format!("Invalid value for color {}: {}", key, value));
+    }
+    validate_css(value).map_err(|e| format!("Invalid value for color {}: {}", key, e))?;
+
+    Ok(())
+}
+
+pub fn validate_definition(definition: &ThemeDefinition) -> Result<(), String> {
+    if definition.name.trim().is_empty() {
+        return Err("Theme has no name".to_string());
+    }
+    if !BUILTIN_THEMES.contains(&definition.base.as_str()) {
+        return Err(format!(
+            "Unknown base theme {}: use one of {}",
+            definition.base,
+            BUILTIN_THEMES.join(", ")
+        ));
+    }
+    for (key, value) in &definition.colors {
+        validate_color(key, value)?;
+    }
+    Ok(())
+}
+
+// The CSS as the browser reads it: comments dropped, escapes decoded
+// (`\40 import` is `@import`) and lowercased
+fn normalize_css(css: &str) -> String {
+    let mut normalized = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '\\' => {
+                let mut hex = String::new();
+                while hex.len() < 6 && chars.peek().is_some_and(char::is_ascii_hexdigit) {
+                    hex.extend(chars.next());
+                }
+                if hex.is_empty() {
+                    // An escaped newline continues the line
+                    match chars.next() {
+                        Some('\n') | None => {}
+                        Some(c) => normalized.push(c),
+                    }
+                } else {
+                    if chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+                        chars.next();
+                    }
+                    normalized.push(
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .unwrap_or(char::REPLACEMENT_CHARACTER),
+                    );
+                }
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized.to_lowercase()
+}
+
+// Arguments of every call to `function` in normalized CSS, up to the
+// matching parenthesis
+fn function_arguments<'a>(css: &'a str, function: &str) -> Vec<&'a str> {
+    let pattern = format!("{}(", function);
+    css.match_indices(&pattern)
+        .map(|(start, _)| {
+            let arguments = &css[start + pattern.len()..];
+            let mut depth = 0;
+            let end = arguments
+                .char_indices()
+                .find(|(_, c)| match c {
+                    '(' => {
+                        depth += 1;
+                        false
+                    }
+                    ')' if depth == 0 => true,
+                    ')' => {
+                        depth -= 1;
+                        false
+                    }
+                    _ => false,
+                })
+                .map_or(arguments.len(), |(end, _)| end);
+            &arguments[..end]
+        })
+        .collect()
+}
+
+// Quoted strings in a piece of CSS, without their quotes
+fn quoted_strings(css: &str) -> Vec<&str> {
+    let mut strings = Vec::new();
+    let mut rest = css;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let after = &rest[start + 1..];
+        let end = after.find(quote).unwrap_or(after.len());
+        strings.push(&after[..end]);
+        rest = after.get(end + 1..).unwrap_or("");
+    }
+    strings
+}
+
+fn is_data_url(url: &str) -> bool {
+    url.trim_start().starts_with("data:")
+}
+
+// Themes must not pull in anything from outside the theme (remote or local
+// files), only inline `data:` URLs, or break out of the style tag
+pub fn validate_css(css: &str) -> Result<(), String> {
+    let css = normalize_css(css);
+    if css.contains("@import") {
+        return Err("Theme CSS must not use @import".to_string());
+    }
+    if css.contains("</style") {
+        return Err("Theme CSS must not close the style tag".to_string());
+    }
+
+    for arguments in function_arguments(&css, "url") {
+        let url = arguments.trim().trim_matches(['"', '\'']);
+        if !is_data_url(url) {
+            return Err(format!(
+                "Theme CSS may only use data: URLs, found url({})",
+                arguments.trim()
+            ));
+        }
+    }
+    // These take a URL as a plain string, too
+    for function in ["image-set", "src"] {
+        for arguments in function_arguments(&css, function) {
+            if let Some(url) = quoted_strings(arguments)
+                .into_iter()
+                .find(|url| !is_data_url(url))
+            {
+                return Err(format!(
+                    "Theme CSS may only use data: URLs, found {}(\"{}\")",
+                    function, url
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_theme_file(path: &Path) -> Result<String, String> {
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read theme file: {}", e))?
+        .len();
+    if size > MAX_THEME_SIZE {
+        return Err(format!(
+            "Theme file is too large ({} bytes, limit is {} bytes)",
+            size, MAX_THEME_SIZE
+        ));
+    }
+
+    fs::read_to_string(path).map_err(|e| format!("Failed to read theme file: {}", e))
+}
+
+fn load_definition(path: &Path) -> Result<ThemeDefinition, String> {
+    let definition: ThemeDefinition = serde_json::from_str(&read_theme_file(path)?)
+        .map_err(|e| format!("Failed to parse theme {}: {}", path.display(), e))?;
+    validate_definition(&definition)?;
+    Ok(definition)
+}
+
+fn builtin_info(id: &str) -> ThemeInfo {
+    let mut name = id.to_string();
+    name[..1].make_ascii_uppercase();
+
+    ThemeInfo {
+        id: id.to_string(),
+        name,
+        base: id.to_string(),
+        builtin: true,
+        has_css: false,
+    }
+}
+
+fn custom_themes<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<(ThemeInfo, ThemeDefinition)> {
+    let themes_dir = get_themes_dir(app_handle);
+    let Ok(entries) = fs::read_dir(&themes_dir) else {
+        return Vec::new();
+    };
+
+    let mut themes: Vec<(ThemeInfo, ThemeDefinition)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let id = theme_id_from_path(&path).ok()?;
+            let definition = match load_definition(&path) {
+                Ok(definition) => definition,
+                Err(e) => {
+                    warn!("Skipping theme {}: {}", id, e);
+                    return None;
+                }
+            };
+            let info = ThemeInfo {
+                has_css: themes_dir.join(format!("{}.css", id)).exists(),
+                id,
+                name: definition.name.clone(),
+                base: definition.base.clone(),
+                builtin: false,
+            };
+            Some((info, definition))
+        })
+        .collect();
+    themes.sort_by_key(|(info, _)| info.name.to_lowercase());
+
+    themes
+}
+
+pub fn theme_exists<R: Runtime>(app_handle: &AppHandle<R>, id: &str) -> bool {
+    BUILTIN_THEMES.contains(&id)
+        || custom_themes(app_handle)
+            .iter()
+            .any(|(info, _)| info.id == id)
+}
+
+// Check the appearance keys of a settings object before it's saved
+pub fn validate_appearance<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    settings: &serde_json::Value,
+) -> Result<(), String> {
+    match &settings["theme"] {
+        serde_json::Value::Null => {}
+        serde_json::Value::String(theme) if theme_exists(app_handle, theme) => {}
+        other => return Err(format!("Unknown theme: {}", other)),
+    }
+
+    match &settings["fontSize"] {
+        serde_json::Value::Null => {}
+        serde_json::Value::String(size) if FONT_SIZES.contains(&size.as_str()) => {}
+        other => {
+            return Err(format!(
+                "Invalid font size {}: use one of {}",
+                other,
+                FONT_SIZES.join(", ")
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+// List built-in and installed themes
+#[tauri::command]
+pub fn list_themes<R: Runtime>(app_handle: AppHandle<R>) -> Vec<ThemeInfo> {
+    BUILTIN_THEMES
+        .iter()
+        .map(|id| builtin_info(id))
+        .chain(custom_themes(&app_handle).into_iter().map(|(info, _)| info))
+        .collect()
+}
+
+// Get a theme's colors and CSS so the UI can apply it
+#[tauri::command]
+pub fn get_theme<R: Runtime>(app_handle: AppHandle<R>, id: String) -> Result<ThemeContent, String> {
+    if BUILTIN_THEMES.contains(&id.as_str()) {
+        return Ok(ThemeContent {
+            info: builtin_info(&id),
+            colors: HashMap::new(),
+            css: None,
+        });
+    }
+
+    let (info, definition) = custom_themes(&app_handle)
+        .into_iter()
+        .find(|(info, _)| info.id == id)
+        .ok_or_else(|| format!("Theme not found: {}", id))?;
+
+    // Also check installed CSS: it may have been copied in by hand or by an
+    // import rather than through install_theme
+    let css = if info.has_css {
+        let css = read_theme_file(&get_themes_dir(&app_handle).join(format!("{}.css", id)))?;
+        validate_css(&css).map_err(|e| format!("Theme {} is not allowed: {}", id, e))?;
+        Some(css)
+    } else {
+        None
+    };
+
+    Ok(ThemeContent {
+        info,
+        colors: definition.colors,
+        css,
+    })
+}
+
+// Install a theme from a .json definition (and its .css next to it, if any)
+#[tauri::command]
+pub fn install_theme<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+) -> Result<ThemeInfo, String> {
+    let source = PathBuf::from(&path);
+    if source.extension().is_none_or(|ext| ext != "json") {
+        return Err("Themes are installed from a .json theme definition".to_string());
+    }
+
+    let id = theme_id_from_path(&source)?;
+    let definition = load_definition(&source)?;
+
+    let source_css = source.with_extension("css");
+    let css = if source_css.exists() {
+        let css = read_theme_file(&source_css)?;
+        validate_css(&css)?;
+        Some(css)
+    } else {
+        None
+    };
+
+    let themes_dir = get_themes_dir(&app_handle);
+    fs::create_dir_all(&themes_dir)
+        .map_err(|e| format!("Failed to create themes directory: {}", e))?;
+
+    let json_str = serde_json::to_string_pretty(&definition)
+        .map_err(|e| format!("Failed to serialize theme: {}", e))?;
+    crate::storage_service::write_atomic(themes_dir.join(format!("{}.json", id)), json_str)
+        .map_err(|e| format!("Failed to install theme: {}", e))?;
+
+    let css_path = themes_dir.join(format!("{}.css", id));
+    match &css {
+        Some(css) => crate::storage_service::write_atomic(&css_path, css)
+            .map_err(|e| format!("Failed to install theme CSS: {}", e))?,
+        // Reinstalling without CSS must not keep a stale stylesheet
+        None if css_path.exists() => fs::remove_file(&css_path)
+            .map_err(|e| format!("Failed to remove old theme CSS: {}", e))?,
+        None => {}
+    }
+
+    info!("Installed theme {} ({})", definition.name, id);
+
+    Ok(ThemeInfo {
+        id,
+        name: definition.name,
+        base: definition.base,
+        builtin: false,
+        has_css: css.is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_css_is_allowed() {
+        let css = ".editor { color: var(--text); background: #fff; }\n/* a comment */\n";
+        assert!(validate_css(css).is_ok());
+    }
+
+    #[test]
+    fn data_urls_are_allowed() {
+        assert!(validate_css("body { background: url(data:image/png;base64,AAAA); }").is_ok());
+        assert!(validate_css("body { background: url( \"data:image/svg+xml,<svg/>\" ); }").is_ok());
+        assert!(
+            validate_css("body { background: image-set('data:image/png;base64,AA' 1x); }").is_ok()
+        );
+    }
+
+    #[test]
+    fn quoted_urls_are_rejected() {
+        assert!(validate_css("body { background: url(\"https://example.com/a.png\"); }").is_err());
+        assert!(validate_css("body { background: url('http://example.com/a.png'); }").is_err());
+    }
+
+    #[test]
+    fn urls_after_whitespace_are_rejected() {
+        assert!(validate_css("body { background: url( http://example.com/a.png ); }").is_err());
+        assert!(
+            validate_css("body { background: url(\n  'https://example.com/a.png'); }").is_err()
+        );
+    }
+
+    #[test]
+    fn protocol_relative_and_local_urls_are_rejected() {
+        assert!(validate_css("body { background: url(//example.com/a.png); }").is_err());
+        assert!(validate_css("body { background: url(../secret.png); }").is_err());
+    }
+
+    #[test]
+    fn urls_in_image_set_are_rejected() {
+        let css = "body { background: image-set(\"https://example.com/a.png\" 1x); }";
+        assert!(validate_css(css).is_err());
+        let css = "body { background: -webkit-image-set(url(//example.com/a.png) 1x); }";
+        assert!(validate_css(css).is_err());
+    }
+
+    #[test]
+    fn import_is_rejected_in_any_form() {
+        assert!(validate_css("@import 'theme.css';").is_err());
+        assert!(validate_css("@IMPORT url(data:text/css,a);").is_err());
+        assert!(validate_css("@\\69mport 'https://example.com/a.css';").is_err());
+        assert!(validate_css("@\\000069 mport 'a.css';").is_err());
+        assert!(validate_css("@im/**/port 'a.css';").is_err());
+    }
+
+    #[test]
+    fn escaped_url_functions_are_rejected() {
+        assert!(validate_css("body { background: \\75rl(https://example.com/a.png); }").is_err());
+        assert!(validate_css("body { background: u\\72l(//example.com/a.png); }").is_err());
+    }
+
+    #[test]
+    fn closing_the_style_tag_is_rejected() {
+        assert!(validate_css("</STYLE><script>alert(1)</script>").is_err());
+    }
+
+    #[test]
+    fn colors_cannot_load_urls() {
+        assert!(validate_color("background", "#202020").is_ok());
+        assert!(validate_color("background", "url(//example.com/a.png)").is_err());
+    }
+}
@@ -0,0 +1,210 @@
+// user_files_service.rs - Check the files users may edit by hand
+// (settings.json, tabs.json, themes) against what Jot expects of them
+//
+// Loaders fall back to defaults when one of these is broken; this reports
+// why, with the line and column where serde_json can tell.
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Runtime};
+
+// Settings read by the backend and the JSON type each must have
+const SETTINGS_SCHEMA: &[(&str, JsonType)] = &[
+    ("theme", JsonType::String),
+    ("fontSize", JsonType::String),
+    ("activeTab", JsonType::Integer),
+    ("custom_storage_path", JsonType::String),
+    ("using_custom_storage", JsonType::Boolean),
+    ("tab_storage_paths", JsonType::Object),
+    ("encrypt_notes", JsonType::Boolean),
+    ("lt_endpoint", JsonType::String),
+    ("lt_username", JsonType::String),
+    ("daily_note_tab", JsonType::Integer),
+    ("daily_note_date", JsonType::String),
+    ("tray_mode", JsonType::String),
+    ("global_shortcut", JsonType::String),
+    ("app_language", JsonType::String),
+    ("low_space_warning_mb", JsonType::Integer),
+    ("maintenance_backup_keep_count", JsonType::Integer),
+    ("maintenance_log_retention_days", JsonType::Integer),
+    ("autosave_delay_ms", JsonType::Integer),
+    ("autosave_flush_on_blur", JsonType::Boolean),
+    ("autosave_flush_interval_secs", JsonType::Integer),
+    ("autosave_flush_before_backup", JsonType::Boolean),
+    ("code_execution_enabled", JsonType::Boolean),
+    ("pandoc_path", JsonType::String),
+    ("compress_history", JsonType::Boolean),
+    ("history_retention_days", JsonType::Integer),
+    ("smtp", JsonType::Object),
+    ("email_digest", JsonType::Object),
+    ("email_digest_last_sent", JsonType::String),
+    ("proxy", JsonType::Object),
+    ("startup_tab", JsonType::String),
+    ("show_tab", JsonType::String),
+    ("startup_fixed_tab", JsonType::Integer),
+    ("startup_weekday_tabs", JsonType::Array),
+    ("startup_timezone", JsonType::String),
+    ("publish_site", JsonType::Object),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JsonType {
+    String,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    fn matches(self, value: &serde_json::Value) -> bool {
+        match self {
+            JsonType::String => value.is_string(),
+            JsonType::Integer => value.is_u64(),
+            JsonType::Boolean => value.is_boolean(),
+            JsonType::Array => value.is_array(),
+            JsonType::Object => value.is_object(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            JsonType::String => "a string",
+            JsonType::Integer => "a whole number",
+            JsonType::Boolean => "true or false",
+            JsonType::Array => "a list",
+            JsonType::Object => "an object",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFileProblem {
+    pub path: String,
+    // 1-based position of a syntax or type error, when known
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+fn problem(path: &Path, message: String) -> UserFileProblem {
+    UserFileProblem {
+        path: path.to_string_lossy().to_string(),
+        line: None,
+        column: None,
+        message,
+    }
+}
+
+fn parse_problem(path: &Path, e: &serde_json::Error) -> UserFileProblem {
+    UserFileProblem {
+        line: Some(e.line()),
+        column: Some(e.column()),
+        ..problem(path, e.to_string())
+    }
+}
+
+// Parse a JSON file into `T`; a missing file is fine
+fn check_json_file<T: DeserializeOwned>(
+    path: &Path,
+    problems: &mut Vec<UserFileProblem>,
+) -> Option<T> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            problems.push(problem(path, format!("Failed to read file: {}", e)));
+            return None;
+        }
+    };
+
+    serde_json::from_str(&content)
+        .map_err(|e| problems.push(parse_problem(path, &e)))
+        .ok()
+}
+
+fn check_settings(path: &Path, problems: &mut Vec<UserFileProblem>) {
+    let Some(settings) = check_json_file::<serde_json::Value>(path, problems) else {
+        return;
+    };
+    let Some(settings) = settings.as_object() else {
+        problems.push(problem(path, "Settings must be a JSON object".to_string()));
+        return;
+    };
+
+    for (key, expected) in SETTINGS_SCHEMA {
+        match settings.get(*key) {
+            Some(value) if !value.is_null() && !expected.matches(value) => {
+                problems.push(problem(
+                    path,
+                    format!("\"{}\" must be {}, found {}", key, expected.name(), value),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_themes<R: Runtime>(app_handle: &AppHandle<R>, problems: &mut Vec<UserFileProblem>) {
+    let Ok(entries) = fs::read_dir(crate::theme_service::get_themes_dir(app_handle)) else {
+        return;
+    };
+
+    for path in entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+    {
+        if let Some(definition) =
+            check_json_file::<crate::theme_service::ThemeDefinition>(&path, problems)
+        {
+            if let Err(e) = crate::theme_service::validate_definition(&definition) {
+                problems.push(problem(&path, e));
+            }
+        }
+    }
+}
+
+pub fn check_user_files<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<UserFileProblem> {
+    let app_dir = crate::data_dir::get_app_data_dir(app_handle);
+    let mut problems = Vec::new();
+
+    check_settings(&app_dir.join("settings.json"), &mut problems);
+    check_json_file::<crate::tabs_service::TabLayout>(&app_dir.join("tabs.json"), &mut problems);
+    check_themes(app_handle, &mut problems);
+
+    problems
+}
+
+// Log problems with the user's files at startup and tell the frontend
+pub fn report_user_files<R: Runtime>(app_handle: &AppHandle<R>) {
+    let problems = check_user_files(app_handle);
+    if problems.is_empty() {
+        return;
+    }
+
+    for problem in &problems {
+        warn!(
+            "{}{}: {}",
+            problem.path,
+            problem
+                .line
+                .map(|line| format!(":{}:{}", line, problem.column.unwrap_or(0)))
+                .unwrap_or_default(),
+            problem.message
+        );
+    }
+    if let Err(e) = app_handle.emit("user-files-invalid", &problems) {
+        warn!("Failed to emit user-files-invalid event: {}", e);
+    }
+}
+
+// Check settings.json, tabs.json and the user themes; returns what's wrong
+// with them, empty if nothing is
+#[tauri::command]
+pub fn validate_user_files<R: Runtime>(app_handle: AppHandle<R>) -> Vec<UserFileProblem> {
+    check_user_files(&app_handle)
+}
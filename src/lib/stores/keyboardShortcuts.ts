@@ -5,6 +5,7 @@ import { myredo, myundo } from "./history";
 import { notes, updateNote } from "./notes";
 import { register } from "@tauri-apps/plugin-global-shortcut";
 import { Window } from "@tauri-apps/api/window";
+import { invoke } from "@tauri-apps/api/core";
 import { logger } from "$lib/utils/logger";
 import { saveNote } from "$lib/utils/persistence";
 
@@ -14,7 +15,8 @@ async function toggleWindow() {
     let visible = await Window.getCurrent().isVisible();
     if (visible) {
       console.log("Hiding window");
-      await Window.getCurrent().hide();
+      // Minimizes instead when there's no tray icon to bring it back
+      await invoke("close_window");
     } else {
       console.log("Showing window");
       await Window.getCurrent().show();
@@ -67,11 +67,14 @@ export async function saveLanguageToolConfig(
   endpoint?: string
 ): Promise<boolean> {
   try {
-    await invoke("save_language_tool_config", {
+    const warning = await invoke<string | null>("save_language_tool_config", {
       apiKey,
       username,
       endpoint,
     });
+    if (warning) {
+      console.warn("LanguageTool server could not be checked:", warning);
+    }
 
     languageConfig.update((config) => ({
       ...config,
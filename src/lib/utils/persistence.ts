@@ -5,20 +5,12 @@ import { logger } from "$lib/utils/logger";
 // Save a note to storage
 export async function saveNote(tabIndex: number, content: string) {
   try {
-    // Use local storage for quick saving (to reduce disk writes)
+    // Use local storage for quick saving
     localStorage.setItem(`jot-note-${tabIndex}`, content);
 
-    // Debounced saving to filesystem via Tauri
-    if (window.savingTimeout) {
-      clearTimeout(window.savingTimeout);
-    }
-
-    window.savingTimeout = setTimeout(async () => {
-      await invoke("save_note", {
-        tabIndex,
-        content,
-      });
-    }, 1000); // 1 second debounce
+    // The backend buffers the content and writes it once typing pauses
+    // (and on exit), so every keystroke doesn't hit the disk
+    await invoke("save_note_debounced", { tabIndex, content });
 
     return true;
   } catch (error) {
@@ -54,11 +46,24 @@ export async function loadNotes() {
     setNotes(notesData);
 
     // Then load from the filesystem (which may update the notes again)
-    const storedNotes = await invoke<Record<number, string>>("load_notes");
+    // Returned in display order (pinned first); tabs are still keyed by index
+    const storedNotes = await invoke<
+      {
+        tabIndex: number;
+        content: string;
+        pinned: boolean;
+        editorState: {
+          cursor: number;
+          anchor: number | null;
+          scrollTop: number;
+          scrollLeft: number;
+        } | null;
+      }[]
+    >("load_notes");
 
     // Merge with what we already have (filesystem takes precedence)
-    for (const [tabIndex, content] of Object.entries(storedNotes)) {
-      notesData[Number(tabIndex)] = content;
+    for (const { tabIndex, content } of storedNotes) {
+      notesData[tabIndex] = content;
       // Also update localStorage
       localStorage.setItem(`jot-note-${tabIndex}`, content);
     }
@@ -100,13 +105,3 @@ export async function setStoragePath(path: string | null): Promise<void> {
     throw error;
   }
 }
-
-// For TypeScript
-declare global {
-  interface Window {
-    savingTimeout: number | null;
-  }
-}
-
-// Initialize timeout property
-window.savingTimeout = null;
@@ -18,6 +18,7 @@ export namespace SettingsCommands {
 
 export namespace NotesCommands {
   export const LOAD_NOTES: string = "load_notes";
+  export const RELOAD_NOTE: string = "reload_note";
   export const SAVE_NOTES: string = "save_notes";
 }
 